@@ -0,0 +1,45 @@
+//! Measures time of building level-10 2D universe with default constructor and with capacity
+//! hinted one.
+//!
+//! Run with:
+//! ```bash
+//! cargo bench --bench construction
+//! ```
+
+extern crate quantized_density_fields;
+
+use quantized_density_fields::{ID, QDF};
+use std::time::Instant;
+
+const DIMENSIONS: usize = 2;
+const LEVELS: usize = 10;
+const RUNS: u32 = 10;
+
+fn measure<F>(name: &str, build: F)
+where
+    F: Fn() -> (QDF<f64>, ID),
+{
+    let mut spaces = 0;
+    let timer = Instant::now();
+    for _ in 0..RUNS {
+        let (mut qdf, root) = build();
+        spaces = qdf.subdivide_to_depth(root, LEVELS).unwrap().len();
+    }
+    let elapsed = timer.elapsed();
+    println!(
+        "{}: {:?} per universe of {} spaces ({} runs in {:?})",
+        name,
+        elapsed / RUNS,
+        spaces,
+        RUNS,
+        elapsed
+    );
+}
+
+fn main() {
+    let capacity = (DIMENSIONS + 1).pow(LEVELS as u32);
+    measure("default constructor", || QDF::new(DIMENSIONS, 1.0));
+    measure("capacity hinted constructor", || {
+        QDF::with_capacity(DIMENSIONS, 1.0, capacity)
+    });
+}
@@ -0,0 +1,71 @@
+//! Measures memory footprint of deeply subdivided QDF.
+//!
+//! Compare both identifier backends by running:
+//! ```bash
+//! cargo bench --bench id_footprint
+//! cargo bench --bench id_footprint --features compact-id
+//! ```
+
+extern crate quantized_density_fields;
+
+use quantized_density_fields::{ID, QDF};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::mem::size_of;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size > layout.size() {
+            ALLOCATED.fetch_add(new_size - layout.size(), Ordering::Relaxed);
+        } else {
+            ALLOCATED.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const DIMENSIONS: usize = 3;
+const LEVELS: usize = 8;
+
+fn main() {
+    let backend = if cfg!(feature = "compact-id") {
+        "u64"
+    } else {
+        "uuid"
+    };
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let timer = Instant::now();
+    let (qdf, _) = QDF::with_levels(DIMENSIONS, 0i32, LEVELS);
+    let elapsed = timer.elapsed();
+    let bytes = ALLOCATED.load(Ordering::Relaxed) - before;
+    let spaces = qdf.spaces().count();
+    let edges = qdf.graph().edge_count();
+    println!("ID backend: {} ({} bytes per ID)", backend, size_of::<ID>());
+    println!(
+        "{}D QDF subdivided {} levels: {} spaces, {} edges, built in {:?}",
+        DIMENSIONS, LEVELS, spaces, edges, elapsed
+    );
+    println!(
+        "heap footprint: {} bytes ({:.1} bytes per space)",
+        bytes,
+        bytes as f64 / spaces as f64
+    );
+}
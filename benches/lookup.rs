@@ -0,0 +1,62 @@
+//! Measures time of looking spaces up by their ids over level-8 universe, which is what hasher of
+//! space maps is used for. Compare default hasher with `fast-hash` feature.
+//!
+//! Run with:
+//! ```bash
+//! cargo bench --bench lookup
+//! cargo bench --bench lookup --features fast-hash
+//! ```
+
+extern crate quantized_density_fields;
+
+use quantized_density_fields::QDF;
+use std::time::Instant;
+
+const DIMENSIONS: usize = 2;
+const LEVELS: usize = 8;
+const STEPS: u32 = 100;
+
+fn measure<F>(name: &str, mut step: F)
+where
+    F: FnMut(),
+{
+    // warm up.
+    step();
+    let timer = Instant::now();
+    for _ in 0..STEPS {
+        step();
+    }
+    let elapsed = timer.elapsed();
+    println!(
+        "{}: {:?} per step ({} steps in {:?})",
+        name,
+        elapsed / STEPS,
+        STEPS,
+        elapsed
+    );
+}
+
+fn main() {
+    let (mut qdf, spaces) = QDF::with_levels(DIMENSIONS, 1.0, LEVELS);
+    println!(
+        "{}D QDF subdivided {} levels: {} spaces",
+        DIMENSIONS,
+        LEVELS,
+        spaces.len()
+    );
+    measure("state lookup of every space", || {
+        let total = spaces.iter().map(|id| *qdf.space(*id).state()).sum::<f64>();
+        assert!(total > 0.0);
+    });
+    measure("state update of every space", || {
+        for id in &spaces {
+            let state = *qdf.space(*id).state();
+            qdf.set_space_state(*id, state * 0.5 + 0.5).unwrap();
+        }
+    });
+    let mut id = spaces[0];
+    measure("topology change", || {
+        let (_, subs, _) = qdf.increase_space_density(id).unwrap();
+        id = qdf.decrease_space_density(subs[0]).unwrap().unwrap().id;
+    });
+}
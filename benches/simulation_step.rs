@@ -0,0 +1,68 @@
+//! Measures time of `QDF::simulation_step()` and `QDF::simulation_step_parallel()` over level-8
+//! universe, with static topology and with topology changed between every step.
+//!
+//! Run with:
+//! ```bash
+//! cargo bench --bench simulation_step
+//! ```
+
+extern crate quantized_density_fields;
+
+use quantized_density_fields::{Simulate, QDF};
+use std::time::Instant;
+
+const DIMENSIONS: usize = 2;
+const LEVELS: usize = 8;
+const STEPS: u32 = 100;
+
+struct Average;
+
+impl Simulate<f64> for Average {
+    fn simulate(state: &f64, neighbor_states: &[&f64]) -> f64 {
+        let sum = neighbor_states.iter().cloned().sum::<f64>();
+        (state + sum) / (neighbor_states.len() + 1) as f64
+    }
+}
+
+fn measure<F>(name: &str, mut step: F)
+where
+    F: FnMut(),
+{
+    // warm up.
+    step();
+    let timer = Instant::now();
+    for _ in 0..STEPS {
+        step();
+    }
+    let elapsed = timer.elapsed();
+    println!(
+        "{}: {:?} per step ({} steps in {:?})",
+        name,
+        elapsed / STEPS,
+        STEPS,
+        elapsed
+    );
+}
+
+fn main() {
+    let (mut qdf, spaces) = QDF::with_levels(DIMENSIONS, 1.0, LEVELS);
+    println!(
+        "{}D QDF subdivided {} levels: {} spaces",
+        DIMENSIONS,
+        LEVELS,
+        spaces.len()
+    );
+    let mut buf = vec![];
+    measure("simulation step", || {
+        qdf.simulation_step_reusing::<Average>(&mut buf)
+    });
+    measure("parallel simulation step", || {
+        qdf.simulation_step_parallel::<Average>()
+    });
+    let mut id = spaces[0];
+    measure("simulation step with topology change", || {
+        let (_, subs, _) = qdf.increase_space_density(id).unwrap();
+        id = qdf.decrease_space_density(subs[0]).unwrap().unwrap().id;
+        qdf.simulation_step_reusing::<Average>(&mut buf);
+    });
+}
@@ -0,0 +1,78 @@
+use error::*;
+use id::ID;
+
+/// Helper that reads length-prefixed binary data.
+pub(crate) struct BytesReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BytesReader<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.position)
+    }
+
+    pub fn read(&mut self, size: usize) -> Result<&'a [u8]> {
+        if size > self.data.len() - self.position {
+            return Err(QDFError::DecodeError(format!(
+                "unexpected end of data at byte {} (needs {} more bytes)",
+                self.position, size
+            )));
+        }
+        let result = &self.data[self.position..self.position + size];
+        self.position += size;
+        Ok(result)
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.read(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    pub fn read_usize(&mut self) -> Result<usize> {
+        let value = self.read_u64()?;
+        if value > usize::MAX as u64 {
+            Err(QDFError::DecodeError(format!(
+                "value {} does not fit into usize",
+                value
+            )))
+        } else {
+            Ok(value as usize)
+        }
+    }
+
+    pub fn read_id(&mut self) -> Result<ID> {
+        let bytes = self.read(ID::BYTES)?;
+        match ID::from_bytes(bytes) {
+            Some(id) => Ok(id),
+            None => Err(QDFError::DecodeError("invalid ID bytes".to_owned())),
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn write_u64(buf: &mut Vec<u8>, value: u64) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+#[inline]
+pub(crate) fn write_usize(buf: &mut Vec<u8>, value: usize) {
+    write_u64(buf, value as u64);
+}
+
+#[inline]
+pub(crate) fn write_id(buf: &mut Vec<u8>, id: ID) {
+    id.write_bytes(buf);
+}
@@ -1,4 +1,6 @@
 use id::ID;
+use std::error::Error;
+use std::fmt;
 use std::result::Result as StdResult;
 
 /// Defines Quantized Density Fields errors.
@@ -6,11 +8,96 @@ use std::result::Result as StdResult;
 pub enum QDFError {
     /// Tells that specified space does not exists in container.
     SpaceDoesNotExists(ID),
+    /// Tells that specified space already exists in container.
+    SpaceAlreadyExists(ID),
+    /// Tells that specified spaces are not connected.
+    EdgeDoesNotExists(ID, ID),
     /// Tells that specified level does not exists in container.
     LevelDoesNotExists(ID),
+    /// Tells that specified level is not platonic (it has sublevels).
+    LevelIsNotPlatonic(ID),
+    /// Tells that requested depth exceeds available depth (requested, available).
+    DepthOutOfRange(usize, usize),
+    /// Tells that requested index exceeds number of available items (requested, available).
+    IndexOutOfRange(usize, usize),
     /// Tells that specified field does not exists in container.
     FieldDoesNotExists(ID),
+    /// Tells that specified field already exists in container.
+    FieldAlreadyExists(ID),
+    /// Tells that containers have different dimensions number (expected, actual).
+    DimensionMismatch(usize, usize),
+    /// Tells that binary data cannot be decoded.
+    DecodeError(String),
+    /// Tells that edge connects space with itself or with space that does not exists.
+    InvalidEdge(ID, ID),
+    /// Tells that space has unexpected number of neighbors (space, neighbors count).
+    UnexpectedDegree(ID, usize),
+    /// Tells that number of given items does not match expected number (expected, actual).
+    CountMismatch(usize, usize),
+    /// Tells that space cannot be split into given number of subspaces.
+    InvalidSubdivisionCount(usize),
+    /// Tells that space does not lay at expected depth below the same root space as other spaces
+    /// (QDF is not uniformly subdivided).
+    NotUniform(ID),
 }
 
+impl fmt::Display for QDFError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QDFError::SpaceDoesNotExists(id) => write!(f, "space {} does not exist", id),
+            QDFError::SpaceAlreadyExists(id) => write!(f, "space {} already exists", id),
+            QDFError::EdgeDoesNotExists(a, b) => {
+                write!(f, "spaces {} and {} are not connected", a, b)
+            }
+            QDFError::LevelDoesNotExists(id) => write!(f, "level {} does not exist", id),
+            QDFError::LevelIsNotPlatonic(id) => {
+                write!(f, "level {} is not platonic (it has sublevels)", id)
+            }
+            QDFError::DepthOutOfRange(requested, available) => write!(
+                f,
+                "requested depth {} exceeds available depth {}",
+                requested, available
+            ),
+            QDFError::IndexOutOfRange(requested, available) => write!(
+                f,
+                "requested index {} is out of range of {} items",
+                requested, available
+            ),
+            QDFError::FieldDoesNotExists(id) => write!(f, "field {} does not exist", id),
+            QDFError::FieldAlreadyExists(id) => write!(f, "field {} already exists", id),
+            QDFError::DimensionMismatch(expected, actual) => write!(
+                f,
+                "dimensions mismatch: expected {}, got {}",
+                expected, actual
+            ),
+            QDFError::DecodeError(reason) => write!(f, "cannot decode data: {}", reason),
+            QDFError::InvalidEdge(a, b) => write!(
+                f,
+                "edge between {} and {} is a loop or points to unknown space",
+                a, b
+            ),
+            QDFError::UnexpectedDegree(id, degree) => {
+                write!(f, "space {} has unexpected number of neighbors: {}", id, degree)
+            }
+            QDFError::CountMismatch(expected, actual) => write!(
+                f,
+                "count mismatch: expected {} items, got {}",
+                expected, actual
+            ),
+            QDFError::InvalidSubdivisionCount(count) => {
+                write!(f, "space cannot be split into {} subspaces", count)
+            }
+            QDFError::NotUniform(id) => write!(
+                f,
+                "space {} breaks uniform subdivision: it does not lay at the same depth below the \
+                 same root space as other spaces",
+                id
+            ),
+        }
+    }
+}
+
+impl Error for QDFError {}
+
 /// Alias for standard result with `QDFError` error type.
 pub type Result<T> = StdResult<T, QDFError>;
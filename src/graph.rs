@@ -0,0 +1,75 @@
+use petgraph::graphmap::{NodeTrait, UnGraphMap};
+use std::collections::{HashMap, HashSet};
+
+/// Gets list of graph edges ordered in a way that when added (after all nodes) to empty graph,
+/// gives the same neighbors order for every node as source graph has (when it is possible).
+///
+/// # Arguments
+/// * `graph` - source graph.
+pub(crate) fn ordered_edges<N, E>(graph: &UnGraphMap<N, E>) -> Vec<(N, N)>
+where
+    N: NodeTrait,
+{
+    let nodes = graph.nodes().collect::<Vec<N>>();
+    let neighbors = nodes
+        .iter()
+        .map(|n| (*n, graph.neighbors(*n).collect::<Vec<N>>()))
+        .collect::<HashMap<N, Vec<N>>>();
+    let mut cursors = nodes.iter().map(|n| (*n, 0)).collect::<HashMap<N, usize>>();
+    let mut added = HashSet::new();
+    let mut result = Vec::with_capacity(graph.edge_count());
+    let key = |a: N, b: N| if a < b { (a, b) } else { (b, a) };
+    let head = |n: N, cursors: &mut HashMap<N, usize>, added: &HashSet<(N, N)>| -> Option<N> {
+        let list = &neighbors[&n];
+        let cursor = cursors.get_mut(&n).unwrap();
+        while *cursor < list.len() {
+            if !added.contains(&key(n, list[*cursor])) {
+                return Some(list[*cursor]);
+            }
+            *cursor += 1;
+        }
+        None
+    };
+    while result.len() < graph.edge_count() {
+        let mut progress = false;
+        for a in &nodes {
+            while let Some(b) = head(*a, &mut cursors, &added) {
+                if head(b, &mut cursors, &added) != Some(*a) {
+                    break;
+                }
+                added.insert(key(*a, b));
+                result.push((*a, b));
+                progress = true;
+            }
+        }
+        if !progress {
+            // neighbors order cannot be fully reproduced so we take first pending edge.
+            if let Some((a, b)) = nodes
+                .iter()
+                .filter_map(|a| head(*a, &mut cursors, &added).map(|b| (*a, b)))
+                .next()
+            {
+                added.insert(key(a, b));
+                result.push((a, b));
+            } else {
+                break;
+            }
+        }
+    }
+    result
+}
+
+/// Escapes text so it can be used inside quoted Graphviz DOT string.
+pub(crate) fn escape_dot(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => {}
+            c => result.push(c),
+        }
+    }
+    result
+}
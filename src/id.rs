@@ -1,10 +1,64 @@
+#[cfg(feature = "fast-hash")]
+use fxhash::FxBuildHasher;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(all(feature = "serde", feature = "compact-id"))]
+use serde::{Deserializer, Serializer};
+#[cfg(not(feature = "fast-hash"))]
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::error::Error;
 use std::fmt;
-use uuid::Uuid;
+use std::str::FromStr;
+#[cfg(feature = "compact-id")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(feature = "compact-id"))]
+use uuid::{Builder, Uuid, Variant, Version};
 
 /// Universal Identifier (uuidv4).
+///
+/// With `compact-id` feature enabled it is backed by `u64` allocated from process-wide counter
+/// instead, which makes it 8 bytes instead of 16 and keeps big space graphs compact.
+///
+/// `to_string()` gives bare UUID, while `Debug` gives `ID(<uuid>)` form (which `to_string()`
+/// gave before `Display` was implemented).
 #[derive(PartialEq, Eq, Hash, Copy, Clone, PartialOrd, Ord)]
+#[cfg_attr(
+    all(feature = "serde", not(feature = "compact-id")),
+    derive(Serialize, Deserialize)
+)]
+#[cfg(not(feature = "compact-id"))]
 pub struct ID(Uuid);
 
+/// Universal Identifier (`u64` allocated from process-wide counter).
+///
+/// Identifiers produced by `IdGenerator` have highest bit set, so they never collide with the
+/// ones allocated by `ID::new()`.
+///
+/// `to_string()` gives bare number, while `Debug` gives `ID(<number>)` form (which `to_string()`
+/// gave before `Display` was implemented).
+#[derive(PartialEq, Eq, Hash, Copy, Clone, PartialOrd, Ord)]
+#[cfg(feature = "compact-id")]
+pub struct ID(u64);
+
+#[cfg(feature = "compact-id")]
+const GENERATED_ID_BIT: u64 = 1 << 63;
+
+// Next identifier handed out by `ID::new()`.
+#[cfg(feature = "compact-id")]
+static ID_SPACE: AtomicU64 = AtomicU64::new(0);
+
+/// Hasher of maps keyed by `ID` used to look spaces up.
+/// With `fast-hash` feature it is `FxHasher` (identifiers are already well-distributed, so
+/// DoS-resistant SipHash is not needed), otherwise it is default `RandomState`.
+#[cfg(feature = "fast-hash")]
+pub(crate) type IdBuildHasher = FxBuildHasher;
+#[cfg(not(feature = "fast-hash"))]
+pub(crate) type IdBuildHasher = RandomState;
+
+/// Map keyed by `ID` that uses `IdBuildHasher`.
+pub(crate) type IdMap<V> = HashMap<ID, V, IdBuildHasher>;
+
 impl ID {
     /// Creates new identifier.
     #[inline]
@@ -13,12 +67,100 @@ impl ID {
     }
 
     /// Gets underlying UUID object.
+    #[cfg(not(feature = "compact-id"))]
     #[inline]
     pub fn uuid(&self) -> Uuid {
         self.0
     }
+
+    /// Gets underlying number.
+    #[cfg(feature = "compact-id")]
+    #[inline]
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+
+    /// Number of bytes used by binary form of identifier.
+    #[cfg(not(feature = "compact-id"))]
+    pub(crate) const BYTES: usize = 16;
+    #[cfg(feature = "compact-id")]
+    pub(crate) const BYTES: usize = 8;
+
+    #[cfg(not(feature = "compact-id"))]
+    #[inline]
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Uuid::from_slice(bytes).ok().map(ID)
+    }
+
+    #[cfg(feature = "compact-id")]
+    #[inline]
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::BYTES {
+            return None;
+        }
+        let mut value = [0; 8];
+        value.copy_from_slice(bytes);
+        Some(Self::reserved(u64::from_le_bytes(value)))
+    }
+
+    #[cfg(not(feature = "compact-id"))]
+    #[inline]
+    pub(crate) fn write_bytes(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.0.as_bytes());
+    }
+
+    #[cfg(feature = "compact-id")]
+    #[inline]
+    pub(crate) fn write_bytes(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.0.to_le_bytes());
+    }
+
+    /// Gets first 8 hex digits of identifier, handy for compact debug output.
+    /// With `compact-id` feature it gives 8 lowest hex digits of underlying number instead, since
+    /// highest ones are the same for most identifiers.
+    /// Short form is not unique, so it must not be used to look spaces up.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::ID;
+    ///
+    /// let id = ID::new();
+    /// let short = id.short();
+    /// assert_eq!(short.len(), 8);
+    /// assert!(short.chars().all(|c| c.is_ascii_hexdigit()));
+    /// ```
+    pub fn short(&self) -> String {
+        #[cfg(not(feature = "compact-id"))]
+        {
+            let bytes = self.0.as_bytes();
+            format!(
+                "{:02x}{:02x}{:02x}{:02x}",
+                bytes[0], bytes[1], bytes[2], bytes[3]
+            )
+        }
+        #[cfg(feature = "compact-id")]
+        {
+            format!("{:08x}", self.0 as u32)
+        }
+    }
+
+    /// Textual form of underlying value, used as node name in graph dumps.
+    #[inline]
+    pub(crate) fn key(&self) -> String {
+        self.0.to_string()
+    }
+
+    // makes sure that identifiers loaded from outside are never handed out again by `ID::new()`.
+    #[cfg(feature = "compact-id")]
+    fn reserved(value: u64) -> Self {
+        if value & GENERATED_ID_BIT == 0 {
+            ID_SPACE.fetch_max(value + 1, Ordering::Relaxed);
+        }
+        ID(value)
+    }
 }
 
+#[cfg(not(feature = "compact-id"))]
 impl Default for ID {
     #[inline]
     fn default() -> Self {
@@ -26,16 +168,182 @@ impl Default for ID {
     }
 }
 
+#[cfg(feature = "compact-id")]
+impl Default for ID {
+    #[inline]
+    fn default() -> Self {
+        let value = ID_SPACE.fetch_add(1, Ordering::Relaxed);
+        assert!(value & GENERATED_ID_BIT == 0, "ID space exhausted");
+        ID(value)
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "compact-id"))]
+impl Serialize for ID {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "compact-id"))]
+impl<'de> Deserialize<'de> for ID {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        u64::deserialize(deserializer).map(Self::reserved)
+    }
+}
+
 impl fmt::Debug for ID {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.to_string())
+        write!(f, "ID({})", self.0)
+    }
+}
+
+/// Prints bare underlying value (UUID, or number with `compact-id` feature), while `Debug` keeps
+/// `ID(<value>)` form.
+/// Since `to_string()` comes from `Display`, it gives bare value too (before `Display` was
+/// implemented it gave `ID(<value>)` form), so use `format!("{:?}", id)` to get the old output.
+///
+/// # Examples
+/// ```
+/// use quantized_density_fields::ID;
+///
+/// let id = ID::new();
+/// assert_eq!(format!("{:?}", id), format!("ID({})", id));
+/// assert_eq!(id.to_string(), format!("{}", id));
+/// ```
+impl fmt::Display for ID {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for ID {
+    type Err = IdParseError;
+
+    /// Parses identifier from either `ID(<value>)` form (produced by `Debug`) or bare value
+    /// (produced by `Display`: UUID, or number with `compact-id` feature).
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::ID;
+    /// use std::str::FromStr;
+    ///
+    /// let id = ID::new();
+    /// assert_eq!(ID::from_str(&format!("{:?}", id)).unwrap(), id);
+    /// assert_eq!(ID::from_str(&id.to_string()).unwrap(), id);
+    /// assert!(ID::from_str("ID(foo)").is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let value = if trimmed.starts_with("ID(") && trimmed.ends_with(')') {
+            &trimmed[3..trimmed.len() - 1]
+        } else {
+            trimmed
+        };
+        #[cfg(not(feature = "compact-id"))]
+        let parsed = Uuid::parse_str(value).map(ID);
+        #[cfg(feature = "compact-id")]
+        let parsed = value.parse::<u64>().map(Self::reserved);
+        match parsed {
+            Ok(id) => Ok(id),
+            Err(error) => Err(IdParseError {
+                input: s.to_owned(),
+                reason: error.to_string(),
+            }),
+        }
+    }
+}
+
+/// Error returned when identifier cannot be parsed from string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdParseError {
+    input: String,
+    reason: String,
+}
+
+impl IdParseError {
+    /// Gets string that failed to parse.
+    #[inline]
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Gets description of why parsing failed.
+    #[inline]
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl fmt::Display for IdParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid ID `{}`: {}", self.input, self.reason)
     }
 }
 
-impl ToString for ID {
+impl Error for IdParseError {}
+
+/// Deterministic identifiers generator.
+/// It wraps seeded pseudo-random numbers generator and produces valid uuidv4 identifiers (or
+/// numbers with highest bit set with `compact-id` feature), so the same seed always gives the same
+/// sequence of identifiers.
+///
+/// # Examples
+/// ```
+/// use quantized_density_fields::IdGenerator;
+///
+/// let mut a = IdGenerator::new(42);
+/// let mut b = IdGenerator::new(42);
+/// assert_eq!(a.generate(), b.generate());
+/// assert_ne!(a.generate(), IdGenerator::new(7).generate());
+/// ```
+#[derive(Debug, Clone)]
+pub struct IdGenerator {
+    state: u64,
+}
+
+impl IdGenerator {
+    /// Creates new generator.
+    ///
+    /// # Arguments
+    /// * `seed` - seed of pseudo-random numbers generator.
     #[inline]
-    fn to_string(&self) -> String {
-        format!("ID({})", self.0)
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Generates next identifier.
+    #[cfg(not(feature = "compact-id"))]
+    pub fn generate(&mut self) -> ID {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.next_u64().to_le_bytes());
+        bytes[8..].copy_from_slice(&self.next_u64().to_le_bytes());
+        ID(Builder::from_bytes(bytes)
+            .set_variant(Variant::RFC4122)
+            .set_version(Version::Random)
+            .build())
+    }
+
+    /// Generates next identifier.
+    #[cfg(feature = "compact-id")]
+    pub fn generate(&mut self) -> ID {
+        ID(self.next_u64() | GENERATED_ID_BIT)
+    }
+
+    // splitmix64
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
     }
 }
@@ -0,0 +1,176 @@
+use std::io::{self, BufReader, Bytes, Read, Write};
+
+/// Helper that reads JSON tokens byte by byte from any reader (buffered internally), so documents
+/// can be parsed without loading them into memory. It understands only what JSON snapshots are
+/// made of: objects, arrays, strings and unsigned integers.
+pub(crate) struct JsonReader<R>
+where
+    R: Read,
+{
+    bytes: Bytes<BufReader<R>>,
+    peeked: Option<u8>,
+    position: usize,
+}
+
+impl<R> JsonReader<R>
+where
+    R: Read,
+{
+    #[inline]
+    pub fn new(reader: R) -> Self {
+        Self {
+            bytes: BufReader::new(reader).bytes(),
+            peeked: None,
+            position: 0,
+        }
+    }
+
+    pub fn error(&self, reason: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("cannot decode JSON at byte {}: {}", self.position, reason),
+        )
+    }
+
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        if self.peeked.is_none() {
+            self.peeked = match self.bytes.next() {
+                Some(byte) => Some(byte?),
+                None => None,
+            };
+        }
+        Ok(self.peeked)
+    }
+
+    fn next(&mut self) -> io::Result<u8> {
+        match self.peek()? {
+            Some(byte) => {
+                self.peeked = None;
+                self.position += 1;
+                Ok(byte)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("unexpected end of JSON at byte {}", self.position),
+            )),
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> io::Result<()> {
+        while let Some(byte) = self.peek()? {
+            if !byte.is_ascii_whitespace() {
+                break;
+            }
+            self.next()?;
+        }
+        Ok(())
+    }
+
+    pub fn expect(&mut self, token: u8) -> io::Result<()> {
+        self.skip_whitespace()?;
+        if self.next()? == token {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", token as char)))
+        }
+    }
+
+    /// Moves to next item of array or object that was opened with `expect()`.
+    ///
+    /// # Arguments
+    /// * `close` - closing token (`]` or `}`).
+    /// * `first` - tells if no item was read yet (updated by this call).
+    ///
+    /// # Returns
+    /// `Ok` with `true` if there is next item, or `Ok` with `false` if closing token was consumed.
+    pub fn next_item(&mut self, close: u8, first: &mut bool) -> io::Result<bool> {
+        self.skip_whitespace()?;
+        if self.peek()? == Some(close) {
+            self.next()?;
+            return Ok(false);
+        }
+        if !*first {
+            self.expect(b',')?;
+        }
+        *first = false;
+        Ok(true)
+    }
+
+    pub fn read_string(&mut self) -> io::Result<String> {
+        self.expect(b'"')?;
+        let mut bytes = vec![];
+        loop {
+            match self.next()? {
+                b'"' => break,
+                b'\\' => match self.next()? {
+                    b'"' => bytes.push(b'"'),
+                    b'\\' => bytes.push(b'\\'),
+                    b'/' => bytes.push(b'/'),
+                    b'n' => bytes.push(b'\n'),
+                    b'r' => bytes.push(b'\r'),
+                    b't' => bytes.push(b'\t'),
+                    _ => return Err(self.error("unsupported escape sequence")),
+                },
+                byte => bytes.push(byte),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("string is not valid UTF-8"))
+    }
+
+    /// Reads object key along with `:` that follows it.
+    pub fn read_key(&mut self) -> io::Result<String> {
+        let key = self.read_string()?;
+        self.expect(b':')?;
+        Ok(key)
+    }
+
+    pub fn read_usize(&mut self) -> io::Result<usize> {
+        self.skip_whitespace()?;
+        let mut value: Option<usize> = None;
+        while let Some(byte) = self.peek()? {
+            if !byte.is_ascii_digit() {
+                break;
+            }
+            self.next()?;
+            let digit = (byte - b'0') as usize;
+            value = Some(
+                value
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|v| v.checked_add(digit))
+                    .ok_or_else(|| self.error("number does not fit into usize"))?,
+            );
+        }
+        value.ok_or_else(|| self.error("expected unsigned integer"))
+    }
+
+    /// Ensures that nothing but whitespaces is left after document end.
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.skip_whitespace()?;
+        if self.peek()?.is_some() {
+            Err(self.error("unexpected data after document end"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+pub(crate) fn write_hex<W>(writer: &mut W, data: &[u8]) -> io::Result<()>
+where
+    W: Write,
+{
+    for byte in data {
+        write!(writer, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 || !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| text.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+        .collect()
+}
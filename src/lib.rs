@@ -2,13 +2,23 @@
 
 // #![feature(test)]
 
+#[cfg(feature = "fast-hash")]
+extern crate fxhash;
 extern crate petgraph;
 extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(not(feature = "compact-id"))]
 extern crate uuid;
 // extern crate test;
 
+mod bytes;
 pub mod error;
+mod graph;
 pub mod id;
+mod json;
 pub mod lod;
 pub mod qdf;
 
@@ -1,8 +1,11 @@
 use id::ID;
 use qdf::state::State;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Holds information about space level.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Level<S>
 where
     S: State,
@@ -3,12 +3,24 @@ mod tests;
 
 pub use self::level::*;
 use error::*;
+use graph::escape_dot;
+#[cfg(feature = "serde")]
+use graph::ordered_edges;
 use id::*;
 use petgraph::algo::astar;
 use petgraph::graphmap::UnGraphMap;
 use qdf::*;
 use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::de::Error as DeError;
+#[cfg(feature = "serde")]
+use serde::ser::SerializeStruct;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::result::Result as StdResult;
 
 /// Object that represents space level of details.
 /// This gives you the ability to sample space area states at different zoom levels (LOD mechanism).
@@ -24,6 +36,9 @@ where
     root: ID,
     dimensions: usize,
     count: usize,
+    branching: usize,
+    // tells if platonic level states were changed without merging parent levels states.
+    stale: bool,
 }
 
 impl<S> LOD<S>
@@ -49,7 +64,46 @@ where
     /// // sampled state at level 1 equals to `4` (`16 / 4`).
     /// assert_eq!(*lod.level(lod.level(lod.root()).sublevels()[0]).state(), 4);
     /// ```
+    #[inline]
     pub fn new(dimensions: usize, count: usize, root_state: S) -> Self {
+        Self::with_branching(
+            dimensions,
+            count,
+            Self::default_branching(dimensions),
+            root_state,
+        )
+    }
+
+    /// Creates new LOD information universe where every level is subdivided into given number of
+    /// sublevels (instead of `dimensions + 2`).
+    ///
+    /// # Arguments
+    /// * `dimensions` - Number of dimensions which space contains.
+    /// * `count` - Number of levels.
+    /// * `branching` - Number of sublevels of every level.
+    /// * `root_state` - State of root level.
+    ///
+    /// # Panics
+    /// When `branching` is `0`.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// // Create binary tree of 2D space with 2 levels of details and `16` as root space.
+    /// let lod = LOD::with_branching(2, 2, 2, 16);
+    /// assert_eq!(lod.branching(), 2);
+    /// assert_eq!(lod.level(lod.root()).sublevels().len(), 2);
+    /// assert_eq!(lod.platonic_count(), 4);
+    /// assert_eq!(*lod.level(lod.level(lod.root()).sublevels()[0]).state(), 8);
+    /// ```
+    pub fn with_branching(
+        dimensions: usize,
+        count: usize,
+        branching: usize,
+        root_state: S,
+    ) -> Self {
+        assert!(branching > 0, "LOD branching must be greater than 0");
         let mut graph = UnGraphMap::new();
         let mut levels = HashMap::new();
         let mut platonic_levels = HashSet::new();
@@ -57,7 +111,7 @@ where
         let main = Level::new(root, None, 0, 0, root_state);
         levels.insert(root, main);
         graph.add_node(root);
-        Self::subdivide_level(root, &mut graph, &mut levels, dimensions + 2, count);
+        Self::subdivide_level(root, &mut graph, &mut levels, branching, count);
         Self::connect_clusters(root, &mut graph, &levels);
         Self::collect_platonic_levels(root, &levels, &mut platonic_levels);
         Self {
@@ -68,7 +122,236 @@ where
             root,
             dimensions,
             count,
+            branching,
+            stale: false,
+        }
+    }
+
+    /// Creates new LOD information universe from states of its platonic levels, or throws error
+    /// if number of given states is not `(dimensions + 2).pow(count)`. States of other levels are
+    /// merged from their sublevels once at the end, which is much faster than setting every
+    /// platonic level state with `set_level_state()`.
+    /// States are assigned to platonic levels in depth-first order of sublevels: state at index
+    /// `i` goes to platonic level reached by taking sublevel indices given by digits of `i`
+    /// written in base `dimensions + 2` (most significant digit first).
+    ///
+    /// # Arguments
+    /// * `dimensions` - Number of dimensions which space contains.
+    /// * `count` - Number of levels.
+    /// * `leaves` - States of platonic levels.
+    ///
+    /// # Returns
+    /// `Ok` with LOD or `Err` if number of states does not match number of platonic levels.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::from_leaf_states(2, 1, vec![1, 2, 3, 4]).unwrap();
+    /// assert_eq!(*lod.state(), 10);
+    /// let subs = lod.level(lod.root()).sublevels();
+    /// assert_eq!(*lod.level(subs[2]).state(), 3);
+    /// assert!(LOD::from_leaf_states(2, 1, vec![1, 2, 3]).is_err());
+    /// ```
+    pub fn from_leaf_states(dimensions: usize, count: usize, leaves: Vec<S>) -> Result<Self> {
+        let branching = Self::default_branching(dimensions);
+        let expected = branching.pow(count as u32);
+        if leaves.len() != expected {
+            return Err(QDFError::CountMismatch(expected, leaves.len()));
+        }
+        let mut lod = Self::with_branching(dimensions, count, branching, S::default());
+        let mut ids = Vec::with_capacity(expected);
+        Self::collect_leaves_in_order(lod.root, &lod.levels, &mut ids);
+        for (id, state) in ids.iter().zip(leaves) {
+            lod.levels.get_mut(id).unwrap().apply_state(state);
+        }
+        lod.recalculate_ancestors_states(&ids);
+        Ok(lod)
+    }
+
+    /// Creates new LOD information universe from uniformly subdivided QDF, or throws error if QDF
+    /// is not uniform. QDF must be made by subdividing single root space `count` times (like
+    /// `QDF::with_levels()` does), so every space has exactly `count` ancestors (see
+    /// `QDF::ancestors()`) ending at the same root space and every subdivided space has
+    /// `subdivision_count()` subspaces. Spaces become platonic levels with the same ids and
+    /// states, subdivided (removed) spaces become levels above them with states merged from their
+    /// sublevels with `State::merge()`, so LOD branching is `dimensions + 1`. Platonic levels are
+    /// connected like spaces, and levels above them are connected when any of their sublevels are
+    /// connected. Sublevels are ordered by their ids.
+    /// Lineage is not kept by `QDF::to_bytes()` and `QDF::write_json()` snapshots, so QDF restored
+    /// from them cannot be converted.
+    ///
+    /// # Arguments
+    /// * `qdf` - uniformly subdivided QDF.
+    /// * `count` - Number of levels (number of subdivisions that QDF was made with).
+    ///
+    /// # Returns
+    /// `Ok` with LOD, or `Err` with `QDFError::NotUniform` if any space does not lay at given
+    /// depth below the same root space or with `QDFError::CountMismatch` if any subdivided space
+    /// has wrong number of subspaces (or QDF has no spaces at all).
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{LOD, QDF};
+    ///
+    /// let (mut qdf, spaces) = QDF::with_levels(2, 81, 2);
+    /// qdf.set_space_state(spaces[0], 18).unwrap();
+    /// let lod = LOD::from_qdf(&qdf, 2).unwrap();
+    /// assert_eq!(lod.branching(), 3);
+    /// assert_eq!(lod.platonic_count(), 9);
+    /// assert_eq!(*lod.state(), 90);
+    /// assert_eq!(*lod.level(spaces[0]).state(), 18);
+    /// assert_eq!(*lod.sample_at_depth(spaces[0], 1).unwrap(), 36);
+    /// assert!(LOD::from_qdf(&qdf, 1).is_err());
+    /// qdf.increase_space_density(spaces[0]).unwrap();
+    /// assert!(LOD::from_qdf(&qdf, 2).is_err());
+    /// ```
+    pub fn from_qdf<E>(qdf: &QDF<S, E>, count: usize) -> Result<Self>
+    where
+        E: Clone + Default,
+    {
+        let branching = qdf.subdivision_count();
+        let mut root = None;
+        let mut parents = HashMap::new();
+        let mut sublevels = HashMap::<ID, Vec<ID>>::new();
+        let mut leaves = Vec::with_capacity(qdf.space_count());
+        for id in qdf.spaces() {
+            let ancestors = qdf.ancestors(*id);
+            let top = ancestors.last().cloned().unwrap_or(*id);
+            if ancestors.len() != count || *root.get_or_insert(top) != top {
+                return Err(QDFError::NotUniform(*id));
+            }
+            let mut child = *id;
+            for parent in ancestors {
+                // levels above were already collected from sibling space.
+                if parents.insert(child, parent).is_some() {
+                    break;
+                }
+                sublevels.entry(parent).or_default().push(child);
+                child = parent;
+            }
+            leaves.push(*id);
+        }
+        let root = match root {
+            Some(root) => root,
+            None => {
+                let expected = branching.saturating_pow(count as u32);
+                return Err(QDFError::CountMismatch(expected, 0));
+            }
+        };
+        if let Some(children) = sublevels.values().find(|c| c.len() != branching) {
+            return Err(QDFError::CountMismatch(branching, children.len()));
+        }
+        let mut graph = UnGraphMap::new();
+        let mut levels = HashMap::with_capacity(parents.len() + 1);
+        let mut pending = vec![(root, None, 0, 0)];
+        while let Some((id, parent, level, index)) = pending.pop() {
+            graph.add_node(id);
+            let state = match qdf.try_get_space(id) {
+                Some(space) => space.state().clone(),
+                None => S::default(),
+            };
+            let mut created = Level::new(id, parent, level, index, state);
+            if let Some(mut children) = sublevels.remove(&id) {
+                children.sort();
+                for (index, child) in children.iter().enumerate() {
+                    pending.push((*child, Some(id), level + 1, index));
+                }
+                created.apply_sublevels(children);
+            }
+            levels.insert(id, created);
         }
+        for (a, b) in qdf.edges() {
+            let (mut a, mut b) = (a, b);
+            while a != b && !graph.contains_edge(a, b) {
+                graph.add_edge(a, b, ());
+                match (parents.get(&a), parents.get(&b)) {
+                    (Some(pa), Some(pb)) => {
+                        a = *pa;
+                        b = *pb;
+                    }
+                    _ => break,
+                }
+            }
+        }
+        let mut lod = Self {
+            id: ID::new(),
+            graph,
+            levels,
+            platonic_levels: leaves.iter().cloned().collect(),
+            root,
+            dimensions: qdf.dimensions(),
+            count,
+            branching,
+            stale: false,
+        };
+        lod.recalculate_ancestors_states(&leaves);
+        Ok(lod)
+    }
+
+    /// Creates new lazy LOD information universe. Only root level is created up front and
+    /// sublevels are created on first access with `sublevel()`, so LOD can be nominally deep while
+    /// only visited branches exist. Until then, levels without sublevels are platonic levels (so
+    /// platonic levels may lay on different zoom levels).
+    ///
+    /// # Arguments
+    /// * `dimensions` - Number of dimensions which space contains.
+    /// * `count` - Maximal number of levels.
+    /// * `root_state` - State of root level.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let mut lod = LOD::lazy(2, 20, 1 << 20);
+    /// assert_eq!(lod.levels_count(), 20);
+    /// assert_eq!(lod.platonic_count(), 1);
+    /// let sub = lod.sublevel(lod.root(), 0).unwrap();
+    /// let sub2 = lod.sublevel(sub, 1).unwrap();
+    /// assert_eq!(lod.level(sub2).level(), 2);
+    /// assert_eq!(*lod.level(sub2).state(), 1 << 16);
+    /// assert_eq!(lod.platonic_count(), 7);
+    /// ```
+    #[inline]
+    pub fn lazy(dimensions: usize, count: usize, root_state: S) -> Self {
+        Self::lazy_with_branching(
+            dimensions,
+            count,
+            Self::default_branching(dimensions),
+            root_state,
+        )
+    }
+
+    /// Creates new lazy LOD information universe (see `lazy()`) where every level is subdivided
+    /// into given number of sublevels (instead of `dimensions + 2`).
+    ///
+    /// # Arguments
+    /// * `dimensions` - Number of dimensions which space contains.
+    /// * `count` - Maximal number of levels.
+    /// * `branching` - Number of sublevels of every level.
+    /// * `root_state` - State of root level.
+    ///
+    /// # Panics
+    /// When `branching` is `0`.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let mut lod = LOD::lazy_with_branching(3, 10, 2, 1024);
+    /// let sub = lod.sublevel(lod.root(), 1).unwrap();
+    /// assert_eq!(lod.level(lod.root()).sublevels().len(), 2);
+    /// assert_eq!(*lod.level(sub).state(), 512);
+    /// ```
+    pub fn lazy_with_branching(
+        dimensions: usize,
+        count: usize,
+        branching: usize,
+        root_state: S,
+    ) -> Self {
+        let mut lod = Self::with_branching(dimensions, 0, branching, root_state);
+        lod.count = count;
+        lod
     }
 
     /// Gets LOD id.
@@ -97,6 +380,31 @@ where
         self.dimensions
     }
 
+    /// Gets reference to underlying level graph, so it can be used with petgraph algorithms.
+    /// Graph contains every level as node, but its edges connect only neighbor levels of the same
+    /// zoom level - parent/child links are not part of it (use `Level::parent()` and
+    /// `Level::sublevels()` for these).
+    ///
+    /// # Returns
+    /// Reference to level graph.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::new(2, 1, 16);
+    /// let root = lod.root();
+    /// let subs = lod.level(root).sublevels();
+    /// assert_eq!(lod.graph().node_count(), 5);
+    /// assert_eq!(lod.graph().edge_count(), 3);
+    /// assert!(lod.graph().contains_edge(subs[0], subs[1]));
+    /// assert!(!lod.graph().contains_edge(root, subs[0]));
+    /// ```
+    #[inline]
+    pub fn graph(&self) -> &UnGraphMap<ID, ()> {
+        &self.graph
+    }
+
     /// Gets LOD zoom levels number.
     ///
     /// # Examples
@@ -111,6 +419,118 @@ where
         self.count
     }
 
+    /// Gets number of all levels (at all zoom levels), unlike `levels_count()` which gives number
+    /// of zoom levels.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::new(2, 2, 16);
+    /// assert_eq!(lod.level_count(), 21);
+    /// assert_eq!(lod.levels_count(), 2);
+    /// ```
+    #[inline]
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Gets number of sublevels of every non-platonic level.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::new(2, 1, 16);
+    /// assert_eq!(lod.branching(), 4);
+    /// ```
+    #[inline]
+    pub fn branching(&self) -> usize {
+        self.branching
+    }
+
+    /// Gets number of sublevels that every non-platonic level is subdivided into. It is the same
+    /// as `branching()` and is `dimensions + 2` for LODs that were not created with custom
+    /// branching.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::new(2, 1, 16);
+    /// assert_eq!(lod.subdivision_count(), 4);
+    /// assert_eq!(lod.level(lod.root()).sublevels().len(), lod.subdivision_count());
+    /// ```
+    #[inline]
+    pub fn subdivision_count(&self) -> usize {
+        self.branching
+    }
+
+    /// Gets iterator over all platonic levels IDs (levels with the highest resolution).
+    ///
+    /// # Returns
+    /// Iterator over platonic level ids.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::new(2, 1, 16);
+    /// let mut platonic = lod.platonic_levels().collect::<Vec<_>>();
+    /// platonic.sort();
+    /// let mut subs = lod.level(lod.root()).sublevels().to_vec();
+    /// subs.sort();
+    /// assert_eq!(platonic, subs);
+    /// ```
+    #[inline]
+    pub fn platonic_levels(&self) -> impl Iterator<Item = ID> + '_ {
+        self.platonic_levels.iter().cloned()
+    }
+
+    /// Gets number of platonic levels.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::new(2, 1, 16);
+    /// assert_eq!(lod.platonic_count(), 4);
+    /// ```
+    #[inline]
+    pub fn platonic_count(&self) -> usize {
+        self.platonic_levels.len()
+    }
+
+    /// Gets list of all levels at given zoom level. Neighbor connections exist only between levels
+    /// of the same zoom level, so together with `find_level_neighbors()` it gives complete view
+    /// of single zoom slice.
+    ///
+    /// # Arguments
+    /// * `depth` - zoom level (`0` is root level).
+    ///
+    /// # Returns
+    /// Vector of level ids (empty if depth exceeds `levels_count()`).
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::new(2, 2, 16);
+    /// assert_eq!(lod.levels_at_depth(0), vec![lod.root()]);
+    /// assert_eq!(lod.levels_at_depth(1), lod.level(lod.root()).sublevels().to_vec());
+    /// assert_eq!(lod.levels_at_depth(2).len(), 16);
+    /// assert!(lod.levels_at_depth(3).is_empty());
+    /// ```
+    pub fn levels_at_depth(&self, depth: usize) -> Vec<ID> {
+        if depth > self.count {
+            return vec![];
+        }
+        self.graph
+            .nodes()
+            .filter(|id| self.levels[id].level() == depth)
+            .collect()
+    }
+
     /// Gets LOD root level state.
     /// # Examples
     /// ```
@@ -124,6 +544,31 @@ where
         self.levels[&self.root].state()
     }
 
+    /// Gets iterator over all levels IDs along with their levels, in no particular order.
+    /// Together with `Level::level()` and `Level::parent()` it allows to rebuild whole tree in one
+    /// pass.
+    ///
+    /// # Returns
+    /// Iterator over tuples of level id and reference to `Level` data.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::new(2, 2, 16);
+    /// assert_eq!(lod.iter_levels().count(), 21);
+    /// for (id, level) in lod.iter_levels() {
+    ///     assert_eq!(id, level.id());
+    ///     if let Some(parent) = level.parent() {
+    ///         assert_eq!(lod.level(parent).level() + 1, level.level());
+    ///     }
+    /// }
+    /// ```
+    #[inline]
+    pub fn iter_levels(&self) -> impl Iterator<Item = (ID, &Level<S>)> {
+        self.levels.iter().map(|(id, level)| (*id, level))
+    }
+
     /// Tells if space level with given id exists in LOD.
     ///
     /// # Arguments
@@ -200,6 +645,183 @@ where
         &self.levels[&id]
     }
 
+    /// Gets id of sublevel at given index of given level, creating all sublevels of that level
+    /// first if it has none yet (see `lazy()`). Created sublevels get states subdivided from
+    /// their parent level state and become platonic levels instead of it. They are connected with
+    /// each other and with sublevels of neighbor levels that already have them, so connections
+    /// do not depend on order of access and fully expanded lazy LOD is connected the same way as
+    /// LOD built by `new()`.
+    ///
+    /// # Arguments
+    /// * `id` - level id.
+    /// * `index` - sublevel index (lower than `branching()`).
+    ///
+    /// # Returns
+    /// `Ok` with sublevel id, or `Err` if level does not exists, index exceeds `branching()` or
+    /// level lays at the last zoom level.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let mut lod = LOD::lazy(2, 1, 16);
+    /// let root = lod.root();
+    /// let sub = lod.sublevel(root, 2).unwrap();
+    /// assert_eq!(lod.sublevel(root, 2).unwrap(), sub);
+    /// assert_eq!(lod.find_level_neighbors(sub).unwrap().len(), 1);
+    /// assert!(lod.sublevel(root, 4).is_err());
+    /// assert!(lod.sublevel(sub, 0).is_err());
+    /// assert!(lod.validate().is_ok());
+    /// ```
+    pub fn sublevel(&mut self, id: ID, index: usize) -> Result<ID> {
+        let level = self.get_level(id)?;
+        if index >= self.branching {
+            return Err(QDFError::IndexOutOfRange(index, self.branching));
+        }
+        if level.sublevels().is_empty() {
+            if level.level() >= self.count {
+                return Err(QDFError::DepthOutOfRange(level.level() + 1, self.count));
+            }
+            self.create_sublevels(id);
+        }
+        Ok(self.levels[&id].sublevels()[index])
+    }
+
+    /// Refines given platonic level by one zoom level at runtime (like
+    /// `QDF::increase_space_density()` does): its state is subdivided into `branching()` new
+    /// platonic sublevels, which are connected with each other and with sublevels of neighbor
+    /// levels the same way as sublevels created on demand by `sublevel()` (so connections do not
+    /// depend on order of refinement), and level itself stops being platonic. Refining level that
+    /// lays at the last zoom level extends LOD height (`levels_count()`) by one, so LOD does not
+    /// have to be uniformly deep anymore.
+    ///
+    /// # Arguments
+    /// * `id` - platonic level id.
+    ///
+    /// # Returns
+    /// `Ok` with ids of new sublevels, or `Err` if level does not exists or it is not platonic.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{LOD, State};
+    ///
+    /// let mut lod = LOD::new(2, 1, 16);
+    /// let leaf = lod.platonic_levels().next().unwrap();
+    /// let sublevels = lod.refine_leaf(leaf).unwrap();
+    /// assert_eq!(sublevels.len(), lod.branching());
+    /// assert_eq!(lod.levels_count(), 2);
+    /// assert_eq!(lod.platonic_count(), 7);
+    /// let states = sublevels.iter().map(|id| *lod.level(*id).state()).collect::<Vec<_>>();
+    /// assert_eq!(State::merge(&states), *lod.level(leaf).state());
+    /// assert!(lod.refine_leaf(leaf).is_err());
+    /// assert!(lod.validate().is_ok());
+    /// ```
+    pub fn refine_leaf(&mut self, id: ID) -> Result<Vec<ID>> {
+        let depth = self.get_level(id)?.level();
+        if !self.platonic_levels.contains(&id) {
+            return Err(QDFError::LevelIsNotPlatonic(id));
+        }
+        if depth >= self.count {
+            self.count = depth + 1;
+        }
+        self.create_sublevels(id);
+        Ok(self.levels[&id].sublevels().to_vec())
+    }
+
+    /// Coarsens given level by one zoom level at runtime (like `QDF::decrease_space_density()`
+    /// does): all its sublevels are removed along with their connections and level becomes
+    /// platonic level holding merged state of removed sublevels. It is reverse of
+    /// `refine_leaf()`. LOD height (`levels_count()`) stays the same. Platonic level is left
+    /// unchanged.
+    ///
+    /// # Arguments
+    /// * `parent` - id of level which sublevels are all platonic.
+    ///
+    /// # Returns
+    /// `Ok` with id of level that became platonic, or `Err` if level does not exists or any of
+    /// its sublevels is not platonic.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let mut lod = LOD::new(2, 2, 16);
+    /// let root = lod.root();
+    /// assert!(lod.coarsen(root).is_err());
+    /// let sub = lod.level(root).sublevels()[0];
+    /// let leaves = lod.level(sub).sublevels().to_vec();
+    /// lod.set_level_state(leaves[0], 5).unwrap();
+    /// assert_eq!(lod.coarsen(sub).unwrap(), sub);
+    /// assert_eq!(*lod.level(sub).state(), 8);
+    /// assert_eq!(lod.platonic_count(), 13);
+    /// assert!(!lod.level_exists(leaves[0]));
+    /// assert!(lod.validate().is_ok());
+    /// ```
+    pub fn coarsen(&mut self, parent: ID) -> Result<ID> {
+        let sublevels = self.get_level(parent)?.sublevels().to_vec();
+        if sublevels.is_empty() {
+            return Ok(parent);
+        }
+        if let Some(id) = sublevels
+            .iter()
+            .find(|id| !self.platonic_levels.contains(id))
+        {
+            return Err(QDFError::LevelIsNotPlatonic(*id));
+        }
+        let states = sublevels
+            .iter()
+            .map(|id| self.levels[id].state().clone())
+            .collect::<Vec<S>>();
+        for id in &sublevels {
+            self.graph.remove_node(*id);
+            self.levels.remove(id);
+            self.platonic_levels.remove(id);
+        }
+        let level = self.levels.get_mut(&parent).unwrap();
+        level.apply_sublevels(vec![]);
+        level.apply_state(State::merge(&states));
+        self.platonic_levels.insert(parent);
+        Ok(parent)
+    }
+
+    /// Samples state of level that lays given number of zoom levels above platonic level (zoom
+    /// out), or throws error if level does not exists, it is not platonic or depth exceeds
+    /// LOD height.
+    ///
+    /// # Arguments
+    /// * `platonic` - platonic level id.
+    /// * `depth` - number of zoom levels to go up (`0` gives platonic level state).
+    ///
+    /// # Returns
+    /// `Ok` with reference to state of ancestor level or `Err` if it cannot be sampled.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::new(2, 2, 16);
+    /// let sub = lod.level(lod.root()).sublevels()[0];
+    /// let platonic = lod.level(sub).sublevels()[0];
+    /// assert_eq!(*lod.sample_at_depth(platonic, 0).unwrap(), 1);
+    /// assert_eq!(*lod.sample_at_depth(platonic, 1).unwrap(), 4);
+    /// assert_eq!(*lod.sample_at_depth(platonic, 2).unwrap(), 16);
+    /// assert!(lod.sample_at_depth(platonic, 3).is_err());
+    /// assert!(lod.sample_at_depth(sub, 1).is_err());
+    /// ```
+    pub fn sample_at_depth(&self, platonic: ID, depth: usize) -> Result<&S> {
+        let mut level = self.get_level(platonic)?;
+        if !self.platonic_levels.contains(&platonic) {
+            return Err(QDFError::LevelIsNotPlatonic(platonic));
+        }
+        if depth > level.level() {
+            return Err(QDFError::DepthOutOfRange(depth, level.level()));
+        }
+        for _ in 0..depth {
+            level = &self.levels[&level.parent().unwrap()];
+        }
+        Ok(level.state())
+    }
+
     /// Try to set given level state.
     ///
     /// # Arguments
@@ -220,6 +842,8 @@ where
     }
 
     /// Set given level state or throw error if level does not exists.
+    /// States of sublevels are recalculated by subdividing new state (platonic levels have no
+    /// sublevels, so this step is skipped for them) and states of parent levels are merged again.
     ///
     /// # Arguments
     /// * `id` - level id.
@@ -245,6 +869,61 @@ where
         }
     }
 
+    /// Set given platonic level state without recalculating states of its parent levels, or throw
+    /// error if level does not exists or is not platonic. Use it for batch of updates followed by
+    /// single `recalculate()` call.
+    /// Until `recalculate()` is called LOD is stale: states of non-platonic levels (including
+    /// `state()` of LOD) do not reflect deferred changes, so sampling them, `validate()` or
+    /// serialization gives outdated results. Platonic level states are always up to date.
+    ///
+    /// # Arguments
+    /// * `id` - platonic level id.
+    /// * `state` - state.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let mut lod = LOD::new(2, 2, 0);
+    /// let platonic = lod.platonic_levels().collect::<Vec<_>>();
+    /// for id in &platonic {
+    ///     lod.set_level_state_deferred(*id, 2).unwrap();
+    /// }
+    /// assert!(lod.is_stale());
+    /// assert_eq!(*lod.state(), 0);
+    /// lod.recalculate();
+    /// assert!(!lod.is_stale());
+    /// assert_eq!(*lod.state(), 32);
+    /// assert!(lod.set_level_state_deferred(lod.root(), 1).is_err());
+    /// ```
+    pub fn set_level_state_deferred(&mut self, id: ID, state: S) -> Result<()> {
+        if !self.level_exists(id) {
+            return Err(QDFError::LevelDoesNotExists(id));
+        }
+        if !self.platonic_levels.contains(&id) {
+            return Err(QDFError::LevelIsNotPlatonic(id));
+        }
+        self.levels.get_mut(&id).unwrap().apply_state(state);
+        self.stale = true;
+        Ok(())
+    }
+
+    /// Recalculates states of all non-platonic levels by merging states of their sublevels,
+    /// starting from platonic levels up to root level, so every level gets visited once.
+    /// It makes LOD consistent again after `set_level_state_deferred()` calls.
+    pub fn recalculate(&mut self) {
+        let platonic = self.platonic_levels.iter().cloned().collect::<Vec<ID>>();
+        self.recalculate_ancestors_states(&platonic);
+        self.stale = false;
+    }
+
+    /// Tells if some platonic level states were changed with `set_level_state_deferred()` and
+    /// `recalculate()` was not called since then.
+    #[inline]
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
     /// Gets list of space level neighbors IDs or throws error if level does not exists.
     ///
     /// # Arguments
@@ -267,6 +946,56 @@ where
         }
     }
 
+    /// Get list of IDs of levels reachable from given level within specified number of hops over
+    /// neighbor connections, or throws error if level does not exists. Neighbors are always on the
+    /// same depth, so all found levels lay on the same zoom level as origin level.
+    ///
+    /// # Arguments
+    /// * `id` - level id.
+    /// * `radius` - maximal number of hops.
+    ///
+    /// # Returns
+    /// `Ok` with vector of level ids (without origin level) ordered by increasing distance if
+    /// level exists, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::new(2, 2, 64);
+    /// let subs = lod.level(lod.root()).sublevels();
+    /// let subs2 = lod.level(subs[0]).sublevels();
+    /// assert_eq!(lod.level_neighbors_within(subs[1], 1).unwrap(), vec![subs[0]]);
+    /// assert_eq!(lod.level_neighbors_within(subs[1], 2).unwrap().len(), 3);
+    /// assert_eq!(lod.level_neighbors_within(subs2[0], 1).unwrap().len(), 3);
+    /// assert_eq!(lod.level_neighbors_within(subs2[0], 2).unwrap().len(), 12);
+    /// assert_eq!(lod.level_neighbors_within(subs2[0], 3).unwrap().len(), 15);
+    /// assert!(lod.level_neighbors_within(lod.root(), 3).unwrap().is_empty());
+    /// ```
+    pub fn level_neighbors_within(&self, id: ID, radius: usize) -> Result<Vec<ID>> {
+        if !self.level_exists(id) {
+            return Err(QDFError::LevelDoesNotExists(id));
+        }
+        let depth = self.levels[&id].level();
+        let mut visited = HashSet::new();
+        visited.insert(id);
+        let mut result = vec![];
+        let mut frontier = vec![id];
+        for _ in 0..radius {
+            let next = frontier
+                .iter()
+                .flat_map(|i| self.graph.neighbors(*i))
+                .filter(|i| self.levels[i].level() == depth && visited.insert(*i))
+                .collect::<Vec<ID>>();
+            if next.is_empty() {
+                break;
+            }
+            result.extend_from_slice(&next);
+            frontier = next;
+        }
+        Ok(result)
+    }
+
     /// Gets list of space level IDs that defines shortest path between two space levels,
     /// or throws error if level does not exists. Levels must lay on the same zoom level!
     ///
@@ -296,6 +1025,203 @@ where
         }
     }
 
+    /// Gets list of space level IDs that defines path between two space levels laying on any zoom
+    /// levels, or throws error if level does not exists. Deeper level climbs up through its
+    /// parents to zoom level of the other one, then path goes through that zoom level like in
+    /// `find_path()` and finally descends through children down to target level.
+    /// Path is ordered from source to target and every vertical step (parent or sublevel) is
+    /// listed explicitly, so following it one level at a time gives zoom-out, pan and zoom-in
+    /// phases in that order (at most one of zoom phases is not empty).
+    ///
+    /// # Arguments
+    /// * `from` - source level id.
+    /// * `to` - target level id.
+    ///
+    /// # Returns
+    /// `Ok` with path (empty if levels are not connected) or `Err` if level does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::new(2, 2, 16);
+    /// let subs = lod.level(lod.root()).sublevels().to_vec();
+    /// let subs2 = lod.level(subs[3]).sublevels().to_vec();
+    /// assert_eq!(
+    ///     lod.find_path_any(subs2[1], subs[1]).unwrap(),
+    ///     vec![subs2[1], subs[3], subs[0], subs[1]],
+    /// );
+    /// assert_eq!(
+    ///     lod.find_path_any(lod.root(), subs2[1]).unwrap(),
+    ///     vec![lod.root(), subs[3], subs2[1]],
+    /// );
+    /// ```
+    pub fn find_path_any(&self, from: ID, to: ID) -> Result<Vec<ID>> {
+        if !self.level_exists(from) {
+            return Err(QDFError::LevelDoesNotExists(from));
+        }
+        if !self.level_exists(to) {
+            return Err(QDFError::LevelDoesNotExists(to));
+        }
+        let depth = self.levels[&from].level().min(self.levels[&to].level());
+        let climb = |id: ID| {
+            let mut result = vec![id];
+            let mut current = id;
+            while self.levels[&current].level() > depth {
+                current = self.levels[&current].parent().unwrap();
+                result.push(current);
+            }
+            result
+        };
+        let ascent = climb(from);
+        let mut descent = climb(to);
+        descent.reverse();
+        let path = self.find_path(*ascent.last().unwrap(), descent[0])?;
+        if path.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut result = ascent;
+        result.pop();
+        result.extend(path);
+        result.extend(descent.into_iter().skip(1));
+        Ok(result)
+    }
+
+    /// Checks LOD consistency: every non-platonic level state equals merge of its sublevels states,
+    /// every sublevel points back to its parent and platonic levels are exactly the levels without
+    /// sublevels.
+    ///
+    /// # Returns
+    /// `Ok` if LOD is consistent, `Err` with list of all found violations otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let mut lod = LOD::new(2, 2, 16);
+    /// let id = lod.platonic_levels().next().unwrap();
+    /// lod.set_level_state(id, 5).unwrap();
+    /// assert!(lod.validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> StdResult<(), Vec<String>>
+    where
+        S: PartialEq,
+    {
+        let mut errors = vec![];
+        if let Some(level) = self.levels.get(&self.root) {
+            if let Some(parent) = level.parent() {
+                errors.push(format!("root level {:?} has parent {:?}", self.root, parent));
+            }
+        } else {
+            errors.push(format!("root level {:?} does not exists", self.root));
+        }
+        for (id, level) in &self.levels {
+            if level.parent().is_none() && *id != self.root {
+                errors.push(format!("level {:?} has no parent but it is not root", id));
+            }
+            let mut states = Vec::with_capacity(level.sublevels().len());
+            for sub in level.sublevels() {
+                if let Some(sublevel) = self.levels.get(sub) {
+                    if sublevel.parent() != Some(*id) {
+                        errors.push(format!(
+                            "sublevel {:?} of level {:?} points to parent {:?}",
+                            sub,
+                            id,
+                            sublevel.parent()
+                        ));
+                    }
+                    states.push(sublevel.state().clone());
+                } else {
+                    errors.push(format!("sublevel {:?} of level {:?} does not exists", sub, id));
+                }
+            }
+            if level.sublevels().is_empty() {
+                if !self.platonic_levels.contains(id) {
+                    errors.push(format!("level {:?} has no sublevels but is not platonic", id));
+                }
+            } else {
+                if self.platonic_levels.contains(id) {
+                    errors.push(format!("level {:?} has sublevels but is platonic", id));
+                }
+                if states.len() == level.sublevels().len() {
+                    let merged = State::merge(&states);
+                    if merged != *level.state() {
+                        errors.push(format!(
+                            "level {:?} state {:?} does not equal merged sublevels state {:?}",
+                            id,
+                            level.state(),
+                            merged
+                        ));
+                    }
+                }
+            }
+        }
+        for id in &self.platonic_levels {
+            if !self.levels.contains_key(id) {
+                errors.push(format!("platonic level {:?} does not exists", id));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Exports LOD hierarchy as Graphviz DOT document. Every level is a node labelled with short
+    /// form of its id, its zoom level and its state. Parent to sublevel relations are drawn as
+    /// solid arrows and neighbor connections between levels as dashed lines.
+    ///
+    /// # Returns
+    /// DOT document string.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let lod = LOD::new(2, 1, 16);
+    /// let dot = lod.to_dot();
+    /// assert!(dot.starts_with("digraph LOD {"));
+    /// assert_eq!(dot.matches("[label=").count(), 5);
+    /// assert_eq!(dot.matches("[style=solid];").count(), 4);
+    /// assert_eq!(dot.matches("[style=dashed, dir=none];").count(), 3);
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut result = String::from("digraph LOD {\n");
+        for id in self.graph.nodes() {
+            let level = &self.levels[&id];
+            let key = id.key();
+            writeln!(
+                result,
+                "    \"{}\" [label=\"{}\\nlevel {}\\n{}\"];",
+                key,
+                &key[..key.len().min(8)],
+                level.level(),
+                escape_dot(&format!("{:?}", level.state())),
+            ).unwrap();
+        }
+        for id in self.graph.nodes() {
+            for sublevel in self.levels[&id].sublevels() {
+                writeln!(
+                    result,
+                    "    \"{}\" -> \"{}\" [style=solid];",
+                    id.key(),
+                    sublevel.key()
+                ).unwrap();
+            }
+        }
+        for (a, b, _) in self.graph.all_edges() {
+            writeln!(
+                result,
+                "    \"{}\" -> \"{}\" [style=dashed, dir=none];",
+                a.key(),
+                b.key()
+            ).unwrap();
+        }
+        result.push_str("}\n");
+        result
+    }
+
     /// Performs simulation step (go through all platonic spaces and modifies its states based on
     /// neighbor states). Actual state simulation is performed by your struct that implements
     /// `Simulation` trait.
@@ -304,11 +1230,7 @@ where
         M: Simulate<S>,
     {
         let states = self.simulate_states::<M>();
-        for (id, state) in states {
-            self.levels.get_mut(&id).unwrap().apply_state(state);
-        }
-        let root = self.root;
-        self.recalculate_states(root);
+        self.apply_platonic_states(states);
     }
 
     /// Does the same as `simulation_step()` but in parallel manner (it may or may not increase
@@ -318,15 +1240,58 @@ where
         M: Simulate<S>,
     {
         let states = self.simulate_states_parallel::<M>();
+        self.apply_platonic_states(states);
+    }
+
+    /// Applies states of platonic levels (for example computed with `simulate_states()` or by
+    /// your own code) and then merges states of all their ancestors once, instead of doing it
+    /// after every single level like `set_level_state()` does. Nothing is applied if any of given
+    /// levels does not exists or is not platonic.
+    ///
+    /// # Arguments
+    /// * `states` - tuples of platonic level id and its new state.
+    ///
+    /// # Returns
+    /// `Ok` if states were applied or `Err` if any level does not exists or is not platonic.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::LOD;
+    ///
+    /// let mut lod = LOD::new(2, 1, 16);
+    /// let states = lod
+    ///     .platonic_levels()
+    ///     .map(|id| (id, *lod.level(id).state() * 2))
+    ///     .collect::<Vec<_>>();
+    /// lod.apply_simulated(states).unwrap();
+    /// assert_eq!(*lod.state(), 32);
+    /// assert!(lod.apply_simulated(vec![(lod.root(), 1)]).is_err());
+    /// ```
+    pub fn apply_simulated(&mut self, states: Vec<(ID, S)>) -> Result<()> {
+        for (id, _) in &states {
+            if !self.level_exists(*id) {
+                return Err(QDFError::LevelDoesNotExists(*id));
+            }
+            if !self.platonic_levels.contains(id) {
+                return Err(QDFError::LevelIsNotPlatonic(*id));
+            }
+        }
+        self.apply_platonic_states(states);
+        Ok(())
+    }
+
+    fn apply_platonic_states(&mut self, states: Vec<(ID, S)>) {
+        let mut changed = Vec::with_capacity(states.len());
         for (id, state) in states {
             self.levels.get_mut(&id).unwrap().apply_state(state);
+            changed.push(id);
         }
-        let root = self.root;
-        self.recalculate_states(root);
+        self.recalculate_ancestors_states(&changed);
     }
 
     /// Performs simulation on LOD like `simulation_step()` but instead of applying results to LOD,
-    /// it returns simulated platonic level states along with their level ID.
+    /// it returns simulated platonic level states along with their level ID. Results can be
+    /// applied later with `apply_simulated()`.
     pub fn simulate_states<M>(&self) -> Vec<(ID, S)>
     where
         M: Simulate<S>,
@@ -361,6 +1326,12 @@ where
             }).collect()
     }
 
+    // number of sublevels of every level when branching is not given explicitly.
+    #[inline]
+    fn default_branching(dimensions: usize) -> usize {
+        dimensions + 2
+    }
+
     fn subdivide_level(
         id: ID,
         graph: &mut UnGraphMap<ID, ()>,
@@ -394,22 +1365,88 @@ where
         }
     }
 
-    fn connect_clusters(id: ID, graph: &mut UnGraphMap<ID, ()>, levels: &HashMap<ID, Level<S>>) {
-        let sublevels = levels[&id].sublevels();
-        if !sublevels.is_empty() {
-            let neighbors = graph
-                .neighbors(id)
-                .map(|i| (i, levels[&i].index()))
-                .collect::<Vec<(ID, usize)>>();
+    fn create_sublevels(&mut self, id: ID) {
+        let (depth, index, substates) = {
+            let level = &self.levels[&id];
+            (
+                level.level(),
+                level.index(),
+                level.state().subdivide(self.branching),
+            )
+        };
+        let sublevels = substates
+            .into_iter()
+            .enumerate()
+            .map(|(idx, substate)| {
+                let i = ID::new();
+                self.graph.add_node(i);
+                self.levels
+                    .insert(i, Level::new(i, Some(id), depth + 1, idx, substate));
+                self.platonic_levels.insert(i);
+                i
+            }).collect::<Vec<ID>>();
+        for l in sublevels.iter().skip(1) {
+            self.graph.add_edge(sublevels[0], *l, ());
+        }
+        // the same rule as `connect_clusters()` uses, applied when second of neighbor levels gets
+        // its sublevels, so it does not matter which of them is subdivided first.
+        let neighbors = self
+            .graph
+            .neighbors(id)
+            .filter(|n| !self.levels[n].sublevels().is_empty())
+            .map(|n| (n, self.levels[&n].index()))
+            .collect::<Vec<(ID, usize)>>();
+        for (n, ni) in neighbors {
             for (i, l) in sublevels.iter().enumerate().skip(1) {
-                for (nl, ni) in &neighbors {
-                    if i != *ni {
-                        graph.add_edge(*l, levels[&nl].sublevels()[i], ());
+                if i != ni || i != index {
+                    let nl = self.levels[&n].sublevels()[i];
+                    self.graph.add_edge(*l, nl, ());
+                }
+            }
+        }
+        self.platonic_levels.remove(&id);
+        self.levels.get_mut(&id).unwrap().apply_sublevels(sublevels);
+    }
+
+    // sublevels are connected depth by depth, so every level knows all of its neighbors before
+    // its own sublevels get connected. Sublevels with the same index of two neighbor levels are
+    // connected unless that index equals indices of both levels (sublevels with index 0 are
+    // connected only within their cluster), the same way as `create_sublevels()` does.
+    #[allow(clippy::needless_borrow)]
+    fn connect_clusters(id: ID, graph: &mut UnGraphMap<ID, ()>, levels: &HashMap<ID, Level<S>>) {
+        let mut current = vec![id];
+        while !current.is_empty() {
+            let mut next = vec![];
+            for id in &current {
+                let level = &levels[id];
+                let sublevels = level.sublevels();
+                if sublevels.is_empty() {
+                    continue;
+                }
+                let neighbors = graph
+                    .neighbors(*id)
+                    .map(|i| (i, levels[&i].index()))
+                    .collect::<Vec<(ID, usize)>>();
+                for (i, l) in sublevels.iter().enumerate().skip(1) {
+                    for (nl, ni) in &neighbors {
+                        if i != *ni || i != level.index() {
+                            graph.add_edge(*l, levels[&nl].sublevels()[i], ());
+                        }
                     }
                 }
+                next.extend_from_slice(sublevels);
             }
-            for l in sublevels.iter().skip(1) {
-                Self::connect_clusters(*l, graph, levels);
+            current = next;
+        }
+    }
+
+    fn collect_leaves_in_order(id: ID, levels: &HashMap<ID, Level<S>>, leaves: &mut Vec<ID>) {
+        let sublevels = levels[&id].sublevels();
+        if sublevels.is_empty() {
+            leaves.push(id);
+        } else {
+            for id in sublevels {
+                Self::collect_leaves_in_order(*id, levels, leaves);
             }
         }
     }
@@ -429,25 +1466,41 @@ where
         }
     }
 
-    fn recalculate_states(&mut self, id: ID) -> S {
-        let level = self.levels[&id].clone();
-        if level.sublevels().is_empty() {
-            level.state().clone()
-        } else {
-            let states = level
+    fn recalculate_ancestors_states(&mut self, changed: &[ID]) {
+        let mut visited = HashSet::new();
+        let mut ancestors = vec![];
+        for id in changed {
+            let mut parent = self.levels[id].parent();
+            while let Some(id) = parent {
+                if !visited.insert(id) {
+                    break;
+                }
+                let level = &self.levels[&id];
+                ancestors.push((level.level(), id));
+                parent = level.parent();
+            }
+        }
+        ancestors.sort_by_key(|(level, _)| Reverse(*level));
+        for (_, id) in ancestors {
+            let states = self.levels[&id]
                 .sublevels()
                 .iter()
-                .map(|i| self.recalculate_states(*i))
+                .map(|i| self.levels[i].state().clone())
                 .collect::<Vec<S>>();
-            let state = State::merge(&states);
-            self.levels.get_mut(&id).unwrap().apply_state(state.clone());
-            state
+            self.levels
+                .get_mut(&id)
+                .unwrap()
+                .apply_state(State::merge(&states));
         }
     }
 
+    #[allow(clippy::needless_borrow, clippy::useless_conversion)]
     fn recalculate_children_states(&mut self, id: ID) {
+        if self.platonic_levels.contains(&id) {
+            return;
+        }
         let level = self.levels[&id].clone();
-        let states = level.state().subdivide(self.dimensions + 2);
+        let states = level.state().subdivide(self.branching);
         for (id, state) in level.sublevels().iter().zip(states.into_iter()) {
             self.levels.get_mut(&id).unwrap().apply_state(state);
             self.recalculate_children_states(*id);
@@ -470,3 +1523,156 @@ where
         }
     }
 }
+
+#[cfg(feature = "serde")]
+impl<S> Serialize for LOD<S>
+where
+    S: State + Serialize,
+{
+    fn serialize<T>(&self, serializer: T) -> StdResult<T::Ok, T::Error>
+    where
+        T: Serializer,
+    {
+        let nodes = self.graph.nodes().collect::<Vec<ID>>();
+        let levels = nodes
+            .iter()
+            .filter_map(|id| self.levels.get(id))
+            .collect::<Vec<&Level<S>>>();
+        let platonic_levels = nodes
+            .iter()
+            .filter(|id| self.platonic_levels.contains(id))
+            .collect::<Vec<&ID>>();
+        let mut state = serializer.serialize_struct("LOD", 9)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("root", &self.root)?;
+        state.serialize_field("dimensions", &self.dimensions)?;
+        state.serialize_field("count", &self.count)?;
+        state.serialize_field("branching", &self.branching)?;
+        state.serialize_field("levels", &levels)?;
+        state.serialize_field("platonic_levels", &platonic_levels)?;
+        state.serialize_field("nodes", &nodes)?;
+        state.serialize_field("edges", &ordered_edges(&self.graph))?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, S> Deserialize<'de> for LOD<S>
+where
+    S: State + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename = "LOD")]
+        struct Data<S>
+        where
+            S: State,
+        {
+            id: ID,
+            root: ID,
+            dimensions: usize,
+            count: usize,
+            #[serde(default)]
+            branching: Option<usize>,
+            levels: Vec<Level<S>>,
+            platonic_levels: Vec<ID>,
+            nodes: Vec<ID>,
+            edges: Vec<(ID, ID)>,
+        }
+
+        let data = Data::<S>::deserialize(deserializer)?;
+        let mut graph = UnGraphMap::with_capacity(data.nodes.len(), data.edges.len());
+        for id in data.nodes {
+            if graph.contains_node(id) {
+                return Err(D::Error::custom(format!("node {:?} is duplicated", id)));
+            }
+            graph.add_node(id);
+        }
+        for (a, b) in data.edges {
+            if !graph.contains_node(a) || !graph.contains_node(b) {
+                return Err(D::Error::custom(format!(
+                    "edge between {:?} and {:?} points to unknown node",
+                    a, b
+                )));
+            }
+            if a == b {
+                return Err(D::Error::custom(format!(
+                    "edge connects node {:?} with itself",
+                    a
+                )));
+            }
+            graph.add_edge(a, b, ());
+        }
+        let mut levels = HashMap::with_capacity(data.levels.len());
+        for level in data.levels {
+            let id = level.id();
+            if !graph.contains_node(id) {
+                return Err(D::Error::custom(format!("level {:?} is not a graph node", id)));
+            }
+            if levels.insert(id, level).is_some() {
+                return Err(D::Error::custom(format!("level {:?} is duplicated", id)));
+            }
+        }
+        if levels.len() != graph.node_count() {
+            return Err(D::Error::custom(format!(
+                "LOD has {} levels for {} nodes",
+                levels.len(),
+                graph.node_count()
+            )));
+        }
+        if !levels.contains_key(&data.root) {
+            return Err(D::Error::custom(format!(
+                "root level {:?} does not exists",
+                data.root
+            )));
+        }
+        for level in levels.values() {
+            if let Some(id) = level
+                .sublevels()
+                .iter()
+                .chain(level.parent().iter())
+                .find(|id| !levels.contains_key(id))
+            {
+                return Err(D::Error::custom(format!(
+                    "level {:?} refers to unknown level {:?}",
+                    level.id(),
+                    id
+                )));
+            }
+        }
+        let platonic_levels = data.platonic_levels.into_iter().collect::<HashSet<ID>>();
+        if let Some(id) = platonic_levels.iter().find(|id| !levels.contains_key(id)) {
+            return Err(D::Error::custom(format!(
+                "platonic level {:?} does not exists",
+                id
+            )));
+        }
+        if let Some(level) = levels
+            .values()
+            .find(|level| level.sublevels().is_empty() != platonic_levels.contains(&level.id()))
+        {
+            return Err(D::Error::custom(format!(
+                "level {:?} is platonic only if it has no sublevels",
+                level.id()
+            )));
+        }
+        let dimensions = data.dimensions;
+        let branching = data
+            .branching
+            .unwrap_or_else(|| Self::default_branching(dimensions));
+        Ok(Self {
+            id: data.id,
+            graph,
+            levels,
+            platonic_levels,
+            root: data.root,
+            dimensions,
+            count: data.count,
+            branching,
+            stale: false,
+        })
+    }
+}
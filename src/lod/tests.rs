@@ -3,6 +3,7 @@
 use super::*;
 
 #[test]
+#[allow(clippy::redundant_pattern_matching, clippy::assertions_on_constants)]
 fn test_2d() {
     let lod = LOD::new(2, 0, 1);
     let root = lod.root();
@@ -72,3 +73,730 @@ fn test_2d() {
         );
     }
 }
+
+#[test]
+fn test_platonic_levels() {
+    for dimensions in 1..4 {
+        for count in 0..4 {
+            let lod = LOD::new(dimensions, count, 1.0);
+            let expected = (dimensions + 2).pow(count as u32);
+            assert_eq!(lod.platonic_count(), expected);
+            assert_eq!(lod.platonic_levels().count(), expected);
+            for id in lod.platonic_levels() {
+                let level = lod.level(id);
+                assert!(level.sublevels().is_empty());
+                assert_eq!(level.level(), count);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_levels_at_depth() {
+    let lod = LOD::new(2, 3, 64);
+    let mut total = 0;
+    for depth in 0..4 {
+        let ids = lod.levels_at_depth(depth);
+        assert_eq!(ids.len(), 4usize.pow(depth as u32));
+        for id in &ids {
+            for n in lod.find_level_neighbors(*id).unwrap() {
+                assert!(ids.contains(&n));
+            }
+        }
+        total += ids.len();
+    }
+    assert_eq!(total, lod.levels.len());
+}
+
+#[derive(Debug, Default, Clone)]
+struct Counted(f64);
+
+thread_local! {
+    static SUBDIVISIONS: ::std::cell::Cell<usize> = const { ::std::cell::Cell::new(0) };
+}
+
+impl State for Counted {
+    fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
+        SUBDIVISIONS.with(|c| c.set(c.get() + 1));
+        vec![Counted(self.0 / subdivisions as f64); subdivisions]
+    }
+    fn merge(states: &[Self]) -> Self {
+        Counted(states.iter().map(|s| s.0).sum())
+    }
+}
+
+#[test]
+fn test_set_platonic_level_state() {
+    let mut lod = LOD::new(2, 3, Counted(64.0));
+    let leaves = lod.platonic_levels().collect::<Vec<ID>>();
+    SUBDIVISIONS.with(|c| c.set(0));
+    for (i, id) in leaves.iter().enumerate() {
+        lod.set_level_state(*id, Counted(i as f64)).unwrap();
+    }
+    assert_eq!(SUBDIVISIONS.with(|c| c.get()), 0);
+    let expected = leaves.iter().map(|id| lod.level(*id).state().0).sum::<f64>();
+    assert_eq!(lod.state().0, expected);
+    let sub = lod.level(lod.root()).sublevels()[0];
+    lod.set_level_state(sub, Counted(16.0)).unwrap();
+    assert_eq!(SUBDIVISIONS.with(|c| c.get()), 5);
+}
+
+fn merged_state(lod: &LOD<i32>, id: ID) -> i32 {
+    let level = lod.level(id);
+    if level.sublevels().is_empty() {
+        *level.state()
+    } else {
+        let states = level
+            .sublevels()
+            .iter()
+            .map(|i| merged_state(lod, *i))
+            .collect::<Vec<i32>>();
+        State::merge(&states)
+    }
+}
+
+struct Drift;
+
+impl Simulate<i32> for Drift {
+    fn simulate(state: &i32, neighbor_states: &[&i32]) -> i32 {
+        state + neighbor_states.iter().map(|s| **s % 3).sum::<i32>()
+    }
+}
+
+#[test]
+fn test_incremental_recalculation() {
+    let mut lod = LOD::new(2, 3, 6400);
+    for _ in 0..3 {
+        lod.simulation_step::<Drift>();
+        for id in lod.levels.keys() {
+            assert_eq!(*lod.level(*id).state(), merged_state(&lod, *id));
+        }
+        lod.simulation_step_parallel::<Drift>();
+        for id in lod.levels.keys() {
+            assert_eq!(*lod.level(*id).state(), merged_state(&lod, *id));
+        }
+    }
+    assert_ne!(*lod.state(), 6400);
+}
+
+#[test]
+fn test_apply_simulated() {
+    let mut lod = LOD::new(2, 3, 6400);
+    let states = lod.simulate_states::<Drift>();
+    lod.apply_simulated(states.clone()).unwrap();
+    for (id, state) in states {
+        assert_eq!(*lod.level(id).state(), state);
+    }
+    for id in lod.levels.keys() {
+        assert_eq!(*lod.level(*id).state(), merged_state(&lod, *id));
+    }
+    assert_ne!(*lod.state(), 6400);
+
+    let leaf = lod.platonic_levels().next().unwrap();
+    let before = *lod.level(leaf).state();
+    match lod.apply_simulated(vec![(leaf, 1), (ID::new(), 1)]) {
+        Err(QDFError::LevelDoesNotExists(_)) => {}
+        _ => panic!("unknown level must not be applied"),
+    }
+    let root = lod.root();
+    match lod.apply_simulated(vec![(leaf, 1), (root, 1)]) {
+        Err(QDFError::LevelIsNotPlatonic(id)) => assert_eq!(id, root),
+        _ => panic!("non-platonic level must not be applied"),
+    }
+    assert_eq!(*lod.level(leaf).state(), before);
+}
+
+#[test]
+fn test_level_neighbors_within() {
+    let lod = LOD::new(3, 3, 4096);
+    for id in lod.levels.keys() {
+        let depth = lod.level(*id).level();
+        let ring = lod.level_neighbors_within(*id, 2).unwrap();
+        assert!(!ring.contains(id));
+        assert!(ring.iter().all(|i| lod.level(*i).level() == depth));
+        let near = lod.level_neighbors_within(*id, 1).unwrap();
+        assert_eq!(near, lod.find_level_neighbors(*id).unwrap());
+        assert_eq!(&ring[..near.len()], &near[..]);
+        let all = lod.level_neighbors_within(*id, 100).unwrap();
+        assert_eq!(&all[..ring.len()], &ring[..]);
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), all.len());
+        assert!(all.len() < lod.levels_at_depth(depth).len());
+    }
+    assert!(lod.level_neighbors_within(ID::new(), 1).is_err());
+}
+
+#[test]
+fn test_with_branching() {
+    for branching in 1..5 {
+        let mut lod = LOD::with_branching(3, 3, branching, 1000.0);
+        for id in lod.levels.keys() {
+            let level = lod.level(*id);
+            if level.level() < 3 {
+                assert_eq!(level.sublevels().len(), branching);
+            } else {
+                assert!(level.sublevels().is_empty());
+            }
+        }
+        assert_eq!(lod.platonic_count(), branching.pow(3));
+        let root = lod.root();
+        lod.set_level_state(root, 8.0).unwrap();
+        let id = lod.platonic_levels().next().unwrap();
+        assert_eq!(*lod.level(id).state(), 8.0 / branching.pow(3) as f64);
+    }
+}
+
+#[test]
+fn test_subdivision_count() {
+    for dimensions in 1..4 {
+        let lod = LOD::new(dimensions, 2, 0);
+        assert_eq!(lod.subdivision_count(), dimensions + 2);
+        for id in lod.levels_at_depth(1) {
+            assert_eq!(lod.level(id).sublevels().len(), lod.subdivision_count());
+        }
+    }
+    let lod = LOD::with_branching(2, 2, 7, 0);
+    assert_eq!(lod.subdivision_count(), 7);
+    assert_eq!(lod.level(lod.root()).sublevels().len(), 7);
+}
+
+#[test]
+fn test_refine_leaf() {
+    let mut lod = LOD::new(2, 2, 4096);
+    let leaves = lod.platonic_levels().collect::<Vec<_>>();
+    let leaf = leaves[0];
+    let sublevels = lod.refine_leaf(leaf).unwrap();
+    assert_eq!(lod.levels_count(), 3);
+    assert_eq!(lod.platonic_count(), leaves.len() - 1 + sublevels.len());
+    for id in &sublevels {
+        assert_eq!(lod.level(*id).parent(), Some(leaf));
+        assert_eq!(lod.level(*id).level(), 3);
+        assert!(lod.platonic_levels().any(|i| i == *id));
+    }
+    let states = sublevels
+        .iter()
+        .map(|id| *lod.level(*id).state())
+        .collect::<Vec<_>>();
+    assert_eq!(State::merge(&states), *lod.level(leaf).state());
+    assert!(lod.validate().is_ok());
+    match lod.refine_leaf(leaf) {
+        Err(QDFError::LevelIsNotPlatonic(id)) => assert_eq!(id, leaf),
+        _ => panic!("refined level must not be refined again"),
+    }
+
+    for id in leaves.iter().skip(1) {
+        lod.refine_leaf(*id).unwrap();
+    }
+    let eager = LOD::new(2, 3, 4096);
+    assert_eq!(lod.level_count(), eager.level_count());
+    assert_eq!(edges_by_path(&lod), edges_by_path(&eager));
+    assert!(lod.validate().is_ok());
+    // sublevels of neighbor leaves get connected no matter which leaf was refined first.
+    for a in &leaves {
+        for b in lod.find_level_neighbors(*a).unwrap() {
+            assert!(lod.level(*a).sublevels().iter().any(|sa| lod
+                .level(b)
+                .sublevels()
+                .iter()
+                .any(|sb| lod.graph().contains_edge(*sa, *sb))));
+        }
+    }
+}
+
+#[test]
+fn test_coarsen() {
+    let mut lod = LOD::new(2, 2, 4096);
+    let before = (lod.level_count(), lod.graph().edge_count());
+    let leaf = lod.platonic_levels().next().unwrap();
+    let sublevels = lod.refine_leaf(leaf).unwrap();
+    lod.set_level_state(sublevels[1], 1000).unwrap();
+    let state = *lod.level(leaf).state();
+    assert_eq!(lod.coarsen(leaf).unwrap(), leaf);
+    assert_eq!(*lod.level(leaf).state(), state);
+    assert!(lod.platonic_levels().any(|id| id == leaf));
+    assert!(sublevels.iter().all(|id| !lod.level_exists(*id)));
+    assert!(sublevels.iter().all(|id| !lod.graph().contains_node(*id)));
+    assert_eq!((lod.level_count(), lod.graph().edge_count()), before);
+    assert!(lod.validate().is_ok());
+
+    let parent = lod.level(leaf).parent().unwrap();
+    match lod.coarsen(lod.root()) {
+        Err(QDFError::LevelIsNotPlatonic(id)) => {
+            assert!(lod.level(lod.root()).sublevels().contains(&id))
+        }
+        _ => panic!("level with non-platonic sublevels must not be coarsened"),
+    }
+    let state = *lod.level(parent).state();
+    lod.coarsen(parent).unwrap();
+    assert_eq!(lod.coarsen(parent).unwrap(), parent);
+    assert_eq!(*lod.level(parent).state(), state);
+    assert_eq!(lod.platonic_count(), 13);
+    assert!(lod.validate().is_ok());
+}
+
+#[test]
+fn test_from_qdf() {
+    for dimensions in 1..4 {
+        let (mut qdf, spaces) = QDF::with_levels(dimensions, 0, 3);
+        for (i, id) in spaces.iter().enumerate() {
+            qdf.set_space_state(*id, i as i32).unwrap();
+        }
+        let lod = LOD::from_qdf(&qdf, 3).unwrap();
+        assert!(lod.validate().is_ok());
+        assert_eq!(lod.dimensions(), dimensions);
+        assert_eq!(lod.levels_count(), 3);
+        assert_eq!(lod.branching(), dimensions + 1);
+        assert_eq!(lod.platonic_count(), spaces.len());
+        assert_eq!(*lod.state(), qdf.total_state());
+        for id in &spaces {
+            assert_eq!(lod.level(*id).state(), qdf.space(*id).state());
+            assert_eq!(lod.level(*id).level(), 3);
+            let ancestors = qdf.ancestors(*id);
+            assert_eq!(lod.level(*id).parent(), Some(ancestors[0]));
+            assert_eq!(*ancestors.last().unwrap(), lod.root());
+            for n in qdf.find_space_neighbors(*id).unwrap() {
+                assert!(lod.graph().contains_edge(*id, n));
+            }
+        }
+        for depth in 0..4 {
+            assert_eq!(
+                lod.levels_at_depth(depth).len(),
+                (dimensions + 1).pow(depth as u32)
+            );
+        }
+    }
+
+    let (qdf, root) = QDF::new(2, 9);
+    let lod = LOD::from_qdf(&qdf, 0).unwrap();
+    assert_eq!(lod.root(), root);
+    assert_eq!(*lod.state(), 9);
+
+    let (mut qdf, spaces) = QDF::with_levels(2, 81, 2);
+    match LOD::from_qdf(&qdf, 3) {
+        Err(QDFError::NotUniform(id)) => assert!(spaces.contains(&id)),
+        _ => panic!("QDF shallower than LOD must not be converted"),
+    }
+    qdf.decrease_space_density(spaces[0]).unwrap();
+    match LOD::from_qdf(&qdf, 2) {
+        Err(error) => assert!(error.to_string().contains("breaks uniform subdivision")),
+        _ => panic!("QDF with merged spaces must not be converted"),
+    }
+    let copy = QDF::<i32>::from_bytes(&qdf.to_bytes()).unwrap();
+    assert!(LOD::from_qdf(&copy, 2).is_err());
+    let (mut qdf, root) = QDF::new(2, 81);
+    qdf.remove_space(root).unwrap();
+    match LOD::from_qdf(&qdf, 2) {
+        Err(QDFError::CountMismatch(9, 0)) => {}
+        _ => panic!("empty QDF must not be converted"),
+    }
+}
+
+#[test]
+fn test_iter_levels() {
+    let lod = LOD::new(2, 3, 64);
+    let mut children = HashMap::<ID, Vec<ID>>::new();
+    let mut roots = vec![];
+    for (id, level) in lod.iter_levels() {
+        match level.parent() {
+            Some(parent) => children.entry(parent).or_default().push(id),
+            None => roots.push(id),
+        }
+    }
+    assert_eq!(roots, vec![lod.root()]);
+    assert_eq!(lod.iter_levels().count(), lod.levels.len());
+    for (id, level) in lod.iter_levels() {
+        let mut expected = level.sublevels().to_vec();
+        let mut found = children.remove(&id).unwrap_or_default();
+        expected.sort();
+        found.sort();
+        assert_eq!(found, expected);
+    }
+    assert!(children.is_empty());
+}
+
+#[test]
+fn test_graph() {
+    let lod = LOD::new(3, 2, 125);
+    let graph = lod.graph();
+    assert_eq!(graph.node_count(), lod.levels.len());
+    for (a, b, _) in graph.all_edges() {
+        assert_eq!(lod.level(a).level(), lod.level(b).level());
+        assert_ne!(lod.level(a).parent(), Some(b));
+        assert_ne!(lod.level(b).parent(), Some(a));
+    }
+    for id in lod.levels.keys() {
+        let mut neighbors = graph.neighbors(*id).collect::<Vec<ID>>();
+        let mut expected = lod.find_level_neighbors(*id).unwrap();
+        neighbors.sort();
+        expected.sort();
+        assert_eq!(neighbors, expected);
+    }
+}
+
+#[test]
+fn test_lazy() {
+    let mut lod = LOD::lazy(3, 12, 1u64 << 40);
+    assert_eq!(lod.level_count(), 1);
+    let mut id = lod.root();
+    for depth in 0..12 {
+        let sub = lod.sublevel(id, depth % 5).unwrap();
+        assert_eq!(lod.sublevel(id, depth % 5).unwrap(), sub);
+        assert_eq!(lod.level(sub).level(), depth + 1);
+        assert_eq!(lod.level(sub).parent(), Some(id));
+        id = sub;
+    }
+    assert_eq!(lod.level_count(), 1 + 5 * 12);
+    assert_eq!(lod.platonic_count(), 1 + 4 * 12);
+    match lod.sublevel(id, 0) {
+        Err(QDFError::DepthOutOfRange(13, 12)) => {}
+        _ => panic!("sublevel must not exceed levels count"),
+    }
+    match lod.sublevel(lod.root(), 5) {
+        Err(QDFError::IndexOutOfRange(5, 5)) => {}
+        _ => panic!("sublevel index must not exceed branching"),
+    }
+    assert!(lod.sublevel(ID::new(), 0).is_err());
+    assert!(lod.validate().is_ok());
+    assert_eq!(*lod.state(), 1 << 40);
+
+    let leaf = lod.platonic_levels().find(|i| lod.level(*i).level() == 12).unwrap();
+    lod.set_level_state(leaf, 0).unwrap();
+    assert!(lod.validate().is_ok());
+    assert!(*lod.state() < 1 << 40);
+}
+
+// position of level in tree, which does not depend on level ids.
+fn position(lod: &LOD<i32>, id: ID) -> (usize, usize, Option<usize>) {
+    let level = lod.level(id);
+    let parent = level.parent().map(|p| lod.level(p).index());
+    (level.level(), level.index(), parent)
+}
+
+// indices of levels on the way from root to given level, which identify level in any LOD of
+// the same shape.
+fn tree_path(lod: &LOD<i32>, id: ID) -> Vec<usize> {
+    let mut path = vec![];
+    let mut id = id;
+    while let Some(parent) = lod.level(id).parent() {
+        path.push(lod.level(id).index());
+        id = parent;
+    }
+    path.reverse();
+    path
+}
+
+fn edges_by_path(lod: &LOD<i32>) -> Vec<(Vec<usize>, Vec<usize>)> {
+    let mut edges = lod
+        .graph
+        .all_edges()
+        .map(|(a, b, _)| {
+            let (a, b) = (tree_path(lod, a), tree_path(lod, b));
+            if a < b {
+                (a, b)
+            } else {
+                (b, a)
+            }
+        }).collect::<Vec<_>>();
+    edges.sort();
+    edges
+}
+
+#[test]
+fn test_lazy_matches_eager() {
+    for dimensions in 1..4 {
+        for count in 1..4 {
+            let eager = LOD::new(dimensions, count, 1 << 20);
+            let mut lazy = LOD::lazy(dimensions, count, 1 << 20);
+            // expand levels from the last branch, so order differs from the eager one.
+            let mut pending = vec![lazy.root()];
+            while let Some(id) = pending.pop() {
+                if lazy.level(id).level() < count {
+                    for index in 0..lazy.branching() {
+                        pending.push(lazy.sublevel(id, index).unwrap());
+                    }
+                }
+            }
+            assert_eq!(lazy.level_count(), eager.level_count());
+            assert_eq!(edges_by_path(&lazy), edges_by_path(&eager));
+        }
+    }
+    assert_eq!(LOD::new(2, 3, 81).graph.edge_count(), 126);
+}
+
+#[test]
+fn test_lazy_connections_do_not_depend_on_order() {
+    let build = |order: &[usize]| {
+        let mut lod = LOD::lazy(2, 2, 256);
+        let root = lod.root();
+        for i in order {
+            let sub = lod.sublevel(root, *i).unwrap();
+            lod.sublevel(sub, 0).unwrap();
+        }
+        let mut edges = lod
+            .graph
+            .all_edges()
+            .map(|(a, b, _)| {
+                let (a, b) = (position(&lod, a), position(&lod, b));
+                if a < b {
+                    (a, b)
+                } else {
+                    (b, a)
+                }
+            }).collect::<Vec<_>>();
+        edges.sort();
+        edges
+    };
+    let edges = build(&[0, 1, 2, 3]);
+    assert_eq!(build(&[3, 2, 1, 0]), edges);
+    assert_eq!(build(&[2, 0, 3, 1]), edges);
+}
+
+#[test]
+fn test_find_path_any() {
+    let lod = LOD::new(2, 3, 81);
+    let platonic = lod.platonic_levels().collect::<Vec<ID>>();
+    let subs = lod.level(lod.root()).sublevels().to_vec();
+    for from in platonic.iter().take(8) {
+        for to in subs.iter().chain(platonic.iter().rev().take(8)) {
+            let path = lod.find_path_any(*from, *to).unwrap();
+            if path.is_empty() {
+                // not every pair of platonic levels is connected.
+                assert_eq!(lod.level(*to).level(), lod.level(*from).level());
+                assert!(lod.find_path(*from, *to).unwrap().is_empty());
+                continue;
+            }
+            assert_eq!(path[0], *from);
+            assert_eq!(*path.last().unwrap(), *to);
+            let depths = path
+                .iter()
+                .map(|id| lod.level(*id).level())
+                .collect::<Vec<usize>>();
+            let top = *depths.iter().min().unwrap();
+            assert_eq!(top, lod.level(*to).level());
+            for (pair, depth) in path.windows(2).zip(depths.windows(2)) {
+                if depth[0] == depth[1] {
+                    assert!(lod.find_level_neighbors(pair[0]).unwrap().contains(&pair[1]));
+                } else {
+                    assert_eq!(depth[0], depth[1] + 1);
+                    assert_eq!(lod.level(pair[0]).parent(), Some(pair[1]));
+                }
+            }
+            let back = lod.find_path_any(*to, *from).unwrap();
+            assert_eq!(back.first(), Some(to));
+            assert_eq!(back.last(), Some(from));
+        }
+    }
+    assert_eq!(
+        lod.find_path_any(platonic[0], platonic[0]).unwrap(),
+        vec![platonic[0]]
+    );
+    assert!(lod.find_path_any(lod.root(), ID::new()).is_err());
+}
+
+#[test]
+fn test_from_leaf_states() {
+    let leaves = (0..64).collect::<Vec<i32>>();
+    let lod = LOD::from_leaf_states(2, 3, leaves.clone()).unwrap();
+    assert_eq!(lod.platonic_count(), 64);
+    assert_eq!(*lod.state(), leaves.iter().sum::<i32>());
+    assert!(lod.validate().is_ok());
+    for (i, state) in leaves.iter().enumerate() {
+        let mut id = lod.root();
+        for digit in &[i / 16, i / 4 % 4, i % 4] {
+            id = lod.level(id).sublevels()[*digit];
+        }
+        assert_eq!(lod.level(id).state(), state);
+    }
+    let mut lod2 = LOD::new(2, 3, 0);
+    let mut id = lod2.root();
+    for _ in 0..3 {
+        id = lod2.level(id).sublevels()[3];
+    }
+    lod2.set_level_state(id, 5).unwrap();
+    let mut leaves = vec![0; 64];
+    leaves[63] = 5;
+    let lod = LOD::from_leaf_states(2, 3, leaves).unwrap();
+    assert_eq!(*lod.state(), *lod2.state());
+    let sub = lod.level(lod.root()).sublevels()[3];
+    let sub2 = lod2.level(lod2.root()).sublevels()[3];
+    assert_eq!(*lod.level(sub).state(), *lod2.level(sub2).state());
+
+    let lod = LOD::from_leaf_states(3, 0, vec![7]).unwrap();
+    assert_eq!(*lod.state(), 7);
+    assert_eq!(lod.platonic_levels().next(), Some(lod.root()));
+    match LOD::from_leaf_states(1, 2, vec![1; 8]) {
+        Err(QDFError::CountMismatch(9, 8)) => {}
+        _ => panic!("wrong number of leaves must be reported"),
+    }
+}
+
+#[test]
+fn test_set_level_state_deferred() {
+    let mut lod = LOD::new(2, 3, 0);
+    let mut lod2 = LOD::from_leaf_states(2, 3, vec![0; 64]).unwrap();
+    let order = |lod: &LOD<i32>| {
+        let mut result = vec![];
+        let mut stack = vec![lod.root()];
+        while let Some(id) = stack.pop() {
+            result.push(id);
+            stack.extend(lod.level(id).sublevels().iter().rev());
+        }
+        result
+    };
+    let ids = order(&lod);
+    let ids2 = order(&lod2);
+    for (i, (id, id2)) in ids.iter().zip(ids2.iter()).enumerate() {
+        if lod.level(*id).sublevels().is_empty() {
+            lod.set_level_state(*id, i as i32).unwrap();
+            lod2.set_level_state_deferred(*id2, i as i32).unwrap();
+        }
+    }
+    assert!(lod2.is_stale());
+    assert!(lod2.validate().is_err());
+    lod2.recalculate();
+    assert!(!lod2.is_stale());
+    assert!(lod2.validate().is_ok());
+    for (id, id2) in ids.iter().zip(ids2.iter()) {
+        assert_eq!(lod.level(*id).state(), lod2.level(*id2).state());
+    }
+    let sub = lod2.level(lod2.root()).sublevels()[0];
+    match lod2.set_level_state_deferred(sub, 1) {
+        Err(QDFError::LevelIsNotPlatonic(id)) => assert_eq!(id, sub),
+        _ => panic!("non-platonic level must be rejected"),
+    }
+    assert!(lod2.set_level_state_deferred(ID::new(), 1).is_err());
+    assert!(!lod2.is_stale());
+}
+
+#[test]
+fn test_validate() {
+    let mut lod = LOD::new(2, 2, 16);
+    assert!(lod.validate().is_ok());
+    let root = lod.root();
+    let sub = lod.level(root).sublevels()[0];
+    let leaf = lod.level(sub).sublevels()[0];
+    lod.levels.get_mut(&sub).unwrap().apply_state(3);
+    lod.platonic_levels.remove(&leaf);
+    let errors = lod.validate().unwrap_err();
+    assert_eq!(errors.len(), 3);
+    assert!(errors.iter().any(|e| e.contains("is not platonic")));
+    assert_eq!(
+        errors
+            .iter()
+            .filter(|e| e.contains("does not equal merged sublevels state"))
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_to_dot() {
+    let lod = LOD::new(2, 2, 16);
+    let dot = lod.to_dot();
+    assert!(dot.starts_with("digraph LOD {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert_eq!(dot.matches("[label=").count(), 21);
+    assert_eq!(dot.matches("[style=solid];").count(), 20);
+    assert_eq!(
+        dot.matches("[style=dashed, dir=none];").count(),
+        lod.graph.edge_count()
+    );
+    let root = lod.root();
+    let subs = lod.level(root).sublevels();
+    assert!(dot.contains(&format!("\"{}\" [label=\"", root.key())));
+    assert!(dot.contains("\\nlevel 2\\n1\"];"));
+    assert!(dot.contains(&format!(
+        "\"{}\" -> \"{}\" [style=solid];",
+        root.key(),
+        subs[0].key()
+    )));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    let lod = LOD::new(2, 3, 81);
+    let json = ::serde_json::to_string(&lod).unwrap();
+    let lod2: LOD<i32> = ::serde_json::from_str(&json).unwrap();
+    assert_eq!(lod2.id(), lod.id());
+    assert_eq!(lod2.root(), lod.root());
+    assert_eq!(lod2.dimensions(), lod.dimensions());
+    assert_eq!(lod2.levels_count(), lod.levels_count());
+    assert_eq!(lod2.branching(), lod.branching());
+    assert_eq!(*lod2.state(), *lod.state());
+
+    let first = |lod: &LOD<i32>, index: usize| {
+        let mut id = lod.root();
+        while let Some(sub) = lod.level(id).sublevels().get(index) {
+            id = *sub;
+        }
+        id
+    };
+    let from = first(&lod, 0);
+    let to = first(&lod, 3);
+    assert_eq!(lod2.level(from).level(), 3);
+    assert_eq!(lod2.level(to).level(), 3);
+    assert_eq!(lod2.find_path(from, to).unwrap(), lod.find_path(from, to).unwrap());
+    assert_eq!(
+        lod2.find_level_neighbors(from).unwrap(),
+        lod.find_level_neighbors(from).unwrap()
+    );
+    assert_eq!(::serde_json::to_string(&lod2).unwrap(), json);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_malformed() {
+    use serde_json::Value;
+
+    let lod = LOD::new(2, 2, 81);
+    let valid = serde_json::to_value(&lod).unwrap();
+    assert!(serde_json::from_value::<LOD<i32>>(valid.clone()).is_ok());
+    let unknown = serde_json::to_value(ID::new()).unwrap();
+    let root = serde_json::to_value(lod.root()).unwrap();
+    let rejected = |change: &dyn Fn(&mut Value), reason: &str| {
+        let mut data = valid.clone();
+        change(&mut data);
+        match serde_json::from_value::<LOD<i32>>(data) {
+            Err(error) => assert!(
+                error.to_string().contains(reason),
+                "`{}` does not mention `{}`",
+                error,
+                reason
+            ),
+            Ok(_) => panic!("malformed LOD must not deserialize ({})", reason),
+        }
+    };
+
+    rejected(
+        &|data| data["nodes"].as_array_mut().unwrap().push(unknown.clone()),
+        "21 levels for 22 nodes",
+    );
+    rejected(
+        &|data| data["nodes"].as_array_mut().unwrap().push(root.clone()),
+        "is duplicated",
+    );
+    rejected(
+        &|data| {
+            let level = data["levels"][0].clone();
+            data["levels"].as_array_mut().unwrap()[1] = level;
+        },
+        "is duplicated",
+    );
+    rejected(
+        &|data| {
+            let edge = Value::Array(vec![root.clone(), root.clone()]);
+            data["edges"].as_array_mut().unwrap().push(edge);
+        },
+        "with itself",
+    );
+    rejected(
+        &|data| {
+            data["platonic_levels"].as_array_mut().unwrap().pop();
+        },
+        "platonic only if it has no sublevels",
+    );
+    rejected(
+        &|data| data["platonic_levels"].as_array_mut().unwrap().push(root.clone()),
+        "platonic only if it has no sublevels",
+    );
+}
@@ -0,0 +1,181 @@
+use id::*;
+use qdf::*;
+use std::ops::Index;
+
+/// Dense storage of spaces.
+/// Spaces live in vector slots addressed by `u32` index and every slot keeps indices of its
+/// neighbors (mirror of space graph edges), so hot loops can walk neighborhood without hashing
+/// identifiers. Identifiers are hashed only when space is looked up from outside by its `ID`.
+/// Removing space moves last slot into its place, so indices are stable only between removals.
+#[derive(Debug, Clone)]
+pub(crate) struct SpaceArena<S>
+where
+    S: State,
+{
+    pub(super) ids: Vec<ID>,
+    pub(super) spaces: Vec<Space<S>>,
+    pub(super) neighbors: Vec<Vec<u32>>,
+    pub(super) index: IdMap<u32>,
+}
+
+impl<S> SpaceArena<S>
+where
+    S: State,
+{
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            ids: Vec::with_capacity(capacity),
+            spaces: Vec::with_capacity(capacity),
+            neighbors: Vec::with_capacity(capacity),
+            index: IdMap::with_capacity_and_hasher(capacity, Default::default()),
+        }
+    }
+
+    /// Builds arena from spaces and mirrors neighborhood of given graph.
+    pub fn from_graph<E, I>(spaces: I, graph: &SpaceGraph<E>) -> Self
+    where
+        I: IntoIterator<Item = Space<S>>,
+    {
+        let spaces = spaces.into_iter();
+        let mut arena = Self::with_capacity(spaces.size_hint().0);
+        for space in spaces {
+            arena.insert(space);
+        }
+        for (a, b, _) in graph.all_edges() {
+            arena.link(a, b);
+        }
+        arena
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    #[inline]
+    pub fn contains(&self, id: ID) -> bool {
+        self.index.contains_key(&id)
+    }
+
+    #[inline]
+    pub fn index_of(&self, id: ID) -> Option<u32> {
+        self.index.get(&id).cloned()
+    }
+
+    #[inline]
+    pub fn get(&self, id: ID) -> Option<&Space<S>> {
+        self.index_of(id).map(|i| &self.spaces[i as usize])
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, id: ID) -> Option<&mut Space<S>> {
+        match self.index_of(id) {
+            Some(i) => Some(&mut self.spaces[i as usize]),
+            None => None,
+        }
+    }
+
+    #[inline]
+    pub fn ids(&self) -> &[ID] {
+        &self.ids
+    }
+
+    #[inline]
+    pub fn spaces(&self) -> &[Space<S>] {
+        &self.spaces
+    }
+
+    #[inline]
+    pub fn spaces_mut(&mut self) -> &mut [Space<S>] {
+        &mut self.spaces
+    }
+
+    #[inline]
+    pub fn neighbors(&self, index: u32) -> &[u32] {
+        &self.neighbors[index as usize]
+    }
+
+    /// Inserts space (replacing one with the same id) and returns its slot index.
+    pub fn insert(&mut self, space: Space<S>) -> u32 {
+        let id = space.id();
+        if let Some(i) = self.index_of(id) {
+            self.spaces[i as usize] = space;
+            return i;
+        }
+        let i = self.ids.len() as u32;
+        self.ids.push(id);
+        self.spaces.push(space);
+        self.neighbors.push(vec![]);
+        self.index.insert(id, i);
+        i
+    }
+
+    /// Removes space along with its neighborhood links.
+    pub fn remove(&mut self, id: ID) -> Option<Space<S>> {
+        let i = self.index.remove(&id)?;
+        for n in ::std::mem::take(&mut self.neighbors[i as usize]) {
+            self.neighbors[n as usize].retain(|m| *m != i);
+        }
+        let last = self.ids.len() as u32 - 1;
+        self.ids.swap_remove(i as usize);
+        self.neighbors.swap_remove(i as usize);
+        let space = self.spaces.swap_remove(i as usize);
+        if i != last {
+            self.index.insert(self.ids[i as usize], i);
+            for n in self.neighbors[i as usize].clone() {
+                for m in &mut self.neighbors[n as usize] {
+                    if *m == last {
+                        *m = i;
+                    }
+                }
+            }
+        }
+        Some(space)
+    }
+
+    /// Links two spaces as neighbors (does nothing if any of them does not exists).
+    pub fn link(&mut self, a: ID, b: ID) {
+        if let (Some(ia), Some(ib)) = (self.index_of(a), self.index_of(b)) {
+            if ia != ib && !self.neighbors[ia as usize].contains(&ib) {
+                self.neighbors[ia as usize].push(ib);
+                self.neighbors[ib as usize].push(ia);
+            }
+        }
+    }
+
+    /// Unlinks two neighbor spaces.
+    pub fn unlink(&mut self, a: ID, b: ID) {
+        if let (Some(ia), Some(ib)) = (self.index_of(a), self.index_of(b)) {
+            self.neighbors[ia as usize].retain(|n| *n != ib);
+            self.neighbors[ib as usize].retain(|n| *n != ia);
+        }
+    }
+}
+
+impl<S> Default for SpaceArena<S>
+where
+    S: State,
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Index<&ID> for SpaceArena<S>
+where
+    S: State,
+{
+    type Output = Space<S>;
+
+    #[inline]
+    fn index(&self, id: &ID) -> &Space<S> {
+        &self.spaces[self.index[id] as usize]
+    }
+}
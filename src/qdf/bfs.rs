@@ -0,0 +1,52 @@
+use id::*;
+use qdf::*;
+use std::collections::{HashSet, VecDeque};
+
+/// Iterator that walks QDF spaces in breadth-first order, starting from given space.
+pub struct BfsIter<'a, S, E = ()>
+where
+    S: 'a + State,
+    E: 'a,
+{
+    qdf: &'a QDF<S, E>,
+    visited: HashSet<ID>,
+    queue: VecDeque<ID>,
+}
+
+impl<'a, S, E> BfsIter<'a, S, E>
+where
+    S: State,
+    E: Clone + Default,
+{
+    pub(crate) fn new(qdf: &'a QDF<S, E>, start: ID) -> Self {
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        if qdf.space_exists(start) {
+            visited.insert(start);
+            queue.push_back(start);
+        }
+        Self {
+            qdf,
+            visited,
+            queue,
+        }
+    }
+}
+
+impl<'a, S, E> Iterator for BfsIter<'a, S, E>
+where
+    S: State,
+    E: Clone + Default,
+{
+    type Item = ID;
+
+    fn next(&mut self) -> Option<ID> {
+        let id = self.queue.pop_front()?;
+        for n in self.qdf.graph.neighbors(id) {
+            if self.visited.insert(n) {
+                self.queue.push_back(n);
+            }
+        }
+        Some(id)
+    }
+}
@@ -0,0 +1,485 @@
+use error::*;
+use id::*;
+use petgraph::graphmap::DiGraphMap;
+use petgraph::Direction;
+use qdf::*;
+use std::collections::{HashMap, HashSet};
+
+/// Graph of directed connections between spaces.
+pub type DirectedSpaceGraph = DiGraphMap<ID, ()>;
+
+/// Object that represents quantized density fields with one-way connections between spaces.
+/// It works like `QDF` but every connection has direction, so information can flow only from
+/// space to its out-neighbors. Spaces created by subdivision are connected with each other in
+/// both directions and connections with outer spaces keep their direction.
+#[derive(Debug)]
+pub struct QDFDirected<S>
+where
+    S: State,
+{
+    id: ID,
+    graph: DirectedSpaceGraph,
+    spaces: SpaceMap<S>,
+    space_ids: HashSet<ID>,
+    dimensions: usize,
+}
+
+impl<S> QDFDirected<S>
+where
+    S: State,
+{
+    /// Creates new directed QDF information universe.
+    ///
+    /// # Arguments
+    /// * `dimensions` - Number of dimensions space contains.
+    /// * `state` - State of space.
+    ///
+    /// # Returns
+    /// Tuple of new directed QDF object and space id.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDFDirected;
+    ///
+    /// let (qdf, root) = QDFDirected::new(2, 9);
+    /// assert_eq!(*qdf.space(root).state(), 9);
+    /// ```
+    pub fn new(dimensions: usize, state: S) -> (Self, ID) {
+        let mut graph = DiGraphMap::new();
+        let mut spaces = HashMap::new();
+        let mut space_ids = HashSet::new();
+        let id = ID::new();
+        graph.add_node(id);
+        spaces.insert(id, Space::new(id, state));
+        space_ids.insert(id);
+        let qdf = Self {
+            id: ID::new(),
+            graph,
+            spaces,
+            space_ids,
+            dimensions,
+        };
+        (qdf, id)
+    }
+
+    /// Gets directed QDF id.
+    #[inline]
+    pub fn id(&self) -> ID {
+        self.id
+    }
+
+    /// Gets directed QDF dimensions number.
+    #[inline]
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Gets reference to underlying directed space graph.
+    #[inline]
+    pub fn graph(&self) -> &DirectedSpaceGraph {
+        &self.graph
+    }
+
+    /// Tells if space with given id exists in directed QDF.
+    #[inline]
+    pub fn space_exists(&self, id: ID) -> bool {
+        self.spaces.contains_key(&id)
+    }
+
+    /// Gets iterator over all spaces IDs.
+    #[inline]
+    pub fn spaces(&self) -> impl ExactSizeIterator<Item = &ID> {
+        self.space_ids.iter()
+    }
+
+    /// Try to get given space.
+    #[inline]
+    pub fn try_get_space(&self, id: ID) -> Option<&Space<S>> {
+        self.spaces.get(&id)
+    }
+
+    /// Get given space or throw error if space does not exists.
+    #[inline]
+    pub fn get_space(&self, id: ID) -> Result<&Space<S>> {
+        if let Some(space) = self.spaces.get(&id) {
+            Ok(space)
+        } else {
+            Err(QDFError::SpaceDoesNotExists(id))
+        }
+    }
+
+    /// Get given space or panic if space does not exists.
+    #[inline]
+    pub fn space(&self, id: ID) -> &Space<S> {
+        &self.spaces[&id]
+    }
+
+    /// Set given space state or throw error if space does not exists.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    /// * `state` - state.
+    ///
+    /// # Returns
+    /// `Ok` if space exists and state was successfuly set, `Err` otherwise.
+    #[inline]
+    pub fn set_space_state(&mut self, id: ID, state: S) -> Result<()> {
+        if let Some(space) = self.spaces.get_mut(&id) {
+            space.apply_state(state);
+            Ok(())
+        } else {
+            Err(QDFError::SpaceDoesNotExists(id))
+        }
+    }
+
+    /// Get list of IDs of spaces that given space points to (out-neighbors) or throws error if
+    /// space does not exists.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with vector of space out-neighbors if space exists, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDFDirected;
+    ///
+    /// let (mut qdf, root) = QDFDirected::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.disconnect_spaces(subs[0], subs[1]).unwrap();
+    /// assert_eq!(qdf.find_space_neighbors(subs[0]).unwrap(), vec![subs[2]]);
+    /// ```
+    #[inline]
+    pub fn find_space_neighbors(&self, id: ID) -> Result<Vec<ID>> {
+        self.find_space_neighbors_directed(id, Direction::Outgoing)
+    }
+
+    /// Get list of IDs of spaces that points to given space (in-neighbors) or throws error if
+    /// space does not exists.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with vector of space in-neighbors if space exists, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDFDirected;
+    ///
+    /// let (mut qdf, root) = QDFDirected::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.disconnect_spaces(subs[0], subs[1]).unwrap();
+    /// assert_eq!(qdf.find_space_predecessors(subs[1]).unwrap(), vec![subs[2]]);
+    /// assert_eq!(qdf.find_space_predecessors(subs[0]).unwrap().len(), 2);
+    /// ```
+    #[inline]
+    pub fn find_space_predecessors(&self, id: ID) -> Result<Vec<ID>> {
+        self.find_space_neighbors_directed(id, Direction::Incoming)
+    }
+
+    fn find_space_neighbors_directed(&self, id: ID, dir: Direction) -> Result<Vec<ID>> {
+        if self.space_exists(id) {
+            Ok(self.graph.neighbors_directed(id, dir).collect())
+        } else {
+            Err(QDFError::SpaceDoesNotExists(id))
+        }
+    }
+
+    /// Creates one-way connection from one space to another or throws error if any of spaces
+    /// does not exists.
+    ///
+    /// # Arguments
+    /// * `from` - source space id.
+    /// * `to` - target space id.
+    ///
+    /// # Returns
+    /// `Ok` if spaces exist, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDFDirected;
+    ///
+    /// let (mut qdf, root) = QDFDirected::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// qdf.connect_spaces(subs2[0], subs[2]).unwrap();
+    /// assert!(qdf.graph().contains_edge(subs2[0], subs[2]));
+    /// assert!(!qdf.graph().contains_edge(subs[2], subs2[0]));
+    /// ```
+    pub fn connect_spaces(&mut self, from: ID, to: ID) -> Result<()> {
+        if !self.space_exists(from) {
+            return Err(QDFError::SpaceDoesNotExists(from));
+        }
+        if !self.space_exists(to) {
+            return Err(QDFError::SpaceDoesNotExists(to));
+        }
+        self.graph.add_edge(from, to, ());
+        Ok(())
+    }
+
+    /// Removes one-way connection from one space to another (connection in opposite direction
+    /// stays untouched) or throws error if spaces are not connected that way.
+    ///
+    /// # Arguments
+    /// * `from` - source space id.
+    /// * `to` - target space id.
+    ///
+    /// # Returns
+    /// `Ok` if connection existed and was removed, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDFDirected;
+    ///
+    /// let (mut qdf, root) = QDFDirected::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert!(qdf.disconnect_spaces(subs[0], subs[1]).is_ok());
+    /// assert!(qdf.disconnect_spaces(subs[0], subs[1]).is_err());
+    /// assert!(qdf.graph().contains_edge(subs[1], subs[0]));
+    /// ```
+    pub fn disconnect_spaces(&mut self, from: ID, to: ID) -> Result<()> {
+        if !self.space_exists(from) {
+            return Err(QDFError::SpaceDoesNotExists(from));
+        }
+        if !self.space_exists(to) {
+            return Err(QDFError::SpaceDoesNotExists(to));
+        }
+        if self.graph.remove_edge(from, to).is_some() {
+            Ok(())
+        } else {
+            Err(QDFError::EdgeDoesNotExists(from, to))
+        }
+    }
+
+    /// Increases given space density (subdivide space and rebind it properly to its neighbors),
+    /// and returns process information (source space id, subdivided space ids, connections pairs)
+    /// or throws error if space does not exists. Subdivided spaces are connected with each other
+    /// in both directions, while every connection with outer space keeps its direction.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with tuple of source space id, vector of subdivided space ids and vector of
+    /// connections pairs (outer space id, subdivided space id) or `Err` if space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDFDirected;
+    ///
+    /// let (mut qdf, root) = QDFDirected::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(subs.len(), 3);
+    /// assert_eq!(qdf.graph().edge_count(), 6);
+    /// qdf.disconnect_spaces(subs[1], subs[0]).unwrap();
+    /// let (_, _, pairs) = qdf.increase_space_density(subs[0]).unwrap();
+    /// assert_eq!(pairs.len(), 2);
+    /// assert!(qdf.graph().contains_edge(pairs[0].1, pairs[0].0));
+    /// assert!(!qdf.graph().contains_edge(pairs[0].0, pairs[0].1));
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn increase_space_density(&mut self, id: ID) -> Result<(ID, Vec<ID>, Vec<(ID, ID)>)> {
+        if !self.space_exists(id) {
+            return Err(QDFError::SpaceDoesNotExists(id));
+        }
+        let substates = self.spaces[&id].state().subdivide(self.dimensions + 1);
+        let spaces = substates
+            .into_iter()
+            .map(|substate| Space::new(ID::new(), substate))
+            .collect::<Vec<Space<S>>>();
+        for s in &spaces {
+            let id = s.id();
+            self.graph.add_node(id);
+            self.space_ids.insert(id);
+            self.spaces.insert(id, s.clone());
+        }
+        for a in &spaces {
+            for b in &spaces {
+                if a.id() != b.id() {
+                    self.graph.add_edge(a.id(), b.id(), ());
+                }
+            }
+        }
+        let mut neighbors = self.graph.neighbors(id).collect::<Vec<ID>>();
+        for n in self.graph.neighbors_directed(id, Direction::Incoming) {
+            if !neighbors.contains(&n) {
+                neighbors.push(n);
+            }
+        }
+        let pairs = neighbors
+            .iter()
+            .enumerate()
+            .map(|(i, n)| {
+                let t = spaces[i % spaces.len()].id();
+                if self.graph.contains_edge(id, *n) {
+                    self.graph.add_edge(t, *n, ());
+                }
+                if self.graph.contains_edge(*n, id) {
+                    self.graph.add_edge(*n, t, ());
+                }
+                (*n, t)
+            })
+            .collect::<Vec<(ID, ID)>>();
+        self.detach_node(id);
+        self.space_ids.remove(&id);
+        self.spaces.remove(&id);
+        let space_ids = spaces.iter().map(|s| s.id()).collect::<Vec<ID>>();
+        Ok((id, space_ids, pairs))
+    }
+
+    /// Decreases given space density (merge space with its siblings and rebind merged space
+    /// properly to theirs neighbors), and returns process information (source space ids, merged
+    /// space id) or throws error if space does not exists. Merged space gets connections of all
+    /// merged spaces with outer spaces, keeping their direction.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with `Some` tuple of vector of merged space ids and created space id, or `Ok` with
+    /// `None` if space cannot be merged or `Err` if given space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDFDirected;
+    ///
+    /// let (mut qdf, root) = QDFDirected::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// qdf.disconnect_spaces(subs[1], subs2[0]).unwrap();
+    /// let (_, merged) = qdf.decrease_space_density(subs2[0]).unwrap().unwrap();
+    /// assert_eq!(qdf.spaces().count(), 3);
+    /// assert_eq!(*qdf.space(merged).state(), 3);
+    /// assert!(qdf.graph().contains_edge(merged, subs[1]));
+    /// assert!(!qdf.graph().contains_edge(subs[1], merged));
+    /// ```
+    pub fn decrease_space_density(&mut self, id: ID) -> Result<Option<(Vec<ID>, ID)>> {
+        if !self.space_exists(id) {
+            return Err(QDFError::SpaceDoesNotExists(id));
+        }
+        let neighbor = self.adjacent_spaces(id);
+        let mut connected = neighbor
+            .iter()
+            .filter(|a| {
+                neighbor.iter().any(|b| {
+                    **a != *b
+                        && (self.graph.contains_edge(**a, *b) || self.graph.contains_edge(*b, **a))
+                })
+            }).cloned()
+            .collect::<Vec<ID>>();
+        if connected.len() != self.dimensions {
+            return Ok(None);
+        }
+        connected.push(id);
+        let states = connected
+            .iter()
+            .map(|i| self.spaces[i].state())
+            .cloned()
+            .collect::<Vec<S>>();
+        let id = ID::new();
+        self.graph.add_node(id);
+        self.space_ids.insert(id);
+        self.spaces
+            .insert(id, Space::new(id, State::merge(&states)));
+        for i in &connected {
+            let outgoing = self
+                .graph
+                .neighbors(*i)
+                .filter(|n| !connected.contains(n) && *n != id)
+                .collect::<Vec<ID>>();
+            for n in outgoing {
+                self.graph.add_edge(id, n, ());
+            }
+            let incoming = self
+                .graph
+                .neighbors_directed(*i, Direction::Incoming)
+                .filter(|n| !connected.contains(n) && *n != id)
+                .collect::<Vec<ID>>();
+            for n in incoming {
+                self.graph.add_edge(n, id, ());
+            }
+        }
+        for i in &connected {
+            self.detach_node(*i);
+            self.spaces.remove(i);
+            self.space_ids.remove(i);
+        }
+        Ok(Some((connected, id)))
+    }
+
+    fn adjacent_spaces(&self, id: ID) -> Vec<ID> {
+        let mut result = self.graph.neighbors(id).collect::<Vec<ID>>();
+        for n in self.graph.neighbors_directed(id, Direction::Incoming) {
+            if !result.contains(&n) {
+                result.push(n);
+            }
+        }
+        result
+    }
+
+    // `DiGraphMap::remove_node()` leaves incoming edges in their sources, so every connection is
+    // removed explicitly before the node itself.
+    fn detach_node(&mut self, id: ID) {
+        for n in self.adjacent_spaces(id) {
+            self.graph.remove_edge(id, n);
+            self.graph.remove_edge(n, id);
+        }
+        self.graph.remove_node(id);
+    }
+
+    /// Performs simulation step (go through all spaces and modifies its states based on states
+    /// of spaces that points to them). Actual state simulation is performed by your struct that
+    /// implements `Simulation` trait.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{QDFDirected, Simulate};
+    ///
+    /// struct Flow;
+    ///
+    /// impl Simulate<i32> for Flow {
+    ///     fn simulate(state: &i32, neighbor_states: &[&i32]) -> i32 {
+    ///         state + neighbor_states.iter().map(|s| **s).sum::<i32>()
+    ///     }
+    /// }
+    ///
+    /// let (mut qdf, root) = QDFDirected::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.disconnect_spaces(subs[1], subs[0]).unwrap();
+    /// qdf.disconnect_spaces(subs[2], subs[0]).unwrap();
+    /// qdf.simulation_step::<Flow>();
+    /// assert_eq!(*qdf.space(subs[0]).state(), 3);
+    /// assert_eq!(*qdf.space(subs[1]).state(), 9);
+    /// ```
+    pub fn simulation_step<M>(&mut self)
+    where
+        M: Simulate<S>,
+    {
+        for (id, state) in self.simulate_states::<M>() {
+            self.spaces.get_mut(&id).unwrap().apply_state(state);
+        }
+    }
+
+    /// Performs simulation on directed QDF like `simulation_step()` but instead of applying
+    /// results to directed QDF, it returns simulated space states along with their space ID.
+    ///
+    /// # Returns
+    /// Vector of tuples of id and its updated space that were simulated.
+    pub fn simulate_states<M>(&self) -> Vec<(ID, S)>
+    where
+        M: Simulate<S>,
+    {
+        self.space_ids
+            .iter()
+            .map(|id| {
+                let neighbor_states = self
+                    .graph
+                    .neighbors_directed(*id, Direction::Incoming)
+                    .map(|i| self.spaces[&i].state())
+                    .collect::<Vec<&S>>();
+                (*id, M::simulate(self.spaces[id].state(), &neighbor_states))
+            }).collect()
+    }
+}
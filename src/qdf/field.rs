@@ -0,0 +1,58 @@
+use id::*;
+use std::collections::HashSet;
+
+/// Named view over set of spaces of QDF, identified by its own id.
+/// Fields follow topology changes: subdivided member is replaced by its subspaces, merged
+/// members are replaced by merged space and removed spaces are dropped from field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    id: ID,
+    spaces: HashSet<ID>,
+}
+
+impl Field {
+    #[inline]
+    pub(crate) fn new(id: ID, spaces: HashSet<ID>) -> Self {
+        Self { id, spaces }
+    }
+
+    /// Gets field id.
+    #[inline]
+    pub fn id(&self) -> ID {
+        self.id
+    }
+
+    /// Gets ids of member spaces.
+    #[inline]
+    pub fn spaces(&self) -> &HashSet<ID> {
+        &self.spaces
+    }
+
+    /// Tells if given space is member of this field.
+    #[inline]
+    pub fn contains(&self, id: ID) -> bool {
+        self.spaces.contains(&id)
+    }
+
+    /// Gets number of member spaces.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.spaces.len()
+    }
+
+    /// Tells if field has no member spaces.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.spaces.is_empty()
+    }
+
+    #[inline]
+    pub(crate) fn insert(&mut self, id: ID) -> bool {
+        self.spaces.insert(id)
+    }
+
+    #[inline]
+    pub(crate) fn remove(&mut self, id: ID) -> bool {
+        self.spaces.remove(&id)
+    }
+}
@@ -0,0 +1,321 @@
+use error::*;
+use id::*;
+use qdf::*;
+use std::collections::HashSet;
+
+// spaces along with everything that ties them to the rest of universe.
+#[derive(Debug, Clone)]
+pub(crate) struct Snapshot<S, E>
+where
+    S: State,
+{
+    spaces: Vec<Space<S>>,
+    // edges touching any of spaces, with their data.
+    edges: Vec<(ID, ID, E)>,
+    // pairs of field id and member space id.
+    fields: Vec<(ID, ID)>,
+    frozen: Vec<ID>,
+}
+
+impl<S, E> Snapshot<S, E>
+where
+    S: State,
+{
+    fn ids(&self) -> Vec<ID> {
+        self.spaces.iter().map(|space| space.id()).collect()
+    }
+}
+
+// single density operation: spaces it removed and spaces it created.
+#[derive(Debug, Clone)]
+struct Change<S, E>
+where
+    S: State,
+{
+    removed: Snapshot<S, E>,
+    created: Snapshot<S, E>,
+    // lineage entries added by operation.
+    lineage: Vec<(ID, ID)>,
+}
+
+#[derive(Debug)]
+pub(crate) struct History<S, E>
+where
+    S: State,
+{
+    undo: Vec<Change<S, E>>,
+    redo: Vec<Change<S, E>>,
+}
+
+impl<S, E> History<S, E>
+where
+    S: State,
+{
+    #[inline]
+    fn new() -> Self {
+        Self {
+            undo: vec![],
+            redo: vec![],
+        }
+    }
+}
+
+impl<S, E> QDF<S, E>
+where
+    S: State,
+    E: Clone + Default,
+{
+    /// Enables history of density operations, so they can be reverted with `undo()` and applied
+    /// again with `redo()`. Recorded operations are `increase_space_density()`,
+    /// `decrease_space_density()` and all their variants (`subdivide_to_depth()` is recorded as
+    /// single operation), and every recorded operation clears redo history.
+    /// Undo brings back exactly the same space ids, along with states, edge data, lineage, field
+    /// membership and frozen flags that spaces had right before operation.
+    ///
+    /// Every recorded operation keeps copies of spaces it removed and created (with their states)
+    /// and of all edges touching them, so memory used by history grows with number of operations
+    /// and size of state (roughly `2 * (dimensions + 1)` spaces and `(dimensions + 1)^2` edges per
+    /// subdivision or merge) until `clear_history()` is called.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (qdf, root) = QDF::new(2, 9);
+    /// let mut qdf = qdf.with_history();
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.set_space_state(subs[0], 5).unwrap();
+    /// let merged = qdf.decrease_space_density(subs[0]).unwrap().unwrap();
+    /// assert!(qdf.undo().unwrap());
+    /// assert!(!qdf.space_exists(merged.id));
+    /// assert_eq!(*qdf.space(subs[0]).state(), 5);
+    /// assert!(qdf.undo().unwrap());
+    /// assert_eq!(qdf.spaces().cloned().collect::<Vec<_>>(), vec![root]);
+    /// assert_eq!(*qdf.space(root).state(), 9);
+    /// assert!(!qdf.undo().unwrap());
+    /// assert!(qdf.redo().unwrap());
+    /// assert!(qdf.space_exists(subs[0]));
+    /// ```
+    pub fn with_history(mut self) -> Self {
+        if self.history.is_none() {
+            self.history = Some(History::new());
+        }
+        self
+    }
+
+    /// Tells if history of density operations is enabled.
+    #[inline]
+    pub fn has_history(&self) -> bool {
+        self.history.is_some()
+    }
+
+    /// Forgets all recorded operations (history stays enabled) to free memory used by them.
+    pub fn clear_history(&mut self) {
+        if let Some(history) = &mut self.history {
+            history.undo.clear();
+            history.redo.clear();
+        }
+    }
+
+    /// Tells if there is recorded operation that can be reverted.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        self.history
+            .as_ref()
+            .is_some_and(|history| !history.undo.is_empty())
+    }
+
+    /// Tells if there is reverted operation that can be applied again.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        self.history
+            .as_ref()
+            .is_some_and(|history| !history.redo.is_empty())
+    }
+
+    /// Reverts last recorded density operation (see `with_history()`).
+    ///
+    /// # Returns
+    /// `Ok` with `true` if operation was reverted, `Ok` with `false` if there is nothing to revert
+    /// (or history is not enabled), or `Err` if spaces created by operation were removed or
+    /// changed in the meantime by operation that is not recorded (then nothing is changed).
+    pub fn undo(&mut self) -> Result<bool> {
+        let mut change = match self.history.as_mut().and_then(|history| history.undo.pop()) {
+            Some(change) => change,
+            None => return Ok(false),
+        };
+        let result = self.apply_change(&mut change, false);
+        let history = self.history.as_mut().unwrap();
+        match result {
+            Ok(()) => {
+                history.redo.push(change);
+                Ok(true)
+            }
+            Err(error) => {
+                history.undo.push(change);
+                Err(error)
+            }
+        }
+    }
+
+    /// Applies again last operation reverted by `undo()`.
+    ///
+    /// # Returns
+    /// `Ok` with `true` if operation was applied, `Ok` with `false` if there is nothing to apply
+    /// (or history is not enabled), or `Err` if spaces brought back by `undo()` were removed or
+    /// changed in the meantime by operation that is not recorded (then nothing is changed).
+    pub fn redo(&mut self) -> Result<bool> {
+        let mut change = match self.history.as_mut().and_then(|history| history.redo.pop()) {
+            Some(change) => change,
+            None => return Ok(false),
+        };
+        let result = self.apply_change(&mut change, true);
+        let history = self.history.as_mut().unwrap();
+        match result {
+            Ok(()) => {
+                history.undo.push(change);
+                Ok(true)
+            }
+            Err(error) => {
+                history.redo.push(change);
+                Err(error)
+            }
+        }
+    }
+
+    // captures given spaces, or nothing when history is not enabled.
+    pub(crate) fn snapshot_for_history(&self, ids: &[ID]) -> Option<Snapshot<S, E>> {
+        if self.history.is_some() {
+            Some(self.snapshot(ids))
+        } else {
+            None
+        }
+    }
+
+    // records operation that removed spaces of given snapshot and created given spaces, along
+    // with spaces that operation created and removed again (only their lineage is recorded).
+    pub(crate) fn record_change(
+        &mut self,
+        removed: Snapshot<S, E>,
+        created: &[ID],
+        transient: &[ID],
+    ) {
+        let lineage = removed
+            .spaces
+            .iter()
+            .map(|space| space.id())
+            .chain(transient.iter().cloned())
+            .filter_map(|id| self.lineage.get(&id).map(|parent| (id, *parent)))
+            .collect();
+        let change = Change {
+            removed,
+            created: self.snapshot(created),
+            lineage,
+        };
+        if let Some(history) = &mut self.history {
+            history.undo.push(change);
+            history.redo.clear();
+        }
+    }
+
+    fn snapshot(&self, ids: &[ID]) -> Snapshot<S, E> {
+        let set = ids.iter().cloned().collect::<HashSet<ID>>();
+        let spaces = ids
+            .iter()
+            .filter_map(|id| self.spaces.get(*id))
+            .cloned()
+            .collect::<Vec<Space<S>>>();
+        let mut edges = vec![];
+        for id in ids.iter().filter(|id| self.graph.contains_node(**id)) {
+            for n in self.graph.neighbors(*id) {
+                // edge between two captured spaces is captured once.
+                if !set.contains(&n) || *id < n {
+                    edges.push((*id, n, self.graph[(*id, n)].clone()));
+                }
+            }
+        }
+        let mut fields = vec![];
+        for field in self.fields.values() {
+            for id in ids.iter().filter(|id| field.contains(**id)) {
+                fields.push((field.id(), *id));
+            }
+        }
+        let frozen = ids
+            .iter()
+            .filter(|id| self.frozen.contains(id))
+            .cloned()
+            .collect();
+        Snapshot {
+            spaces,
+            edges,
+            fields,
+            frozen,
+        }
+    }
+
+    // replaces one side of change with the other one (`forward` replaces removed spaces with
+    // created ones, like operation did). Both sides may share ids of spaces that operation kept.
+    fn apply_change(&mut self, change: &mut Change<S, E>, forward: bool) -> Result<()> {
+        let (remove, add) = if forward {
+            (&mut change.removed, &change.created)
+        } else {
+            (&mut change.created, &change.removed)
+        };
+        let remove_ids = remove.ids();
+        if let Some(id) = remove_ids.iter().find(|id| !self.space_exists(**id)) {
+            return Err(QDFError::SpaceDoesNotExists(*id));
+        }
+        let remove_set = remove_ids.iter().cloned().collect::<HashSet<ID>>();
+        let add_set = add.ids().into_iter().collect::<HashSet<ID>>();
+        if let Some(id) = add_set
+            .iter()
+            .find(|id| !remove_set.contains(id) && self.space_exists(**id))
+        {
+            return Err(QDFError::SpaceAlreadyExists(*id));
+        }
+        for (a, b, _) in &add.edges {
+            for id in &[*a, *b] {
+                if !add_set.contains(id) && (remove_set.contains(id) || !self.space_exists(*id)) {
+                    return Err(QDFError::SpaceDoesNotExists(*id));
+                }
+            }
+        }
+        // spaces that are going to be removed are captured again, so reverting this step later
+        // brings back their current states and connections.
+        *remove = self.snapshot(&remove_ids);
+        // spaces are removed first, so kept ones can be brought back with their former data.
+        for (a, b, _) in &remove.edges {
+            self.remove_edge(*a, *b);
+            self.observer.notify(QdfEvent::EdgeRemoved(*a, *b));
+        }
+        for id in remove_ids {
+            self.graph.remove_node(id);
+            self.spaces.remove(id);
+            self.replace_in_fields(id, &[]);
+            self.observer.notify(QdfEvent::SpaceRemoved(id));
+        }
+        for space in &add.spaces {
+            self.graph.add_node(space.id());
+            self.spaces.insert(space.clone());
+            self.observer.notify(QdfEvent::SpaceCreated(space.id()));
+        }
+        for (a, b, data) in &add.edges {
+            self.add_edge(*a, *b, data.clone());
+            self.observer.notify(QdfEvent::EdgeAdded(*a, *b));
+        }
+        for (field, id) in &add.fields {
+            if let Some(field) = self.fields.get_mut(field) {
+                field.insert(*id);
+            }
+        }
+        self.frozen.extend(add.frozen.iter().cloned());
+        for (id, parent) in &change.lineage {
+            if forward {
+                self.lineage.insert(*id, *parent);
+            } else {
+                self.lineage.remove(id);
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,46 +1,125 @@
+mod arena;
+pub mod bfs;
+pub mod directed;
+pub mod field;
+mod history;
+pub mod observer;
+mod path;
+mod project;
 pub mod simulate;
 pub mod space;
 pub mod state;
 mod tests;
+pub mod topology;
 
+pub use self::bfs::*;
+pub use self::directed::*;
+pub use self::field::*;
+pub use self::observer::*;
 pub use self::simulate::*;
 pub use self::space::*;
 pub use self::state::*;
+pub use self::topology::*;
+use self::arena::SpaceArena;
+use self::history::History;
+use bytes::*;
 use error::*;
+use graph::{escape_dot, ordered_edges};
 use id::*;
+use json::*;
 use petgraph::algo::astar;
 use petgraph::graphmap::UnGraphMap;
 use rayon::prelude::*;
-use std::collections::hash_set::Iter;
-use std::collections::{HashMap, HashSet};
+use rayon::ThreadPool;
+#[cfg(feature = "serde")]
+use serde::de::Error as DeError;
+#[cfg(feature = "serde")]
+use serde::ser::SerializeStruct;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::{Ordering, Reverse};
+use std::collections::hash_map::Keys;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt::Write;
+use std::io;
+use std::result::Result as StdResult;
 
 /// Short hand type alias for space graph.
-pub type SpaceGraph = UnGraphMap<ID, ()>;
+pub type SpaceGraph<E = ()> = UnGraphMap<ID, E>;
 /// Short hand type alias for space map.
 pub type SpaceMap<S> = HashMap<ID, Space<S>>;
 
+/// Process information of space density decrease.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeInfo {
+    /// Ids of spaces that were merged (and removed).
+    pub merged: Vec<ID>,
+    /// Id of space created from merged spaces.
+    pub id: ID,
+    /// Edges removed together with merged spaces.
+    pub removed_edges: Vec<(ID, ID)>,
+    /// Edges added between created space and neighbors of merged spaces.
+    pub added_edges: Vec<(ID, ID)>,
+}
+
+// space waiting for subdivision, ordered so that the highest priority pops first (ties pop in
+// order of ids, so refinement does not depend on hash map order).
+struct MaxPriority(f64, ID);
+
+impl PartialEq for MaxPriority {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MaxPriority {}
+
+impl PartialOrd for MaxPriority {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MaxPriority {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .total_cmp(&other.0)
+            .then_with(|| other.1.cmp(&self.1))
+    }
+}
+
 /// Object that represents quantized density fields.
 ///
 /// # Concept
 /// QDF does not exists in any space - it IS the space, it defines it,
 /// it describes it so there are no space coordinates and it is your responsibility to deliver it.
-/// In future releases this crate will have module for projecting QDF into Euclidean space
-/// and will have a satelite crate to easlyy traverse and visualize space.
+/// You can project QDF into Euclidean plane with `project_2d()` to visualize it, and in future
+/// releases this crate will have a satelite crate to easlyy traverse and visualize space.
 ///
 /// To sample specified region you have to know some space ID and gather the rest of information
 /// based on it neighbors spaces.
 /// It gives the ability to cotrol space density at specified locations, which can be used
 /// for example to simulate space curvature based on gravity.
+///
+/// Edges between spaces can hold custom data of type `E` (`()` by default).
 #[derive(Debug)]
-pub struct QDF<S>
+pub struct QDF<S, E = ()>
 where
     S: State,
 {
     id: ID,
-    graph: SpaceGraph,
-    spaces: SpaceMap<S>,
-    space_ids: HashSet<ID>,
+    graph: SpaceGraph<E>,
+    spaces: SpaceArena<S>,
+    // parents of spaces that were already subdivided (and removed).
+    lineage: HashMap<ID, ID>,
+    fields: HashMap<ID, Field>,
+    // spaces which states are not changed by simulation.
+    frozen: HashSet<ID>,
+    topology: SubdivisionTopology,
+    // recorded density operations, when enabled with `with_history()`.
+    history: Option<History<S, E>>,
     dimensions: usize,
+    observer: ObserverSlot,
 }
 
 impl<S> QDF<S>
@@ -65,21 +144,54 @@ where
     /// assert_eq!(*qdf.space(root).state(), 9);
     /// ```
     pub fn new(dimensions: usize, state: S) -> (Self, ID) {
-        let mut graph = UnGraphMap::new();
-        let mut spaces = HashMap::new();
-        let mut space_ids = HashSet::new();
-        let id = ID::new();
-        graph.add_node(id);
-        spaces.insert(id, Space::new(id, state));
-        space_ids.insert(id);
-        let qdf = Self {
-            id: ID::new(),
-            graph,
-            spaces,
-            space_ids,
-            dimensions,
-        };
-        (qdf, id)
+        Self::new_with_id_source(dimensions, state, 0, ID::new)
+    }
+
+    /// Creates new QDF information universe with identifiers taken from given generator.
+    /// Use it together with `increase_space_density_with()` and `decrease_space_density_with()`
+    /// to get reproducible results.
+    ///
+    /// # Arguments
+    /// * `dimensions` - Number of dimensions space contains.
+    /// * `state` - State of space.
+    /// * `gen` - Identifiers generator.
+    ///
+    /// # Returns
+    /// Tuple of new QDF object and space id.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{IdGenerator, QDF};
+    ///
+    /// let (_, a) = QDF::new_with_generator(2, 9, &mut IdGenerator::new(42));
+    /// let (_, b) = QDF::new_with_generator(2, 9, &mut IdGenerator::new(42));
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn new_with_generator(dimensions: usize, state: S, gen: &mut IdGenerator) -> (Self, ID) {
+        Self::new_with_id_source(dimensions, state, 0, || gen.generate())
+    }
+
+    /// Creates new QDF information universe with storage pre-sized for given number of spaces,
+    /// so universe can grow up to that size without reallocating its containers.
+    ///
+    /// # Arguments
+    /// * `dimensions` - Number of dimensions space contains.
+    /// * `state` - State of space.
+    /// * `capacity` - Expected number of spaces.
+    ///
+    /// # Returns
+    /// Tuple of new QDF object and space id.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::with_capacity(2, 81, 81);
+    /// let spaces = qdf.subdivide_to_depth(root, 4).unwrap();
+    /// assert_eq!(spaces.len(), 81);
+    /// ```
+    pub fn with_capacity(dimensions: usize, state: S, capacity: usize) -> (Self, ID) {
+        Self::new_with_id_source(dimensions, state, capacity, ID::new)
     }
 
     /// Creates new QDF information universe and increase its levels of density.
@@ -106,7 +218,8 @@ where
     /// );
     /// ```
     pub fn with_levels(dimensions: usize, state: S, levels: usize) -> (Self, Vec<ID>) {
-        let (mut qdf, _) = Self::new(dimensions, state);
+        let capacity = (dimensions + 1).saturating_pow(levels as u32);
+        let (mut qdf, _) = Self::with_capacity(dimensions, state, capacity);
         for _ in 0..levels {
             let spaces = qdf.spaces().cloned().collect::<Vec<ID>>();
             for id in spaces {
@@ -117,6 +230,49 @@ where
         (qdf, spaces)
     }
 
+    /// Creates new QDF information universe and greedily increases density of space with the
+    /// highest state until there are at least `target` spaces. It gives non-uniform density,
+    /// concentrated where states are high. States must be comparable (`PartialOrd`), which all
+    /// numeric states are.
+    ///
+    /// # Arguments
+    /// * `dimensions` - Number of dimensions which space contains.
+    /// * `state` - State of space.
+    /// * `target` - Minimal number of spaces.
+    ///
+    /// # Returns
+    /// Tuple of new QDF object and vector of space ids.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (qdf, spaces) = QDF::with_target_count(2, 90.0, 6);
+    /// assert_eq!(spaces.len(), 7);
+    /// let mut states = spaces.iter().map(|id| *qdf.space(*id).state()).collect::<Vec<f64>>();
+    /// states.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    /// assert_eq!(states, vec![10.0, 10.0, 10.0, 10.0, 10.0, 10.0, 30.0]);
+    /// ```
+    pub fn with_target_count(dimensions: usize, state: S, target: usize) -> (Self, Vec<ID>)
+    where
+        S: PartialOrd,
+    {
+        let (mut qdf, _) = Self::new(dimensions, state);
+        // subdivision of 0-dimensional space does not increase number of spaces.
+        while dimensions > 0 && qdf.spaces.len() < target {
+            let id = qdf
+                .graph
+                .nodes()
+                .fold(None, |best: Option<ID>, id| match best {
+                    Some(b) if qdf.spaces[&b].state() >= qdf.spaces[&id].state() => Some(b),
+                    _ => Some(id),
+                }).unwrap();
+            qdf.increase_space_density(id).unwrap();
+        }
+        let spaces = qdf.graph.nodes().collect();
+        (qdf, spaces)
+    }
+
     /// Creates new QDF information universe and increase its levels of density and state applied
     /// to lowest space lavel.
     ///
@@ -150,398 +306,3379 @@ where
         Self::with_levels(dimensions, state.super_state_at_level(dimensions, levels), levels)
     }
 
-    /// Gets QDF id.
-    #[inline]
-    pub fn id(&self) -> ID {
-        self.id
-    }
-
-    /// Gets QDF dimensions number.
+    /// Encodes QDF into compact binary snapshot.
+    /// States are encoded with `State::encode()`.
     ///
     /// # Returns
-    /// Number of dimensions (axes) space has.
+    /// Vector of bytes that can be decoded with `QDF::from_bytes()`.
+    ///
+    /// # Panics
+    /// When state type does not implement `State::encode()`.
     ///
     /// # Examples
     /// ```
     /// use quantized_density_fields::QDF;
     ///
-    /// let (qdf, _) = QDF::new(2, 9);
-    /// assert_eq!(qdf.dimensions(), 2);
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// qdf.increase_space_density(root).unwrap();
+    /// let bytes = qdf.to_bytes();
+    /// let qdf2 = QDF::<i32>::from_bytes(&bytes).unwrap();
+    /// assert_eq!(qdf2.spaces().count(), 3);
     /// ```
-    #[inline]
-    pub fn dimensions(&self) -> usize {
-        self.dimensions
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let nodes = self.graph.nodes().collect::<Vec<ID>>();
+        let indices = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect::<HashMap<ID, usize>>();
+        let edges = ordered_edges(&self.graph);
+        let mut buf = vec![];
+        write_id(&mut buf, self.id);
+        write_usize(&mut buf, self.dimensions);
+        write_usize(&mut buf, nodes.len());
+        for id in &nodes {
+            write_id(&mut buf, *id);
+        }
+        write_usize(&mut buf, edges.len());
+        for (a, b) in &edges {
+            write_usize(&mut buf, indices[a]);
+            write_usize(&mut buf, indices[b]);
+        }
+        write_usize(&mut buf, self.spaces.len());
+        let mut state = vec![];
+        for (i, id) in nodes.iter().enumerate() {
+            if let Some(space) = self.spaces.get(*id) {
+                state.clear();
+                space.state().encode(&mut state);
+                write_usize(&mut buf, i);
+                write_usize(&mut buf, state.len());
+                buf.extend_from_slice(&state);
+            }
+        }
+        buf
     }
 
-    /// Tells if space with given id exists in QDF.
+    /// Decodes QDF from binary snapshot made with `QDF::to_bytes()`.
+    /// States are decoded with `State::decode()`.
     ///
     /// # Arguments
-    /// * `id` - space id.
+    /// * `data` - snapshot bytes.
     ///
     /// # Returns
-    /// `true` if given space exists, `false` otherwise.
+    /// `Ok` with decoded QDF or `Err` with `QDFError::DecodeError` if data is invalid.
+    ///
+    /// # Panics
+    /// When state type does not implement `State::decode()`.
     ///
     /// # Examples
     /// ```
     /// use quantized_density_fields::QDF;
     ///
     /// let (qdf, root) = QDF::new(2, 9);
-    /// assert!(qdf.space_exists(root));
+    /// let qdf2 = QDF::<i32>::from_bytes(&qdf.to_bytes()).unwrap();
+    /// assert_eq!(*qdf2.space(root).state(), 9);
+    /// assert!(QDF::<i32>::from_bytes(&[1, 2, 3]).is_err());
     /// ```
-    #[inline]
-    pub fn space_exists(&self, id: ID) -> bool {
-        self.spaces.contains_key(&id)
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let mut reader = BytesReader::new(data);
+        let id = reader.read_id()?;
+        let dimensions = reader.read_usize()?;
+        let nodes_count = reader.read_usize()?;
+        let mut nodes = vec![];
+        let mut graph = UnGraphMap::new();
+        for _ in 0..nodes_count {
+            let id = reader.read_id()?;
+            if graph.contains_node(id) {
+                return Err(QDFError::DecodeError(format!("node {} is duplicated", id)));
+            }
+            graph.add_node(id);
+            nodes.push(id);
+        }
+        let node = |index: usize| {
+            if let Some(id) = nodes.get(index) {
+                Ok(*id)
+            } else {
+                Err(QDFError::DecodeError(format!(
+                    "node index {} is out of bounds",
+                    index
+                )))
+            }
+        };
+        let edges_count = reader.read_usize()?;
+        for _ in 0..edges_count {
+            let a = node(reader.read_usize()?)?;
+            let b = node(reader.read_usize()?)?;
+            if a == b {
+                return Err(QDFError::DecodeError(format!(
+                    "edge connects node {} with itself",
+                    a
+                )));
+            }
+            graph.add_edge(a, b, ());
+        }
+        let spaces_count = reader.read_usize()?;
+        if spaces_count != nodes.len() {
+            return Err(QDFError::DecodeError(format!(
+                "snapshot has {} spaces for {} nodes",
+                spaces_count,
+                nodes.len()
+            )));
+        }
+        // every space takes at least 16 bytes (node index and state size), so count read from
+        // broken data cannot make huge allocation.
+        let mut spaces = Vec::with_capacity(spaces_count.min(reader.remaining() / 16));
+        let mut decoded = vec![false; nodes.len()];
+        for _ in 0..spaces_count {
+            let index = reader.read_usize()?;
+            let id = node(index)?;
+            if decoded[index] {
+                return Err(QDFError::DecodeError(format!(
+                    "space of node {} is duplicated",
+                    id
+                )));
+            }
+            decoded[index] = true;
+            let size = reader.read_usize()?;
+            let state = S::decode(reader.read(size)?)?;
+            spaces.push(Space::new(id, state));
+        }
+        if !reader.is_empty() {
+            return Err(QDFError::DecodeError(
+                "unexpected data after snapshot end".to_owned(),
+            ));
+        }
+        let spaces = SpaceArena::from_graph(spaces, &graph);
+        Ok(Self {
+            id,
+            graph,
+            spaces,
+            dimensions,
+            lineage: HashMap::new(),
+            fields: HashMap::new(),
+            frozen: HashSet::new(),
+            topology: SubdivisionTopology::default(),
+            history: None,
+            observer: ObserverSlot::default(),
+        })
     }
 
-    /// Gets iterator over all spaces IDs.
+    /// Streams QDF as JSON document into given writer, one space and one edge at a time, so even
+    /// huge universes can be saved without building whole document in memory. Document holds
+    /// the same data as `to_bytes()` snapshot: QDF id, dimensions, spaces with their states
+    /// (encoded with `State::encode()` and written as hex strings) and edges between spaces.
+    /// Writer is not buffered by this function, so wrap file or socket in `BufWriter`.
+    ///
+    /// # Arguments
+    /// * `writer` - target writer.
     ///
     /// # Returns
-    /// Iterator over all space ids.
+    /// `Ok` if document was written, or `Err` with writer error.
     ///
     /// # Examples
     /// ```
-    /// use quantized_density_fields::{QDF, ID};
+    /// use quantized_density_fields::QDF;
     ///
-    /// let (mut qdf, root) = QDF::new(2, 9);
-    /// assert_eq!(qdf.spaces().count(), 1);
-    /// assert_eq!(*qdf.spaces().nth(0).unwrap(), root);
-    /// let (_, mut subs, _) = qdf.increase_space_density(root).unwrap();
-    /// subs.sort();
-    /// assert_eq!(qdf.spaces().count(), 3);
-    /// let mut spaces = qdf.spaces().cloned().collect::<Vec<ID>>();
-    /// spaces.sort();
-    /// assert_eq!(spaces, subs);
+    /// let (qdf, root) = QDF::new(2, 9);
+    /// let mut json = vec![];
+    /// qdf.write_json(&mut json).unwrap();
+    /// let json = String::from_utf8(json).unwrap();
+    /// assert!(json.contains(&format!("{{\"id\":\"{}\",\"state\":", root)));
     /// ```
-    #[inline]
-    pub fn spaces(&self) -> Iter<ID> {
-        self.space_ids.iter()
+    pub fn write_json<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        write!(
+            writer,
+            "{{\"id\":\"{}\",\"dimensions\":{},\"spaces\":[",
+            self.id, self.dimensions
+        )?;
+        let mut state = vec![];
+        for (i, space) in self.spaces.spaces().iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b",")?;
+            }
+            write!(writer, "\n{{\"id\":\"{}\",\"state\":\"", space.id())?;
+            state.clear();
+            space.state().encode(&mut state);
+            write_hex(writer, &state)?;
+            writer.write_all(b"\"}")?;
+        }
+        writer.write_all(b"],\"edges\":[")?;
+        for (i, (a, b, _)) in self.graph.all_edges().enumerate() {
+            if i > 0 {
+                writer.write_all(b",")?;
+            }
+            write!(writer, "\n[\"{}\",\"{}\"]", a, b)?;
+        }
+        writer.write_all(b"]}\n")
     }
 
-    /// Try to get given space.
+    /// Parses QDF from JSON document made with `QDF::write_json()`, reading one space and one
+    /// edge at a time. States are decoded with `State::decode()`. Reader is buffered internally
+    /// and is read until its end, so it must not contain anything after the document.
     ///
     /// # Arguments
-    /// * `id` - space id.
+    /// * `reader` - source reader.
     ///
     /// # Returns
-    /// `Some` reference to given `Space` data or `None` if space does not exists.
+    /// `Ok` with parsed QDF, or `Err` with reader error or with `io::ErrorKind::InvalidData` error
+    /// if document is invalid.
     ///
     /// # Examples
     /// ```
     /// use quantized_density_fields::QDF;
+    /// use std::io::Cursor;
     ///
-    /// let (qdf, root) = QDF::new(2, 9);
-    /// if let Some(space) = qdf.try_get_space(root) {
-    ///     assert_eq!(*space.state(), 9);
-    /// }
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// qdf.increase_space_density(root).unwrap();
+    /// let mut cursor = Cursor::new(vec![]);
+    /// qdf.write_json(&mut cursor).unwrap();
+    /// cursor.set_position(0);
+    /// let qdf2 = QDF::<i32>::read_json(&mut cursor).unwrap();
+    /// assert!(qdf == qdf2);
+    /// assert!(QDF::<i32>::read_json(&mut Cursor::new(b"{}")).is_err());
     /// ```
-    #[inline]
-    pub fn try_get_space(&self, id: ID) -> Option<&Space<S>> {
-        self.spaces.get(&id)
+    pub fn read_json<R>(reader: &mut R) -> io::Result<Self>
+    where
+        R: io::Read,
+    {
+        let mut reader = JsonReader::new(reader);
+        let mut id = None;
+        let mut dimensions = None;
+        let mut spaces = vec![];
+        let mut graph = UnGraphMap::new();
+        let mut has_spaces = false;
+        let mut has_edges = false;
+        reader.expect(b'{')?;
+        let mut first = true;
+        while reader.next_item(b'}', &mut first)? {
+            match reader.read_key()?.as_str() {
+                "id" => id = Some(Self::read_json_id(&mut reader)?),
+                "dimensions" => dimensions = Some(reader.read_usize()?),
+                "spaces" => {
+                    has_spaces = true;
+                    reader.expect(b'[')?;
+                    let mut first = true;
+                    while reader.next_item(b']', &mut first)? {
+                        let space = Self::read_json_space(&mut reader)?;
+                        if graph.contains_node(space.id()) {
+                            let reason = format!("duplicated space {}", space.id());
+                            return Err(reader.error(&reason));
+                        }
+                        graph.add_node(space.id());
+                        spaces.push(space);
+                    }
+                }
+                "edges" => {
+                    has_edges = true;
+                    reader.expect(b'[')?;
+                    let mut first = true;
+                    while reader.next_item(b']', &mut first)? {
+                        reader.expect(b'[')?;
+                        let a = Self::read_json_id(&mut reader)?;
+                        reader.expect(b',')?;
+                        let b = Self::read_json_id(&mut reader)?;
+                        reader.expect(b']')?;
+                        graph.add_edge(a, b, ());
+                    }
+                }
+                key => return Err(reader.error(&format!("unexpected key `{}`", key))),
+            }
+        }
+        reader.finish()?;
+        let (id, dimensions) = match (id, dimensions, has_spaces, has_edges) {
+            (Some(id), Some(dimensions), true, true) => (id, dimensions),
+            _ => return Err(reader.error("document lacks some of required keys")),
+        };
+        // edges are added along with missing nodes, so any extra node is unknown space.
+        if graph.node_count() != spaces.len() {
+            return Err(reader.error("edge points to unknown space"));
+        }
+        if let Some(a) = graph.nodes().find(|a| graph.contains_edge(*a, *a)) {
+            return Err(reader.error(&format!("space {} is connected with itself", a)));
+        }
+        let spaces = SpaceArena::from_graph(spaces, &graph);
+        Ok(Self {
+            id,
+            graph,
+            spaces,
+            dimensions,
+            lineage: HashMap::new(),
+            fields: HashMap::new(),
+            frozen: HashSet::new(),
+            topology: SubdivisionTopology::default(),
+            history: None,
+            observer: ObserverSlot::default(),
+        })
     }
 
-    /// Get given space or throw error if space does not exists.
+    fn read_json_id<R>(reader: &mut JsonReader<R>) -> io::Result<ID>
+    where
+        R: io::Read,
+    {
+        let text = reader.read_string()?;
+        text.parse()
+            .map_err(|_| reader.error(&format!("invalid ID `{}`", text)))
+    }
+
+    fn read_json_space<R>(reader: &mut JsonReader<R>) -> io::Result<Space<S>>
+    where
+        R: io::Read,
+    {
+        let mut id = None;
+        let mut state = None;
+        reader.expect(b'{')?;
+        let mut first = true;
+        while reader.next_item(b'}', &mut first)? {
+            match reader.read_key()?.as_str() {
+                "id" => id = Some(Self::read_json_id(reader)?),
+                "state" => {
+                    let text = reader.read_string()?;
+                    let data =
+                        decode_hex(&text).ok_or_else(|| reader.error("invalid state hex"))?;
+                    let decoded = S::decode(&data);
+                    state = Some(decoded.map_err(|error| reader.error(&error.to_string()))?);
+                }
+                key => return Err(reader.error(&format!("unexpected key `{}`", key))),
+            }
+        }
+        match (id, state) {
+            (Some(id), Some(state)) => Ok(Space::new(id, state)),
+            _ => Err(reader.error("space lacks id or state")),
+        }
+    }
+}
+
+impl<S, E> QDF<S, E>
+where
+    S: State,
+    E: Clone + Default,
+{
+    /// Creates new QDF information universe with custom edge data type.
+    /// Newly created edges gets `E::default()` data.
     ///
     /// # Arguments
-    /// * `id` - space id.
+    /// * `dimensions` - Number of dimensions space contains.
+    /// * `state` - State of space.
     ///
     /// # Returns
-    /// `Ok` with reference to given `Space` data or `Err` if space does not exists.
+    /// Tuple of new QDF object and space id.
     ///
     /// # Examples
     /// ```
     /// use quantized_density_fields::QDF;
     ///
-    /// let (qdf, root) = QDF::new(2, 9);
-    /// if let Ok(space) = qdf.get_space(root) {
-    ///     assert_eq!(*space.state(), 9);
-    /// }
+    /// let (mut qdf, root) = QDF::<i32, f32>::new_with_edge_data(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(qdf.edge_data(subs[0], subs[1]), Some(&0.0));
     /// ```
     #[inline]
-    pub fn get_space(&self, id: ID) -> Result<&Space<S>> {
-        if let Some(space) = self.spaces.get(&id) {
-            Ok(space)
-        } else {
-            Err(QDFError::SpaceDoesNotExists(id))
-        }
+    pub fn new_with_edge_data(dimensions: usize, state: S) -> (Self, ID) {
+        Self::new_with_id_source(dimensions, state, 0, ID::new)
     }
 
-    /// Get given space or panic if space does not exists.
+    /// Creates new QDF information universe directly from spaces and connections between them,
+    /// without going through subdivision. Edges gets `E::default()` data.
     ///
     /// # Arguments
-    /// * `id` - space id.
+    /// * `dimensions` - Number of dimensions space contains.
+    /// * `nodes` - Pairs of space id and its state.
+    /// * `edges` - Pairs of connected space ids.
     ///
     /// # Returns
-    /// Reference to `Space` data.
-    ///
-    /// # Panics
-    /// When given space does not exists.
+    /// `Ok` with new QDF, or `Err` with `QDFError::SpaceAlreadyExists` if some space id is listed
+    /// twice or `QDFError::InvalidEdge` with first edge that points to unknown space or connects
+    /// space with itself.
     ///
     /// # Examples
     /// ```
-    /// use quantized_density_fields::QDF;
+    /// use quantized_density_fields::{ID, QDFError, QDF};
     ///
-    /// let (qdf, root) = QDF::new(2, 9);
-    /// assert_eq!(*qdf.space(root).state(), 9);
+    /// let (a, b, c) = (ID::new(), ID::new(), ID::new());
+    /// let qdf = QDF::<i32>::from_parts(2, vec![(a, 1), (b, 2), (c, 3)], vec![(a, b), (b, c)])
+    ///     .unwrap();
+    /// assert_eq!(qdf.find_space_neighbors(b).unwrap().len(), 2);
+    /// assert_eq!(*qdf.space(c).state(), 3);
+    /// match QDF::<i32>::from_parts(2, vec![(a, 1)], vec![(a, b)]) {
+    ///     Err(QDFError::InvalidEdge(x, y)) => assert_eq!((x, y), (a, b)),
+    ///     _ => unreachable!(),
+    /// }
     /// ```
+    pub fn from_parts(
+        dimensions: usize,
+        nodes: Vec<(ID, S)>,
+        edges: Vec<(ID, ID)>,
+    ) -> Result<Self> {
+        let mut graph = SpaceGraph::with_capacity(nodes.len(), edges.len());
+        let mut spaces = Vec::with_capacity(nodes.len());
+        for (id, state) in nodes {
+            if graph.contains_node(id) {
+                return Err(QDFError::SpaceAlreadyExists(id));
+            }
+            graph.add_node(id);
+            spaces.push(Space::new(id, state));
+        }
+        for (a, b) in edges {
+            if a == b || !graph.contains_node(a) || !graph.contains_node(b) {
+                return Err(QDFError::InvalidEdge(a, b));
+            }
+            graph.add_edge(a, b, E::default());
+        }
+        let spaces = SpaceArena::from_graph(spaces, &graph);
+        Ok(Self {
+            id: ID::new(),
+            graph,
+            spaces,
+            dimensions,
+            lineage: HashMap::new(),
+            fields: HashMap::new(),
+            frozen: HashSet::new(),
+            topology: SubdivisionTopology::default(),
+            history: None,
+            observer: ObserverSlot::default(),
+        })
+    }
+
+    fn new_with_id_source<F>(
+        dimensions: usize,
+        state: S,
+        capacity: usize,
+        mut new_id: F,
+    ) -> (Self, ID)
+    where
+        F: FnMut() -> ID,
+    {
+        // uniformly subdivided space has about `(dimensions + 1) / 2` edges per space.
+        let edges = capacity.saturating_mul(dimensions + 1) / 2;
+        let mut graph = UnGraphMap::with_capacity(capacity, edges);
+        let mut spaces = SpaceArena::with_capacity(capacity);
+        let id = new_id();
+        graph.add_node(id);
+        spaces.insert(Space::new(id, state));
+        let qdf = Self {
+            id: new_id(),
+            graph,
+            spaces,
+            dimensions,
+            // every subdivision adds `dimensions` spaces and one lineage entry.
+            lineage: HashMap::with_capacity(capacity / dimensions.max(1)),
+            fields: HashMap::new(),
+            frozen: HashSet::new(),
+            topology: SubdivisionTopology::default(),
+            history: None,
+            observer: ObserverSlot::default(),
+        };
+        (qdf, id)
+    }
+
+    /// Gets QDF id.
     #[inline]
-    pub fn space(&self, id: ID) -> &Space<S> {
-        &self.spaces[&id]
+    pub fn id(&self) -> ID {
+        self.id
     }
 
-    /// Try to set given space state.
-    ///
-    /// # Arguments
-    /// * `id` - space id.
-    /// * `state` - state.
+    /// Gets QDF dimensions number.
     ///
     /// # Returns
-    /// `true` if space exists and state was successfuly set, `false` otherwise.
+    /// Number of dimensions (axes) space has.
     ///
     /// # Examples
     /// ```
     /// use quantized_density_fields::QDF;
     ///
-    /// let (mut qdf, root) = QDF::new(2, 9);
-    /// assert!(qdf.try_set_space_state(root, 3));
+    /// let (qdf, _) = QDF::new(2, 9);
+    /// assert_eq!(qdf.dimensions(), 2);
     /// ```
     #[inline]
-    pub fn try_set_space_state(&mut self, id: ID, state: S) -> bool {
-        self.set_space_state(id, state).is_ok()
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
     }
 
-    /// Set given space state or throw error if space does not exists.
+    /// Gets number of subspaces that space is subdivided into by `increase_space_density()`
+    /// (`dimensions + 1`), which is also number of spaces merged together by
+    /// `decrease_space_density()`.
     ///
-    /// # Arguments
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// assert_eq!(qdf.subdivision_count(), 3);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(subs.len(), qdf.subdivision_count());
+    /// ```
+    #[inline]
+    pub fn subdivision_count(&self) -> usize {
+        self.dimensions + 1
+    }
+
+    /// Gets reference to underlying space graph, so it can be used with petgraph algorithms.
+    ///
+    /// # Returns
+    /// Reference to space graph.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(qdf.graph().edge_count(), 3);
+    /// assert!(qdf.graph().contains_edge(subs[0], subs[1]));
+    /// ```
+    #[inline]
+    pub fn graph(&self) -> &SpaceGraph<E> {
+        &self.graph
+    }
+
+    /// Sets callback that gets notified about spaces and edges created or removed by
+    /// `increase_space_density()` and `decrease_space_density()` (and their variants). Events of
+    /// single operation are reported in order: created spaces and added edges first, then removed
+    /// edges and spaces.
+    ///
+    /// # Arguments
+    /// * `observer` - callback that receives events.
+    ///
+    /// # Returns
+    /// Previously set observer if there was any.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{QdfEvent, QDF};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let events = Arc::new(Mutex::new(vec![]));
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let events2 = events.clone();
+    /// qdf.set_observer(Box::new(move |event| events2.lock().unwrap().push(event)));
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let events = events.lock().unwrap();
+    /// assert_eq!(events[0], QdfEvent::SpaceCreated(subs[0]));
+    /// assert_eq!(events[3], QdfEvent::EdgeAdded(subs[0], subs[1]));
+    /// assert_eq!(*events.last().unwrap(), QdfEvent::SpaceRemoved(root));
+    /// ```
+    #[inline]
+    pub fn set_observer(&mut self, observer: Observer) -> Option<Observer> {
+        self.observer.set(Some(observer))
+    }
+
+    /// Removes callback that gets notified about QDF structure changes.
+    ///
+    /// # Returns
+    /// Previously set observer if there was any.
+    #[inline]
+    pub fn clear_observer(&mut self) -> Option<Observer> {
+        self.observer.set(None)
+    }
+
+    /// Sets topology of connections between subspaces created by subsequent subdivisions
+    /// (`SubdivisionTopology::Complete` by default). See `SubdivisionTopology` for invariants that
+    /// only complete topology conserves. Topology is not kept by snapshots.
+    ///
+    /// # Arguments
+    /// * `topology` - topology of subspaces connections.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{SubdivisionTopology, QDF};
+    ///
+    /// let (mut qdf, root) = QDF::new(3, 16);
+    /// qdf.set_subdivision_topology(SubdivisionTopology::Ring);
+    /// assert_eq!(qdf.subdivision_topology(), SubdivisionTopology::Ring);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(qdf.find_space_neighbors(subs[0]).unwrap().len(), 2);
+    /// assert!(qdf.decrease_space_density(subs[0]).unwrap().is_none());
+    /// ```
+    #[inline]
+    pub fn set_subdivision_topology(&mut self, topology: SubdivisionTopology) {
+        self.topology = topology;
+    }
+
+    /// Gets topology of connections between subspaces created by subdivisions.
+    #[inline]
+    pub fn subdivision_topology(&self) -> SubdivisionTopology {
+        self.topology
+    }
+
+    /// Tells if space with given id exists in QDF.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `true` if given space exists, `false` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (qdf, root) = QDF::new(2, 9);
+    /// assert!(qdf.space_exists(root));
+    /// ```
+    #[inline]
+    pub fn space_exists(&self, id: ID) -> bool {
+        self.spaces.contains(id)
+    }
+
+    /// Gets iterator over all spaces IDs.
+    ///
+    /// # Returns
+    /// Iterator over all space ids.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{QDF, ID};
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// assert_eq!(qdf.spaces().count(), 1);
+    /// assert_eq!(*qdf.spaces().nth(0).unwrap(), root);
+    /// let (_, mut subs, _) = qdf.increase_space_density(root).unwrap();
+    /// subs.sort();
+    /// assert_eq!(qdf.spaces().count(), 3);
+    /// let mut spaces = qdf.spaces().cloned().collect::<Vec<ID>>();
+    /// spaces.sort();
+    /// assert_eq!(spaces, subs);
+    /// ```
+    #[inline]
+    pub fn spaces(&self) -> impl ExactSizeIterator<Item = &ID> {
+        self.spaces.ids().iter()
+    }
+
+    /// Gets number of spaces.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// assert_eq!(qdf.space_count(), 1);
+    /// qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(qdf.space_count(), 3);
+    /// ```
+    #[inline]
+    pub fn space_count(&self) -> usize {
+        self.spaces.len()
+    }
+
+    /// Gets list of all connections between spaces. Every connection is listed once, as pair
+    /// with lower space id first.
+    ///
+    /// # Returns
+    /// Vector of connected space ids pairs.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// assert!(qdf.edges().is_empty());
+    /// qdf.increase_space_density(root).unwrap();
+    /// let edges = qdf.edges();
+    /// assert_eq!(edges.len(), 3);
+    /// assert!(edges.iter().all(|(a, b)| a < b));
+    /// ```
+    pub fn edges(&self) -> Vec<(ID, ID)> {
+        self.graph
+            .all_edges()
+            .map(|(a, b, _)| if a < b { (a, b) } else { (b, a) })
+            .collect()
+    }
+
+    /// Gets iterator over all spaces IDs along with their spaces.
+    ///
+    /// # Returns
+    /// Iterator over tuples of space id and reference to `Space` data.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(qdf.iter_spaces().map(|(_, space)| *space.state()).sum::<i32>(), 9);
+    /// for (id, space) in qdf.iter_spaces() {
+    ///     assert_eq!(id, space.id());
+    /// }
+    /// ```
+    #[inline]
+    pub fn iter_spaces(&self) -> impl Iterator<Item = (ID, &Space<S>)> {
+        self.spaces.spaces().iter().map(|space| (space.id(), space))
+    }
+
+    /// Gets list of spaces IDs which states satisfy given predicate.
+    ///
+    /// # Arguments
+    /// * `pred` - function that tells if space state matches.
+    ///
+    /// # Returns
+    /// Vector of matching space ids.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.set_space_state(subs[1], 5).unwrap();
+    /// assert_eq!(qdf.find_spaces(|state| *state > 3), vec![subs[1]]);
+    /// ```
+    pub fn find_spaces<F>(&self, pred: F) -> Vec<ID>
+    where
+        F: Fn(&S) -> bool,
+    {
+        self.spaces
+            .spaces()
+            .iter()
+            .filter(|space| pred(space.state()))
+            .map(|space| space.id())
+            .collect()
+    }
+
+    /// Does the same as `find_spaces()` but in parallel manner.
+    ///
+    /// # Arguments
+    /// * `pred` - function that tells if space state matches.
+    ///
+    /// # Returns
+    /// Vector of matching space ids.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.set_space_state(subs[1], 5).unwrap();
+    /// assert_eq!(qdf.find_spaces_parallel(|state| *state > 3), vec![subs[1]]);
+    /// ```
+    pub fn find_spaces_parallel<F>(&self, pred: F) -> Vec<ID>
+    where
+        F: Fn(&S) -> bool + Sync + Send,
+    {
+        self.spaces
+            .spaces()
+            .par_iter()
+            .filter(|space| pred(space.state()))
+            .map(|space| space.id())
+            .collect()
+    }
+
+    /// Gets total state of QDF (states of all spaces merged with `State::merge()`). It is like
+    /// integrating the field, so for states that conserve quantity it stays the same no matter
+    /// how space density changes.
+    ///
+    /// # Returns
+    /// Merged state of all spaces.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 10);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.increase_space_density(subs[0]).unwrap();
+    /// assert_eq!(qdf.total_state(), 10);
+    /// ```
+    pub fn total_state(&self) -> S {
+        let states = self
+            .spaces
+            .spaces()
+            .iter()
+            .map(|space| space.state().clone())
+            .collect::<Vec<S>>();
+        S::merge(&states)
+    }
+
+    /// Does the same as `total_state()` but in parallel manner, merging states pairwise, so it
+    /// gives the same result only when merging is associative (like summing numbers).
+    ///
+    /// # Returns
+    /// Merged state of all spaces.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 10);
+    /// qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(qdf.total_state_parallel(), qdf.total_state());
+    /// ```
+    pub fn total_state_parallel(&self) -> S {
+        self.spaces
+            .spaces()
+            .par_iter()
+            .map(|space| space.state().clone())
+            .reduce_with(|a, b| S::merge(&[a, b]))
+            .unwrap_or_default()
+    }
+
+    /// Folds states of all spaces with given function, which is a generalization of
+    /// `total_state()` for queries that do not follow `State::merge()` semantics.
+    ///
+    /// # Arguments
+    /// * `init` - initial value.
+    /// * `f` - function that gets accumulated value and space state and returns new value.
+    ///
+    /// # Returns
+    /// Accumulated value.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9.0);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.set_space_state(subs[1], 7.5).unwrap();
+    /// let max = qdf.reduce(::std::f64::MIN, |max, state| max.max(*state));
+    /// assert_eq!(max, 7.5);
+    /// let above = qdf.reduce(0, |count, state| if *state > 3.0 { count + 1 } else { count });
+    /// assert_eq!(above, 1);
+    /// ```
+    pub fn reduce<T, F>(&self, init: T, f: F) -> T
+    where
+        F: Fn(T, &S) -> T,
+    {
+        self.spaces
+            .spaces()
+            .iter()
+            .fold(init, |acc, space| f(acc, space.state()))
+    }
+
+    /// Does the same as `reduce()` but in parallel manner: states are folded in chunks starting
+    /// from `init` and partial results are combined pairwise, so `init` must be neutral for
+    /// `combine` (like `0` for addition) and the result does not depend on spaces order only when
+    /// both functions are associative and commutative.
+    ///
+    /// # Arguments
+    /// * `init` - initial value of every chunk.
+    /// * `f` - function that gets accumulated value and space state and returns new value.
+    /// * `combine` - function that combines two partial results.
+    ///
+    /// # Returns
+    /// Accumulated value.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, spaces) = QDF::with_levels(2, 81.0, 2);
+    /// qdf.set_space_state(spaces[4], 20.0).unwrap();
+    /// let max = qdf.reduce_parallel(::std::f64::MIN, |max, state| max.max(*state), f64::max);
+    /// assert_eq!(max, 20.0);
+    /// ```
+    pub fn reduce_parallel<T, F, C>(&self, init: T, f: F, combine: C) -> T
+    where
+        T: Clone + Send + Sync,
+        F: Fn(T, &S) -> T + Send + Sync,
+        C: Fn(T, T) -> T + Send + Sync,
+    {
+        self.spaces
+            .spaces()
+            .par_iter()
+            .fold(|| init.clone(), |acc, space| f(acc, space.state()))
+            .reduce(|| init.clone(), combine)
+    }
+
+    /// Try to get given space.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Some` reference to given `Space` data or `None` if space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (qdf, root) = QDF::new(2, 9);
+    /// if let Some(space) = qdf.try_get_space(root) {
+    ///     assert_eq!(*space.state(), 9);
+    /// }
+    /// ```
+    #[inline]
+    pub fn try_get_space(&self, id: ID) -> Option<&Space<S>> {
+        self.spaces.get(id)
+    }
+
+    /// Get given space or throw error if space does not exists.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with reference to given `Space` data or `Err` if space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (qdf, root) = QDF::new(2, 9);
+    /// if let Ok(space) = qdf.get_space(root) {
+    ///     assert_eq!(*space.state(), 9);
+    /// }
+    /// ```
+    #[inline]
+    pub fn get_space(&self, id: ID) -> Result<&Space<S>> {
+        if let Some(space) = self.spaces.get(id) {
+            Ok(space)
+        } else {
+            Err(QDFError::SpaceDoesNotExists(id))
+        }
+    }
+
+    /// Get given space or panic if space does not exists.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// Reference to `Space` data.
+    ///
+    /// # Panics
+    /// When given space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (qdf, root) = QDF::new(2, 9);
+    /// assert_eq!(*qdf.space(root).state(), 9);
+    /// ```
+    #[inline]
+    pub fn space(&self, id: ID) -> &Space<S> {
+        &self.spaces[&id]
+    }
+
+    /// Gets ids of spaces that given space was subdivided from, starting with its direct parent
+    /// and ending with the oldest known ancestor (empty for unknown space).
+    /// Merging spaces creates brand new space with no parent, so lineage resets on merge.
+    /// Lineage is not kept by binary snapshots and by regions made with `extract_region()`.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// assert!(qdf.ancestors(root).is_empty());
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(qdf.space(subs[0]).parent(), Some(root));
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// assert_eq!(qdf.ancestors(subs2[0]), vec![subs[0], root]);
+    /// let merged = qdf.decrease_space_density(subs2[0]).unwrap().unwrap();
+    /// assert!(qdf.ancestors(merged.id).is_empty());
+    /// ```
+    pub fn ancestors(&self, id: ID) -> Vec<ID> {
+        let mut result = vec![];
+        let mut current = self.spaces.get(id).and_then(|space| space.parent());
+        while let Some(id) = current {
+            result.push(id);
+            current = self.lineage.get(&id).cloned();
+        }
+        result
+    }
+
+    /// Try to set given space state.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    /// * `state` - state.
+    ///
+    /// # Returns
+    /// `true` if space exists and state was successfuly set, `false` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// assert!(qdf.try_set_space_state(root, 3));
+    /// ```
+    #[inline]
+    pub fn try_set_space_state(&mut self, id: ID, state: S) -> bool {
+        self.set_space_state(id, state).is_ok()
+    }
+
+    /// Set given space state or throw error if space does not exists.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    /// * `state` - state.
+    ///
+    /// # Returns
+    /// `Ok` if space exists and state was successfuly set, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// assert!(qdf.set_space_state(root, 3).is_ok());
+    /// ```
+    #[inline]
+    pub fn set_space_state(&mut self, id: ID, state: S) -> Result<()> {
+        if self.space_exists(id) {
+            self.spaces.get_mut(id).unwrap().apply_state(state);
+            Ok(())
+        } else {
+            Err(QDFError::SpaceDoesNotExists(id))
+        }
+    }
+
+    /// Gets mutable reference to given space state or throw error if space does not exists, so
+    /// large states can be modified in place instead of being cloned, changed and set back.
+    /// QDF does not keep any states derived from space states, so nothing is recomputed after
+    /// modification - caller is responsible for upholding any invariants (like conservation of
+    /// total state) that state modification is expected to keep.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with mutable reference to space state if space exists, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// *qdf.space_state_mut(root).unwrap() += 3;
+    /// assert_eq!(*qdf.space(root).state(), 12);
+    /// ```
+    #[inline]
+    pub fn space_state_mut(&mut self, id: ID) -> Result<&mut S> {
+        match self.spaces.get_mut(id) {
+            Some(space) => Ok(space.state_mut()),
+            None => Err(QDFError::SpaceDoesNotExists(id)),
+        }
+    }
+
+    /// Set states of multiple spaces at once or throw error if any of spaces does not exists (in
+    /// that case no state is applied).
+    ///
+    /// # Arguments
+    /// * `states` - pairs of space id and its new state.
+    ///
+    /// # Returns
+    /// `Ok` if all spaces exist and states were successfuly set, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{QDF, ID};
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert!(qdf.apply_states(vec![(subs[0], 1), (root, 2)]).is_err());
+    /// assert_eq!(*qdf.space(subs[0]).state(), 3);
+    /// assert!(qdf.apply_states(subs.iter().map(|id| (*id, 1))).is_ok());
+    /// assert_eq!(*qdf.space(subs[0]).state(), 1);
+    /// ```
+    pub fn apply_states<I>(&mut self, states: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (ID, S)>,
+    {
+        let states = states.into_iter().collect::<Vec<(ID, S)>>();
+        if let Some((id, _)) = states.iter().find(|(id, _)| !self.space_exists(*id)) {
+            return Err(QDFError::SpaceDoesNotExists(*id));
+        }
+        for (id, state) in states {
+            self.spaces.get_mut(id).unwrap().apply_state(state);
+        }
+        Ok(())
+    }
+
+    /// Replaces state of every space (frozen ones too) with result of given function applied to
+    /// its current state. Only states change, topology stays untouched.
+    ///
+    /// # Arguments
+    /// * `f` - function that gets current state and returns new one.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// qdf.increase_space_density(root).unwrap();
+    /// qdf.map_states(|state| state * 2);
+    /// assert_eq!(qdf.total_state(), 18);
+    /// ```
+    pub fn map_states<F>(&mut self, f: F)
+    where
+        F: Fn(&S) -> S,
+    {
+        for space in self.spaces.spaces_mut() {
+            let state = f(space.state());
+            space.apply_state(state);
+        }
+    }
+
+    /// Does the same as `map_states()` but in parallel manner.
+    ///
+    /// # Arguments
+    /// * `f` - function that gets current state and returns new one.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, _) = QDF::with_levels(2, 9.0, 2);
+    /// qdf.map_states_parallel(|state| state * 0.5);
+    /// assert_eq!(qdf.total_state(), 4.5);
+    /// ```
+    pub fn map_states_parallel<F>(&mut self, f: F)
+    where
+        F: Fn(&S) -> S + Sync,
+    {
+        self.spaces.spaces_mut().par_iter_mut().for_each(|space| {
+            let state = f(space.state());
+            space.apply_state(state);
+        });
+    }
+
+    /// Gets ids and states of all spaces as two flat lists sorted by space id, so the same
+    /// topology always gives the same order. States can be exported to external tools and later
+    /// applied back with `apply_states()` by zipping them with ids.
+    ///
+    /// # Returns
+    /// Tuple of sorted space ids and their states (in the same order).
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, mut subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (ids, states) = qdf.states_in_order();
+    /// subs.sort();
+    /// assert_eq!(ids, subs);
+    /// assert_eq!(states, vec![3, 3, 3]);
+    /// let states = states.iter().map(|s| s * 2).collect::<Vec<i32>>();
+    /// qdf.apply_states(ids.into_iter().zip(states)).unwrap();
+    /// assert_eq!(qdf.total_state(), 18);
+    /// ```
+    pub fn states_in_order(&self) -> (Vec<ID>, Vec<S>) {
+        let mut ids = Vec::with_capacity(self.spaces.len());
+        let mut states = Vec::with_capacity(self.spaces.len());
+        self.visit_ordered(|id, space| {
+            ids.push(id);
+            states.push(space.state().clone());
+        });
+        (ids, states)
+    }
+
+    /// Visits all spaces sorted by their ids, so the same topology is always visited in the same
+    /// order no matter how spaces are stored (iteration over `spaces()` follows storage order,
+    /// which depends on history of operations rather than on ids, so the same topology built in
+    /// different way gets visited differently). Order is given by `Ord` implementation of `ID`,
+    /// which compares UUID bytes (or numbers with `compact-id` feature), so it is reproducible
+    /// but has nothing in common with space placement. It is the way to produce stable output
+    /// (like golden files or custom exporters) from QDF.
+    ///
+    /// # Arguments
+    /// * `f` - function that gets space id and space.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, mut subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let mut visited = vec![];
+    /// qdf.visit_ordered(|id, space| visited.push((id, *space.state())));
+    /// subs.sort();
+    /// assert_eq!(visited, subs.into_iter().map(|id| (id, 3)).collect::<Vec<_>>());
+    /// ```
+    pub fn visit_ordered<F>(&self, mut f: F)
+    where
+        F: FnMut(ID, &Space<S>),
+    {
+        let mut spaces = self.spaces.spaces().iter().collect::<Vec<&Space<S>>>();
+        spaces.sort_by_key(|space| space.id());
+        for space in spaces {
+            f(space.id(), space);
+        }
+    }
+
+    /// Freezes or unfreezes given space, or throws error if space does not exists. Simulation
+    /// does not change states of frozen spaces (they still take part in simulation as neighbors
+    /// of other spaces), which is the way to define fixed boundary conditions. Spaces created by
+    /// changing density of frozen space are frozen too.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    /// * `frozen` - tells if space should be frozen.
+    ///
+    /// # Returns
+    /// `Ok` if space exists, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// qdf.set_frozen(root, true).unwrap();
+    /// assert!(qdf.is_frozen(root));
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert!(subs.iter().all(|id| qdf.is_frozen(*id)));
+    /// qdf.set_frozen(subs[0], false).unwrap();
+    /// assert!(!qdf.is_frozen(subs[0]));
+    /// ```
+    pub fn set_frozen(&mut self, id: ID, frozen: bool) -> Result<()> {
+        if !self.space_exists(id) {
+            return Err(QDFError::SpaceDoesNotExists(id));
+        }
+        if frozen {
+            self.frozen.insert(id);
+        } else {
+            self.frozen.remove(&id);
+        }
+        Ok(())
+    }
+
+    /// Tells if given space is frozen (`false` if space does not exists).
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    #[inline]
+    pub fn is_frozen(&self, id: ID) -> bool {
+        self.frozen.contains(&id)
+    }
+
+    /// Get list of IDs of given space neighbors or throws error if space does not exists.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with vector of space neighbors if space exists, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(qdf.find_space_neighbors(subs[0]).unwrap(), vec![subs[1], subs[2]]);
+    /// ```
+    #[inline]
+    pub fn find_space_neighbors(&self, id: ID) -> Result<Vec<ID>> {
+        if self.graph.contains_node(id) {
+            Ok(self.graph.neighbors(id).collect())
+        } else {
+            Err(QDFError::SpaceDoesNotExists(id))
+        }
+    }
+
+    /// Try to get list of IDs of given space neighbors, like `find_space_neighbors()` but
+    /// without error, which is handy when iterating over space ids kept somewhere else that may
+    /// be already removed from QDF (for example merged by `decrease_space_density()`).
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Some` vector of space neighbors if space exists, `None` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(qdf.neighbors_opt(subs[0]), Some(vec![subs[1], subs[2]]));
+    /// assert_eq!(qdf.neighbors_opt(root), None);
+    /// ```
+    #[inline]
+    pub fn neighbors_opt(&self, id: ID) -> Option<Vec<ID>> {
+        if self.graph.contains_node(id) {
+            Some(self.graph.neighbors(id).collect())
+        } else {
+            None
+        }
+    }
+
+    /// Gets states of given space neighbors or throws error if space does not exists.
+    /// States come in the same order as simulation gets them in `Simulate::simulate()`.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with vector of borrowed neighbor states if space exists, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.set_space_state(subs[1], 5).unwrap();
+    /// let mut states = qdf.neighbor_states(subs[0]).unwrap();
+    /// states.sort();
+    /// assert_eq!(states, vec![&3, &5]);
+    /// ```
+    pub fn neighbor_states(&self, id: ID) -> Result<Vec<&S>> {
+        if let Some(index) = self.spaces.index_of(id) {
+            let spaces = self.spaces.spaces();
+            Ok(self
+                .spaces
+                .neighbors(index)
+                .iter()
+                .map(|n| spaces[*n as usize].state())
+                .collect())
+        } else {
+            Err(QDFError::SpaceDoesNotExists(id))
+        }
+    }
+
+    /// Gets number of given space neighbors or throws error if space does not exists.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with number of space neighbors if space exists, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// assert_eq!(qdf.space_degree(root).unwrap(), 0);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(qdf.space_degree(subs[0]).unwrap(), 2);
+    /// ```
+    #[inline]
+    pub fn space_degree(&self, id: ID) -> Result<usize> {
+        if self.space_exists(id) {
+            Ok(self.graph.neighbors(id).count())
+        } else {
+            Err(QDFError::SpaceDoesNotExists(id))
+        }
+    }
+
+    /// Gets list of spaces that lay on the boundary of universe (spaces that have less neighbors
+    /// than `dimensions + 1`, which is the number of neighbors of fully surrounded space).
+    ///
+    /// # Returns
+    /// Vector of boundary space ids.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// let mut boundary = qdf.boundary_spaces();
+    /// boundary.sort();
+    /// let mut expected = vec![subs[1], subs[2], subs2[2]];
+    /// expected.sort();
+    /// assert_eq!(boundary, expected);
+    /// ```
+    pub fn boundary_spaces(&self) -> Vec<ID> {
+        let ids = self.spaces.ids();
+        (0..ids.len())
+            .filter(|i| self.spaces.neighbors(*i as u32).len() <= self.dimensions)
+            .map(|i| ids[i])
+            .collect()
+    }
+
+    /// Get list of IDs of spaces reachable from given space within specified number of graph hops
+    /// or throws error if space does not exists.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    /// * `radius` - maximal number of hops.
+    ///
+    /// # Returns
+    /// `Ok` with vector of space ids (without origin space) ordered by increasing distance if space
+    /// exists, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// assert_eq!(qdf.neighbors_within(subs2[2], 0).unwrap(), vec![]);
+    /// assert_eq!(qdf.neighbors_within(subs2[2], 1).unwrap(), vec![subs2[0], subs2[1]]);
+    /// assert_eq!(
+    ///     qdf.neighbors_within(subs2[2], 2).unwrap(),
+    ///     vec![subs2[0], subs2[1], subs[1], subs[2]],
+    /// );
+    /// ```
+    pub fn neighbors_within(&self, id: ID, radius: usize) -> Result<Vec<ID>> {
+        Ok(self
+            .hops_within(id, radius)?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect())
+    }
+
+    // spaces within given number of hops (without origin space) paired with their hop distance.
+    fn hops_within(&self, id: ID, radius: usize) -> Result<Vec<(ID, usize)>> {
+        if !self.space_exists(id) {
+            return Err(QDFError::SpaceDoesNotExists(id));
+        }
+        let mut visited = HashSet::new();
+        visited.insert(id);
+        let mut result = vec![];
+        let mut frontier = vec![id];
+        for hops in 1..=radius {
+            let next = frontier
+                .iter()
+                .flat_map(|i| self.graph.neighbors(*i))
+                .filter(|i| visited.insert(*i))
+                .collect::<Vec<ID>>();
+            if next.is_empty() {
+                break;
+            }
+            result.extend(next.iter().map(|i| (*i, hops)));
+            frontier = next;
+        }
+        Ok(result)
+    }
+
+    /// Samples region of space: merges states of given space and all spaces reachable from it
+    /// within specified number of graph hops, or throws error if space does not exists.
+    ///
+    /// # Arguments
+    /// * `center` - id of space in the center of region.
+    /// * `radius` - maximal number of hops.
+    ///
+    /// # Returns
+    /// `Ok` with merged state of region if space exists, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 27);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// assert_eq!(qdf.sample_region(subs2[2], 0).unwrap(), 3);
+    /// assert_eq!(qdf.sample_region(subs2[2], 1).unwrap(), 9);
+    /// assert_eq!(qdf.sample_region(subs2[2], 2).unwrap(), 27);
+    /// ```
+    pub fn sample_region(&self, center: ID, radius: usize) -> Result<S> {
+        let mut ids = self.neighbors_within(center, radius)?;
+        ids.push(center);
+        let states = ids
+            .iter()
+            .map(|id| self.spaces[id].state().clone())
+            .collect::<Vec<S>>();
+        Ok(State::merge(&states))
+    }
+
+    /// Samples region of space like `sample_region()`, but contribution of every space is weighted
+    /// by falloff function of its hop distance from center space (center space has distance `0`),
+    /// which gives smooth sample instead of hard cutoff at region border.
+    /// States are merged with `State::merge_weighted()`, so state type must implement it to take
+    /// weights into account - default implementation ignores weights and gives the same result as
+    /// `sample_region()`.
+    ///
+    /// # Arguments
+    /// * `center` - id of space in the center of region.
+    /// * `radius` - maximal number of hops.
+    /// * `falloff` - function that gives weight of space from its hop distance.
+    ///
+    /// # Returns
+    /// `Ok` with weighted merged state of region if space exists, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 27.0);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// let falloff = |hops| 1.0 / (1 << hops) as f64;
+    /// assert_eq!(qdf.sample_region_weighted(subs2[2], 0, falloff).unwrap(), 3.0);
+    /// assert_eq!(qdf.sample_region_weighted(subs2[2], 1, falloff).unwrap(), 6.0);
+    /// assert_eq!(qdf.sample_region_weighted(subs2[2], 2, falloff).unwrap(), 10.5);
+    /// ```
+    pub fn sample_region_weighted<F>(&self, center: ID, radius: usize, falloff: F) -> Result<S>
+    where
+        F: Fn(usize) -> f64,
+    {
+        let mut hops = self.hops_within(center, radius)?;
+        hops.push((center, 0));
+        let states = hops
+            .iter()
+            .map(|(id, _)| self.spaces[id].state().clone())
+            .collect::<Vec<S>>();
+        let weights = hops
+            .iter()
+            .map(|(_, hops)| falloff(*hops))
+            .collect::<Vec<f64>>();
+        Ok(State::merge_weighted(&states, &weights))
+    }
+
+    /// Gets iterator that lazily walks spaces in breadth-first order, starting from given space.
+    ///
+    /// # Arguments
+    /// * `start` - starting space id.
+    ///
+    /// # Returns
+    /// Iterator over space ids (starting space goes first). It yields nothing if starting space
+    /// does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// assert_eq!(
+    ///     qdf.bfs(subs2[2]).collect::<Vec<_>>(),
+    ///     vec![subs2[2], subs2[0], subs2[1], subs[1], subs[2]],
+    /// );
+    /// assert_eq!(qdf.bfs(subs2[2]).find(|id| *qdf.space(*id).state() == 3), Some(subs[1]));
+    /// assert_eq!(qdf.bfs(root).count(), 0);
+    /// ```
+    #[inline]
+    pub fn bfs(&self, start: ID) -> BfsIter<'_, S, E> {
+        BfsIter::new(self, start)
+    }
+
+    /// Partitions all spaces into connected subsets (islands).
+    ///
+    /// # Returns
+    /// Vector of connected components, each being vector of space ids.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// assert_eq!(qdf.connected_components(), vec![vec![root]]);
+    /// qdf.increase_space_density(root).unwrap();
+    /// let components = qdf.connected_components();
+    /// assert_eq!(components.len(), 1);
+    /// assert_eq!(components[0].len(), 3);
+    /// ```
+    pub fn connected_components(&self) -> Vec<Vec<ID>> {
+        let mut visited = HashSet::with_capacity(self.spaces.len());
+        let mut result = vec![];
+        for id in self.spaces.ids() {
+            if !visited.contains(id) {
+                let component = self.bfs(*id).collect::<Vec<ID>>();
+                visited.extend(component.iter().cloned());
+                result.push(component);
+            }
+        }
+        result
+    }
+
+    /// Gets matrix of hop distances between every pair of given spaces or throws error if any
+    /// of spaces does not exists. It performs single breadth-first search per listed space.
+    ///
+    /// # Arguments
+    /// * `ids` - space ids.
+    ///
+    /// # Returns
+    /// `Ok` with `ids.len() x ids.len()` matrix where `result[i][j]` is number of hops between
+    /// `ids[i]` and `ids[j]` (`usize::MAX` if unreachable), `Err` if any space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// assert_eq!(
+    ///     qdf.distance_matrix(&[subs2[2], subs2[0], subs[1]]).unwrap(),
+    ///     vec![vec![0, 1, 2], vec![1, 0, 1], vec![2, 1, 0]],
+    /// );
+    /// assert!(qdf.distance_matrix(&[root]).is_err());
+    /// ```
+    pub fn distance_matrix(&self, ids: &[ID]) -> Result<Vec<Vec<usize>>> {
+        if let Some(id) = ids.iter().find(|id| !self.space_exists(**id)) {
+            return Err(QDFError::SpaceDoesNotExists(*id));
+        }
+        let targets = ids.iter().cloned().collect::<HashSet<ID>>();
+        Ok(ids
+            .iter()
+            .map(|id| {
+                let mut distances = HashMap::with_capacity(targets.len());
+                let mut visited = HashSet::new();
+                visited.insert(*id);
+                let mut frontier = vec![*id];
+                let mut distance = 0;
+                while !frontier.is_empty() && distances.len() < targets.len() {
+                    for i in &frontier {
+                        if targets.contains(i) {
+                            distances.insert(*i, distance);
+                        }
+                    }
+                    frontier = frontier
+                        .iter()
+                        .flat_map(|i| self.graph.neighbors(*i))
+                        .filter(|i| visited.insert(*i))
+                        .collect();
+                    distance += 1;
+                }
+                ids.iter()
+                    .map(|i| *distances.get(i).unwrap_or(&usize::MAX))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Checks QDF consistency: spaces and space graph nodes must be identical, every edge must
+    /// connect existing spaces and neighborhood of spaces must mirror graph edges.
+    ///
+    /// # Returns
+    /// `Ok` if QDF is consistent, `Err` with list of all found violations otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.increase_space_density(subs[0]).unwrap();
+    /// assert!(qdf.validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> StdResult<(), Vec<String>> {
+        let mut errors = vec![];
+        for (i, id) in self.spaces.ids().iter().enumerate() {
+            if self.spaces.index_of(*id) != Some(i as u32) {
+                errors.push(format!("space id {:?} is not listed in space index", id));
+            }
+            let space = &self.spaces.spaces()[i];
+            if space.id() != *id {
+                errors.push(format!("space {:?} is stored under id {:?}", space.id(), id));
+            }
+            if !self.graph.contains_node(*id) {
+                errors.push(format!("space id {:?} has no graph node", id));
+            }
+        }
+        for id in self.graph.nodes() {
+            if !self.spaces.contains(id) {
+                errors.push(format!("graph node {:?} has no space", id));
+            }
+        }
+        let mut links = 0;
+        for (a, b, _) in self.graph.all_edges() {
+            match (self.spaces.index_of(a), self.spaces.index_of(b)) {
+                (Some(ia), Some(ib)) => {
+                    if !self.spaces.neighbors(ia).contains(&ib)
+                        || !self.spaces.neighbors(ib).contains(&ia)
+                    {
+                        errors.push(format!(
+                            "edge between {:?} and {:?} is not mirrored in space neighbors",
+                            a, b
+                        ));
+                    }
+                    links += 2;
+                }
+                _ => errors.push(format!(
+                    "edge between {:?} and {:?} points to missing space",
+                    a, b
+                )),
+            }
+        }
+        let neighbors = (0..self.spaces.len())
+            .map(|i| self.spaces.neighbors(i as u32).len())
+            .sum::<usize>();
+        if neighbors != links {
+            errors.push(format!(
+                "spaces have {} neighbor links while graph edges give {}",
+                neighbors, links
+            ));
+        }
+        for field in self.fields.values() {
+            for id in field.spaces() {
+                if !self.spaces.contains(*id) {
+                    errors.push(format!(
+                        "field {:?} points to missing space {:?}",
+                        field.id(),
+                        id
+                    ));
+                }
+            }
+        }
+        for id in &self.frozen {
+            if !self.spaces.contains(*id) {
+                errors.push(format!("frozen space {:?} does not exists", id));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Tells if both QDFs describe the same space: they have the same number of dimensions, the
+    /// same spaces (compared by id) with equal states and the same edges with equal data.
+    /// Space ids are not relabeled, so copies with fresh ids (like `extract_region()` results)
+    /// are not equal. QDF id, subdivision lineage, fields and observer are not compared.
+    /// This is also the notion of equality used by `PartialEq` implementation.
+    ///
+    /// # Arguments
+    /// * `other` - QDF to compare with.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let copy = QDF::from_bytes(&qdf.to_bytes()).unwrap();
+    /// assert!(qdf.structurally_eq(&copy));
+    /// assert!(qdf == copy);
+    /// qdf.set_space_state(subs[0], 4).unwrap();
+    /// assert!(!qdf.structurally_eq(&copy));
+    /// ```
+    pub fn structurally_eq(&self, other: &QDF<S, E>) -> bool
+    where
+        S: PartialEq,
+        E: PartialEq,
+    {
+        self.dimensions == other.dimensions
+            && self.spaces.len() == other.spaces.len()
+            && self.graph.edge_count() == other.graph.edge_count()
+            && self.spaces.spaces().iter().all(|space| {
+                other
+                    .try_get_space(space.id())
+                    .is_some_and(|o| o.state() == space.state())
+            }) && self
+            .graph
+            .all_edges()
+            .all(|(a, b, data)| other.graph.edge_weight(a, b) == Some(data))
+    }
+
+    /// Exports space graph as Graphviz DOT document. Every space is a node labelled with short
+    /// form of its id and its state, and every connection between spaces is an edge.
+    ///
+    /// # Returns
+    /// DOT document string.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// qdf.increase_space_density(root).unwrap();
+    /// let dot = qdf.to_dot();
+    /// assert!(dot.starts_with("graph QDF {"));
+    /// assert_eq!(dot.matches("[label=").count(), 3);
+    /// assert_eq!(dot.matches(" -- ").count(), 3);
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut result = String::from("graph QDF {\n");
+        for id in self.graph.nodes() {
+            if let Some(space) = self.spaces.get(id) {
+                let key = id.key();
+                writeln!(
+                    result,
+                    "    \"{}\" [label=\"{}\\n{}\"];",
+                    key,
+                    &key[..key.len().min(8)],
+                    escape_dot(&format!("{:?}", space.state())),
+                ).unwrap();
+            }
+        }
+        for (a, b) in self.edges() {
+            writeln!(result, "    \"{}\" -- \"{}\";", a.key(), b.key()).unwrap();
+        }
+        result.push_str("}\n");
+        result
+    }
+
+    /// Gets data of edge between two spaces.
+    ///
+    /// # Arguments
+    /// * `a` - first space id.
+    /// * `b` - second space id.
+    ///
+    /// # Returns
+    /// `Some` reference to edge data or `None` if spaces are not connected.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::<i32, f32>::new_with_edge_data(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(qdf.edge_data(subs[0], subs[1]), Some(&0.0));
+    /// assert_eq!(qdf.edge_data(subs[0], root), None);
+    /// ```
+    #[inline]
+    pub fn edge_data(&self, a: ID, b: ID) -> Option<&E> {
+        self.graph.edge_weight(a, b)
+    }
+
+    /// Set data of edge between two spaces or throw error if spaces are not connected.
+    ///
+    /// # Arguments
+    /// * `a` - first space id.
+    /// * `b` - second space id.
+    /// * `data` - edge data.
+    ///
+    /// # Returns
+    /// `Ok` if edge exists and data was successfuly set, `Err` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::<i32, f32>::new_with_edge_data(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert!(qdf.set_edge_data(subs[0], subs[1], 0.5).is_ok());
+    /// assert_eq!(qdf.edge_data(subs[1], subs[0]), Some(&0.5));
+    /// ```
+    pub fn set_edge_data(&mut self, a: ID, b: ID, data: E) -> Result<()> {
+        if !self.space_exists(a) {
+            return Err(QDFError::SpaceDoesNotExists(a));
+        }
+        if !self.space_exists(b) {
+            return Err(QDFError::SpaceDoesNotExists(b));
+        }
+        if let Some(weight) = self.graph.edge_weight_mut(a, b) {
+            *weight = data;
+            Ok(())
+        } else {
+            Err(QDFError::EdgeDoesNotExists(a, b))
+        }
+    }
+
+    /// Gets list of space IDs that defines shortest path between two spaces,
+    /// or throws error if space does not exists.
+    ///
+    /// # Arguments
+    /// * `from` - source space id.
+    /// * `to` - target space id.
+    ///
+    /// # Returns
+    /// `Ok` with `Some` space ids that builds shortest path between two points (including both
+    /// of them), `Ok` with `None` if path cannot be found, or `Err` if spaces does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// assert_eq!(
+    ///     qdf.find_path(subs2[0], subs[2]).unwrap(),
+    ///     Some(vec![subs2[0], subs2[1], subs[2]]),
+    /// );
+    /// assert_eq!(qdf.find_path(subs[1], subs[1]).unwrap(), Some(vec![subs[1]]));
+    /// ```
+    #[inline]
+    pub fn find_path(&self, from: ID, to: ID) -> Result<Option<Vec<ID>>> {
+        Ok(self.find_path_with_cost(from, to)?.map(|(spaces, _)| spaces))
+    }
+
+    /// Gets states of spaces along given path (like one returned by `find_path()`), or throws
+    /// error with first space that does not exists. Connections between spaces are not checked.
+    ///
+    /// # Arguments
+    /// * `path` - space ids.
+    ///
+    /// # Returns
+    /// `Ok` with vector of borrowed states (in path order) or `Err` if some space does not
+    /// exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{ID, QDF};
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// let path = qdf.find_path(subs2[0], subs[2]).unwrap().unwrap();
+    /// let total = qdf.path_states(&path).unwrap().into_iter().sum::<i32>();
+    /// assert_eq!(total, 5);
+    /// assert!(qdf.path_states(&[subs[1], ID::new()]).is_err());
+    /// ```
+    pub fn path_states(&self, path: &[ID]) -> Result<Vec<&S>> {
+        path.iter()
+            .map(|id| match self.spaces.get(*id) {
+                Some(space) => Ok(space.state()),
+                None => Err(QDFError::SpaceDoesNotExists(*id)),
+            }).collect()
+    }
+
+    /// Gets list of space IDs that defines shortest path between two spaces along with its cost
+    /// (number of hops), or throws error if space does not exists.
+    ///
+    /// # Arguments
+    /// * `from` - source space id.
+    /// * `to` - target space id.
+    ///
+    /// # Returns
+    /// `Ok` with `Some` tuple of space ids that builds shortest path between two points and path
+    /// cost, `Ok` with `None` if path cannot be found, or `Err` if spaces does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// assert_eq!(
+    ///     qdf.find_path_with_cost(subs2[0], subs[2]).unwrap(),
+    ///     Some((vec![subs2[0], subs2[1], subs[2]], 2)),
+    /// );
+    /// assert_eq!(qdf.find_path_with_cost(subs[1], subs[1]).unwrap(), Some((vec![subs[1]], 0)));
+    /// ```
+    pub fn find_path_with_cost(&self, from: ID, to: ID) -> Result<Option<(Vec<ID>, u32)>> {
+        self.find_path_heuristic_with_cost(from, to, |_, _| 0)
+    }
+
+    /// Gets list of space IDs that defines path between two spaces, using given heuristic to
+    /// speed up the search, or throws error if space does not exists. Heuristic estimates number
+    /// of hops between two spaces - it must never overestimate it (be admissible), otherwise
+    /// returned path may not be the shortest one.
+    ///
+    /// # Arguments
+    /// * `from` - source space id.
+    /// * `to` - target space id.
+    /// * `h` - function that estimates cost of path between visited space and target space.
+    ///
+    /// # Returns
+    /// `Ok` with `Some` space ids that builds path between two points (including both of them),
+    /// `Ok` with `None` if path cannot be found, or `Err` if spaces does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// assert_eq!(
+    ///     qdf.find_path_heuristic(subs2[0], subs[2], |a, b| if a == b { 0 } else { 1 }).unwrap(),
+    ///     Some(vec![subs2[0], subs2[1], subs[2]]),
+    /// );
+    /// ```
+    pub fn find_path_heuristic<F>(&self, from: ID, to: ID, h: F) -> Result<Option<Vec<ID>>>
+    where
+        F: Fn(ID, ID) -> u32,
+    {
+        Ok(self
+            .find_path_heuristic_with_cost(from, to, h)?
+            .map(|(spaces, _)| spaces))
+    }
+
+    fn find_path_heuristic_with_cost<F>(
+        &self,
+        from: ID,
+        to: ID,
+        h: F,
+    ) -> Result<Option<(Vec<ID>, u32)>>
+    where
+        F: Fn(ID, ID) -> u32,
+    {
+        if !self.space_exists(from) {
+            return Err(QDFError::SpaceDoesNotExists(from));
+        }
+        if !self.space_exists(to) {
+            return Err(QDFError::SpaceDoesNotExists(to));
+        }
+        Ok(astar(&self.graph, from, |f| f == to, |_| 1, |n| h(n, to))
+            .map(|(cost, spaces)| (spaces, cost)))
+    }
+
+    /// Gets list of space IDs that defines cheapest path between two spaces, where cost of
+    /// moving between neighbor spaces is calculated from their edge data, or throws error if
+    /// space does not exists.
+    ///
+    /// # Arguments
+    /// * `from` - source space id.
+    /// * `to` - target space id.
+    /// * `cost` - function that gets cost of edge based on its data.
+    ///
+    /// # Returns
+    /// `Ok` with `Some` space ids that builds cheapest path between two points (including both
+    /// of them), `Ok` with `None` if path cannot be found, or `Err` if spaces does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::<i32, u32>::new_with_edge_data(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.set_edge_data(subs[0], subs[2], 10).unwrap();
+    /// assert_eq!(
+    ///     qdf.find_weighted_path(subs[0], subs[2], |w| *w + 1).unwrap(),
+    ///     Some(vec![subs[0], subs[1], subs[2]]),
+    /// );
+    /// ```
+    pub fn find_weighted_path<F>(&self, from: ID, to: ID, cost: F) -> Result<Option<Vec<ID>>>
+    where
+        F: Fn(&E) -> u32,
+    {
+        if !self.space_exists(from) {
+            return Err(QDFError::SpaceDoesNotExists(from));
+        }
+        if !self.space_exists(to) {
+            return Err(QDFError::SpaceDoesNotExists(to));
+        }
+        Ok(
+            astar(&self.graph, from, |f| f == to, |(_, _, e)| cost(e), |_| 0)
+                .map(|(_, spaces)| spaces),
+        )
+    }
+
+    /// Increases given space density (subdivide space and rebind it properly to its neighbors),
+    /// and returns process information (source space id, subdivided space ids, connections pairs)
+    /// or throws error if space does not exists.
+    /// Subdivided spaces are connected according to subdivision topology (see
+    /// `set_subdivision_topology()`). Edges between subdivided spaces gets `E::default()` data,
+    /// while edges rebound to neighbors keep their data.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with tuple of source space id, vector of subdivided space ids and vector of
+    /// connections pairs or `Err` if space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(subs.len(), 3);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    pub fn increase_space_density(&mut self, id: ID) -> Result<(ID, Vec<ID>, Vec<(ID, ID)>)> {
+        self.increase_space_density_with_id_source(id, ID::new)
+    }
+
+    /// Does the same as `increase_space_density()` but new space ids are taken from given
+    /// generator.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    /// * `gen` - Identifiers generator.
+    ///
+    /// # Returns
+    /// `Ok` with tuple of source space id, vector of subdivided space ids and vector of
+    /// connections pairs or `Err` if space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{IdGenerator, QDF};
+    ///
+    /// let mut gen = IdGenerator::new(42);
+    /// let (mut qdf, root) = QDF::new_with_generator(2, 9, &mut gen);
+    /// let (_, subs, _) = qdf.increase_space_density_with(root, &mut gen).unwrap();
+    /// assert_eq!(subs.len(), 3);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    pub fn increase_space_density_with(
+        &mut self,
+        id: ID,
+        gen: &mut IdGenerator,
+    ) -> Result<(ID, Vec<ID>, Vec<(ID, ID)>)> {
+        self.increase_space_density_with_id_source(id, || gen.generate())
+    }
+
+    /// Does the same as `increase_space_density()` but source space id is reused for first
+    /// subspace, so ids held outside of QDF (like selected region in editor) stay valid through
+    /// refinement. Remaining subspaces get new ids. Adjacency of source space is redistributed
+    /// like in `increase_space_density()`: neighbors are handed out to subspaces round-robin and
+    /// only neighbors handed out to first subspace stay connected with source id. Source space
+    /// keeps its lineage and other subspaces share its parent.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with tuple of source space id, vector of subdivided space ids (starting with source
+    /// space id) and vector of connections pairs or `Err` if space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 27);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, pairs) = qdf.increase_space_density_keeping_id(subs[0]).unwrap();
+    /// assert_eq!(subs2.len(), 3);
+    /// assert_eq!(subs2[0], subs[0]);
+    /// assert_eq!(*qdf.space(subs[0]).state(), 3);
+    /// assert_eq!(pairs, vec![(subs[1], subs[0]), (subs[2], subs2[1])]);
+    /// assert_eq!(qdf.find_space_neighbors(subs[0]).unwrap().len(), 3);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn increase_space_density_keeping_id(
+        &mut self,
+        id: ID,
+    ) -> Result<(ID, Vec<ID>, Vec<(ID, ID)>)> {
+        if !self.space_exists(id) {
+            return Err(QDFError::SpaceDoesNotExists(id));
+        }
+        let removed = self.snapshot_for_history(&[id]);
+        let parent = self.spaces[&id].parent();
+        let mut substates = self.spaces[&id]
+            .state()
+            .subdivide(self.subdivision_count())
+            .into_iter();
+        if let Some(substate) = substates.next() {
+            self.spaces.get_mut(id).unwrap().apply_state(substate);
+        }
+        let neighbors = self.graph.neighbors(id).collect::<Vec<ID>>();
+        let mut space_ids = vec![id];
+        for substate in substates {
+            let id = ID::new();
+            let space = match parent {
+                Some(parent) => Space::with_parent(id, substate, parent),
+                None => Space::new(id, substate),
+            };
+            self.spaces.insert(space);
+            self.graph.add_node(id);
+            self.observer.notify(QdfEvent::SpaceCreated(id));
+            space_ids.push(id);
+        }
+        self.connect_subspaces(&space_ids);
+        // outer neighbors are handed out to subspaces round-robin.
+        let mut pairs = Vec::with_capacity(neighbors.len());
+        for (i, n) in neighbors.into_iter().enumerate() {
+            let t = space_ids[i % space_ids.len()];
+            if t != id {
+                let data = self.remove_edge(n, id).unwrap_or_default();
+                self.observer.notify(QdfEvent::EdgeRemoved(n, id));
+                self.add_edge(n, t, data);
+                self.observer.notify(QdfEvent::EdgeAdded(n, t));
+            }
+            pairs.push((n, t));
+        }
+        self.replace_in_fields(id, &space_ids);
+        if let Some(removed) = removed {
+            self.record_change(removed, &space_ids, &[]);
+        }
+        Ok((id, space_ids, pairs))
+    }
+
+    /// Does the same as `increase_space_density()` but splits space into given number of
+    /// subspaces instead of `dimensions + 1`. Subspaces are connected according to subdivision
+    /// topology (all with each other by default, see `set_subdivision_topology()`) and neighbors
+    /// of source space are connected to subspaces round-robin (first neighbor to first subspace,
+    /// second to second and so on, wrapping around when there are more neighbors than
+    /// subspaces), so with fewer subspaces than `dimensions + 1` some of them get more than one
+    /// outer neighbor.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    /// * `subdivisions` - number of subspaces.
+    ///
+    /// # Returns
+    /// `Ok` with tuple of source space id, vector of subdivided space ids and vector of
+    /// connections pairs, `Err` with `QDFError::SpaceDoesNotExists` if space does not exists or
+    /// `QDFError::InvalidSubdivisionCount` if `subdivisions` is `0`.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 12);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, pairs) = qdf.increase_space_density_into(subs[0], 4).unwrap();
+    /// assert_eq!(subs2.len(), 4);
+    /// assert_eq!(*qdf.space(subs2[0]).state(), 1);
+    /// assert_eq!(qdf.find_space_neighbors(subs2[3]).unwrap().len(), 3);
+    /// assert_eq!(pairs, vec![(subs[1], subs2[0]), (subs[2], subs2[1])]);
+    /// let (_, subs3, pairs) = qdf.increase_space_density_into(subs[1], 1).unwrap();
+    /// assert_eq!(pairs, vec![(subs[2], subs3[0]), (subs2[0], subs3[0])]);
+    /// assert!(qdf.increase_space_density_into(subs[2], 0).is_err());
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn increase_space_density_into(
+        &mut self,
+        id: ID,
+        subdivisions: usize,
+    ) -> Result<(ID, Vec<ID>, Vec<(ID, ID)>)> {
+        if !self.space_exists(id) {
+            Err(QDFError::SpaceDoesNotExists(id))
+        } else if subdivisions == 0 {
+            Err(QDFError::InvalidSubdivisionCount(subdivisions))
+        } else {
+            let substates = self.spaces[&id].state().subdivide(subdivisions);
+            Ok(self.apply_space_subdivision(id, substates, ID::new))
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn increase_space_density_with_id_source<F>(
+        &mut self,
+        id: ID,
+        new_id: F,
+    ) -> Result<(ID, Vec<ID>, Vec<(ID, ID)>)>
+    where
+        F: FnMut() -> ID,
+    {
+        if self.space_exists(id) {
+            let substates = self.spaces[&id].state().subdivide(self.subdivision_count());
+            Ok(self.apply_space_subdivision(id, substates, new_id))
+        } else {
+            Err(QDFError::SpaceDoesNotExists(id))
+        }
+    }
+
+    /// Uniformly increases density of given space `depth` times (every space created at one level
+    /// gets subdivided at the next one) or throws error if space does not exists. Unlike calling
+    /// `increase_space_density()` recursively, it does not build process information per
+    /// subdivision.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    /// * `depth` - number of subdivision levels.
+    ///
+    /// # Returns
+    /// `Ok` with vector of ids of `(dimensions + 1).pow(depth)` leaf spaces, `Err` with
+    /// `QDFError::SpaceDoesNotExists` if space does not exists or `QDFError::DepthOutOfRange`
+    /// (with the deepest depth which leaves count still fits in `usize`) if number of leaves
+    /// overflows.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 81);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let leaves = qdf.subdivide_to_depth(subs[0], 2).unwrap();
+    /// assert_eq!(leaves.len(), 9);
+    /// assert_eq!(qdf.spaces().count(), 11);
+    /// assert_eq!(*qdf.space(leaves[0]).state(), 3);
+    /// assert_eq!(qdf.subdivide_to_depth(subs[1], 0).unwrap(), vec![subs[1]]);
+    /// ```
+    pub fn subdivide_to_depth(&mut self, id: ID, depth: usize) -> Result<Vec<ID>> {
+        if !self.space_exists(id) {
+            return Err(QDFError::SpaceDoesNotExists(id));
+        }
+        let subdivisions = self.subdivision_count();
+        let leaves_count = |depth: usize| {
+            if depth > u32::MAX as usize {
+                None
+            } else {
+                subdivisions.checked_pow(depth as u32)
+            }
+        };
+        if leaves_count(depth).is_none() {
+            let available = (0..depth)
+                .take_while(|depth| leaves_count(*depth).is_some())
+                .last()
+                .unwrap_or(0);
+            return Err(QDFError::DepthOutOfRange(depth, available));
+        }
+        let mut leaves = vec![];
+        let mut next = vec![];
+        let mut pairs = vec![];
+        let mut new_id = ID::new;
+        let removed = if depth > 0 {
+            self.snapshot_for_history(&[id])
+        } else {
+            None
+        };
+        // spaces created at one level and subdivided at the next one.
+        let mut transient = vec![];
+        leaves.push(id);
+        for level in 0..depth {
+            next.clear();
+            next.reserve(leaves.len() * subdivisions);
+            for id in &leaves {
+                let substates = self.spaces[id].state().subdivide(subdivisions);
+                pairs.clear();
+                self.subdivide_space_into(*id, substates, &mut new_id, &mut next, &mut pairs);
+            }
+            if removed.is_some() && level > 0 {
+                transient.extend_from_slice(&leaves);
+            }
+            ::std::mem::swap(&mut leaves, &mut next);
+        }
+        if let Some(removed) = removed {
+            self.record_change(removed, &leaves, &transient);
+        }
+        Ok(leaves)
+    }
+
+    /// Does the same as `increase_space_density()` but subdivides space state with
+    /// `TrySubdivide::try_subdivide()`, so subdivision error is reported and QDF stays untouched.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with either `Ok` tuple of source space id, vector of subdivided space ids and vector
+    /// of connections pairs or `Err` with subdivision error, or `Err` if space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{State, TrySubdivide, QDF};
+    ///
+    /// #[derive(Debug, Default, Clone, PartialEq)]
+    /// struct Strict(u32);
+    ///
+    /// impl State for Strict {
+    ///     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
+    ///         vec![Strict(self.0 / subdivisions as u32); subdivisions]
+    ///     }
+    ///     fn merge(states: &[Self]) -> Self {
+    ///         Strict(states.iter().map(|s| s.0).sum())
+    ///     }
+    /// }
+    ///
+    /// impl TrySubdivide for Strict {
+    ///     type Error = u32;
+    ///     fn try_subdivide(&self, subdivisions: usize) -> Result<Vec<Self>, u32> {
+    ///         match self.0 % subdivisions as u32 {
+    ///             0 => Ok(self.subdivide(subdivisions)),
+    ///             remainder => Err(remainder),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let (mut qdf, root) = QDF::new(2, Strict(10));
+    /// assert_eq!(qdf.try_increase_space_density(root).unwrap(), Err(1));
+    /// assert!(qdf.space_exists(root));
+    /// qdf.set_space_state(root, Strict(9)).unwrap();
+    /// let (_, subs, _) = qdf.try_increase_space_density(root).unwrap().unwrap();
+    /// assert_eq!(*qdf.space(subs[0]).state(), Strict(3));
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn try_increase_space_density(
+        &mut self,
+        id: ID,
+    ) -> Result<StdResult<(ID, Vec<ID>, Vec<(ID, ID)>), S::Error>>
+    where
+        S: TrySubdivide,
+    {
+        if self.space_exists(id) {
+            match self.spaces[&id].state().try_subdivide(self.subdivision_count()) {
+                Ok(substates) => Ok(Ok(self.apply_space_subdivision(id, substates, ID::new))),
+                Err(error) => Ok(Err(error)),
+            }
+        } else {
+            Err(QDFError::SpaceDoesNotExists(id))
+        }
+    }
+
+    fn apply_space_subdivision<F>(
+        &mut self,
+        id: ID,
+        substates: Vec<S>,
+        mut new_id: F,
+    ) -> (ID, Vec<ID>, Vec<(ID, ID)>)
+    where
+        F: FnMut() -> ID,
+    {
+        let removed = self.snapshot_for_history(&[id]);
+        let mut space_ids = Vec::with_capacity(substates.len());
+        let mut pairs = vec![];
+        self.subdivide_space_into(id, substates, &mut new_id, &mut space_ids, &mut pairs);
+        if let Some(removed) = removed {
+            self.record_change(removed, &space_ids, &[]);
+        }
+        (id, space_ids, pairs)
+    }
+
+    // appends subdivided space ids and connections pairs to given buffers.
+    fn subdivide_space_into<F>(
+        &mut self,
+        id: ID,
+        substates: Vec<S>,
+        new_id: &mut F,
+        space_ids: &mut Vec<ID>,
+        pairs: &mut Vec<(ID, ID)>,
+    ) where
+        F: FnMut() -> ID,
+    {
+        let parent = id;
+        let first = space_ids.len();
+        for substate in substates {
+            let id = new_id();
+            self.spaces.insert(Space::with_parent(id, substate, parent));
+            self.graph.add_node(id);
+            self.observer.notify(QdfEvent::SpaceCreated(id));
+            space_ids.push(id);
+        }
+        self.connect_subspaces(&space_ids[first..]);
+        // outer neighbors are handed out to subspaces round-robin.
+        let count = space_ids.len() - first;
+        let start = pairs.len();
+        pairs.extend(self.graph.neighbors(id).map(|n| (n, id)));
+        for i in start..pairs.len() {
+            let n = pairs[i].0;
+            let t = space_ids[first + (i - start) % count];
+            let data = self.remove_edge(n, id).unwrap_or_default();
+            self.observer.notify(QdfEvent::EdgeRemoved(n, id));
+            self.add_edge(n, t, data);
+            self.observer.notify(QdfEvent::EdgeAdded(n, t));
+            pairs[i].1 = t;
+        }
+        self.graph.remove_node(id);
+        if let Some(grandparent) = self.spaces.remove(id).and_then(|space| space.parent()) {
+            self.lineage.insert(id, grandparent);
+        }
+        self.replace_in_fields(id, &space_ids[first..]);
+        self.observer.notify(QdfEvent::SpaceRemoved(id));
+    }
+
+    // connects subspaces of single subdivision according to subdivision topology.
+    fn connect_subspaces(&mut self, space_ids: &[ID]) {
+        for (i, j) in self.topology.connections(space_ids.len()) {
+            let (aid, bid) = (space_ids[i], space_ids[j]);
+            if self.add_edge(aid, bid, E::default()).is_none() {
+                self.observer.notify(QdfEvent::EdgeAdded(aid, bid));
+            }
+        }
+    }
+
+    // fields must not point to removed spaces, so they get spaces that took its place.
+    // The same goes for frozen spaces, so frozen region stays frozen when its density changes.
+    fn replace_in_fields(&mut self, id: ID, replacement: &[ID]) {
+        for field in self.fields.values_mut() {
+            if field.remove(id) {
+                for r in replacement {
+                    field.insert(*r);
+                }
+            }
+        }
+        if self.frozen.remove(&id) {
+            self.frozen.extend(replacement);
+        }
+    }
+
+    // restores states of frozen spaces (simulated states come in the same order as spaces).
+    fn keep_frozen_states(&self, states: &mut [(ID, S)]) {
+        for id in &self.frozen {
+            if let Some(index) = self.spaces.index_of(*id) {
+                states[index as usize].1 = self.spaces[id].state().clone();
+            }
+        }
+    }
+
+    // graph edges must always go through these, so neighborhood of spaces stays in sync.
+    fn add_edge(&mut self, a: ID, b: ID, data: E) -> Option<E> {
+        self.spaces.link(a, b);
+        self.graph.add_edge(a, b, data)
+    }
+
+    fn remove_edge(&mut self, a: ID, b: ID) -> Option<E> {
+        self.spaces.unlink(a, b);
+        self.graph.remove_edge(a, b)
+    }
+
+    // simulated states come in the same order as spaces are stored.
+    fn apply_simulated_states<I>(&mut self, states: I)
+    where
+        I: IntoIterator<Item = (ID, S)>,
+    {
+        for (space, (id, state)) in self.spaces.spaces_mut().iter_mut().zip(states) {
+            debug_assert_eq!(space.id(), id);
+            space.apply_state(state);
+        }
+    }
+
+    /// Increases density of all spaces which states satisfy given predicate. Matching spaces are
+    /// selected before any subdivision, so newly created spaces are not checked in the same pass.
+    ///
+    /// # Arguments
+    /// * `pred` - function that tells if space should be subdivided.
+    ///
+    /// # Returns
+    /// Vector of process information (like returned from `increase_space_density()`) for every
+    /// subdivided space.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 27);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.set_space_state(subs[0], 18).unwrap();
+    /// let result = qdf.refine_where(|state| *state > 9);
+    /// assert_eq!(result.len(), 1);
+    /// assert_eq!(result[0].0, subs[0]);
+    /// assert_eq!(qdf.spaces().count(), 5);
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn refine_where<F>(&mut self, pred: F) -> Vec<(ID, Vec<ID>, Vec<(ID, ID)>)>
+    where
+        F: Fn(&S) -> bool,
+    {
+        self.find_spaces(pred)
+            .into_iter()
+            .map(|id| self.increase_space_density(id).unwrap())
+            .collect()
+    }
+
+    /// Decreases given space density (merge space children and rebind them properly to theirs
+    /// neighbors if space has 1 level of subdivision, otherwise perform this operation on its
+    /// subspaces), and returns process information (source space ids, merged space id, removed
+    /// and added edges) or throws error if space does not exists.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with `Some` merge information, or `Ok` with `None` if space cannot be merged or `Err`
+    /// if given space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(subs.len(), 3);
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// let info = qdf.decrease_space_density(subs2[0]).unwrap().unwrap();
+    /// assert_eq!(qdf.spaces().len(), 3);
+    /// assert_eq!(info.merged.len(), 3);
+    /// assert_eq!(info.removed_edges.len(), 5);
+    /// assert_eq!(info.added_edges.len(), 2);
+    /// assert!(info.added_edges.contains(&(info.id, subs[1])));
+    /// assert!(info.added_edges.contains(&(info.id, subs[2])));
+    /// ```
+    #[inline]
+    pub fn decrease_space_density(&mut self, id: ID) -> Result<Option<MergeInfo>> {
+        self.decrease_space_density_with_id_source(id, ID::new)
+    }
+
+    /// Does the same as `decrease_space_density()` but merged space id is taken from given
+    /// generator.
+    ///
+    /// # Arguments
+    /// * `id` - space id.
+    /// * `gen` - Identifiers generator.
+    ///
+    /// # Returns
+    /// `Ok` with `Some` merge information, or `Ok` with `None` if space cannot be merged or `Err`
+    /// if given space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{IdGenerator, QDF};
+    ///
+    /// let mut gen = IdGenerator::new(42);
+    /// let (mut qdf, root) = QDF::new_with_generator(2, 9, &mut gen);
+    /// let (_, subs, _) = qdf.increase_space_density_with(root, &mut gen).unwrap();
+    /// let info = qdf.decrease_space_density_with(subs[0], &mut gen).unwrap().unwrap();
+    /// assert_eq!(qdf.spaces().len(), 1);
+    /// assert_eq!(*qdf.spaces().nth(0).unwrap(), info.id);
+    /// ```
+    #[inline]
+    pub fn decrease_space_density_with(
+        &mut self,
+        id: ID,
+        gen: &mut IdGenerator,
+    ) -> Result<Option<MergeInfo>> {
+        self.decrease_space_density_with_id_source(id, || gen.generate())
+    }
+
+    fn decrease_space_density_with_id_source<F>(
+        &mut self,
+        id: ID,
+        mut new_id: F,
+    ) -> Result<Option<MergeInfo>>
+    where
+        F: FnMut() -> ID,
+    {
+        if self.space_exists(id) {
+            let neighbor = self.graph.neighbors(id).collect::<Vec<ID>>();
+            let neighbor_set = neighbor.iter().cloned().collect::<HashSet<ID>>();
+            let mut connected = neighbor
+                .iter()
+                .filter(|a| {
+                    self.graph
+                        .neighbors(**a)
+                        .any(|b| **a != b && neighbor_set.contains(&b))
+                }).cloned()
+                .collect::<Vec<ID>>();
+            if connected.len() != self.dimensions {
+                Ok(None)
+            } else {
+                connected.push(id);
+                let removed = self.snapshot_for_history(&connected);
+                let states = connected
+                    .iter()
+                    .map(|i| self.spaces[i].state())
+                    .cloned()
+                    .collect::<Vec<S>>();
+                let id = new_id();
+                self.graph.add_node(id);
+                self.spaces.insert(Space::new(id, State::merge(&states)));
+                self.observer.notify(QdfEvent::SpaceCreated(id));
+                let mut added_edges = vec![];
+                for i in &connected {
+                    let outsiders = self
+                        .graph
+                        .neighbors(*i)
+                        .filter(|n| !connected.contains(n))
+                        .collect::<Vec<ID>>();
+                    for n in outsiders {
+                        if !self.graph.contains_edge(id, n) {
+                            let data = self.graph[(*i, n)].clone();
+                            self.add_edge(id, n, data);
+                            self.observer.notify(QdfEvent::EdgeAdded(id, n));
+                            added_edges.push((id, n));
+                        }
+                    }
+                }
+                let mut removed_edges = vec![];
+                for i in &connected {
+                    let edges = self.graph.neighbors(*i).collect::<Vec<ID>>();
+                    for n in edges {
+                        self.remove_edge(*i, n);
+                        self.observer.notify(QdfEvent::EdgeRemoved(*i, n));
+                        removed_edges.push((*i, n));
+                    }
+                    self.graph.remove_node(*i);
+                    self.spaces.remove(*i);
+                    self.replace_in_fields(*i, &[id]);
+                    self.observer.notify(QdfEvent::SpaceRemoved(*i));
+                }
+                if let Some(removed) = removed {
+                    self.record_change(removed, &[id], &[]);
+                }
+                Ok(Some(MergeInfo {
+                    merged: connected,
+                    id,
+                    removed_edges,
+                    added_edges,
+                }))
+            }
+        } else {
+            Err(QDFError::SpaceDoesNotExists(id))
+        }
+    }
+
+    /// Subdivides or merges spaces until number of spaces gets close to given target. Coarsest
+    /// spaces (with the shortest lineage) are subdivided first and finest ones are merged first,
+    /// so resolution stays as uniform as possible. Since every operation changes number of spaces
+    /// by `dimensions`, it stops once number of spaces is at least `target` but less than
+    /// `target + dimensions`, or when no space can be merged anymore. Calling it again with the
+    /// same target does nothing, which makes it a fixpoint of relaxation loop.
+    ///
+    /// # Arguments
+    /// * `target` - desired number of spaces.
+    ///
+    /// # Returns
+    /// Number of performed subdivisions and merges (`0` if topology did not change).
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, _) = QDF::new(2, 81);
+    /// assert_eq!(qdf.try_relax_to(9), 4);
+    /// assert_eq!(qdf.space_count(), 9);
+    /// assert_eq!(qdf.try_relax_to(9), 0);
+    /// assert_eq!(qdf.try_relax_to(3), 3);
+    /// assert_eq!(qdf.space_count(), 3);
+    /// assert_eq!(qdf.total_state(), 81);
+    /// ```
+    pub fn try_relax_to(&mut self, target: usize) -> usize {
+        // subdivision and merge of 0-dimensional space does not change number of spaces.
+        if self.dimensions == 0 {
+            return 0;
+        }
+        let mut operations = 0;
+        // lineage depths are found once and updated as spaces are created, so heaps below can
+        // skip entries of spaces that are gone.
+        let mut depths = self
+            .spaces
+            .ids()
+            .iter()
+            .map(|id| (*id, self.ancestors(*id).len()))
+            .collect::<HashMap<ID, usize>>();
+        let mut coarsest = depths
+            .iter()
+            .map(|(id, depth)| Reverse((*depth, *id)))
+            .collect::<BinaryHeap<Reverse<(usize, ID)>>>();
+        while self.spaces.len() < target {
+            let Reverse((depth, id)) = coarsest.pop().unwrap();
+            if depths.get(&id) != Some(&depth) {
+                continue;
+            }
+            let (_, subs, _) = self.increase_space_density(id).unwrap();
+            for id in subs {
+                let depth = self.ancestors(id).len();
+                depths.insert(id, depth);
+                coarsest.push(Reverse((depth, id)));
+            }
+            operations += 1;
+        }
+        let mut finest = depths
+            .iter()
+            .map(|(id, depth)| (*depth, Reverse(*id)))
+            .collect::<BinaryHeap<(usize, Reverse<ID>)>>();
+        // spaces that cannot be merged until one of their neighbors gets merged.
+        let mut blocked = HashSet::new();
+        while self.spaces.len() >= target + self.dimensions {
+            let (depth, Reverse(id)) = match finest.pop() {
+                Some(item) => item,
+                None => break,
+            };
+            if depths.get(&id) != Some(&depth) {
+                continue;
+            }
+            match self.decrease_space_density(id).unwrap() {
+                Some(info) => {
+                    for id in &info.merged {
+                        depths.remove(id);
+                        blocked.remove(id);
+                    }
+                    let depth = self.ancestors(info.id).len();
+                    depths.insert(info.id, depth);
+                    finest.push((depth, Reverse(info.id)));
+                    // merge changes neighborhood of its outer neighbors only.
+                    for id in self.graph.neighbors(info.id) {
+                        if blocked.remove(&id) {
+                            finest.push((depths[&id], Reverse(id)));
+                        }
+                    }
+                    operations += 1;
+                }
+                None => {
+                    blocked.insert(id);
+                }
+            }
+        }
+        operations
+    }
+
+    /// Greedily subdivides spaces with the highest priority first, until given budget of created
+    /// spaces is exhausted or no space has positive priority. Every subdivision creates
+    /// `subdivision_count()` spaces (and removes subdivided one), so at most
+    /// `budget / subdivision_count()` subdivisions are performed. Created subspaces get their own
+    /// priorities and compete with the rest of spaces, so dense regions can be refined more than
+    /// once. Spaces with priority that is not positive (or is NaN) are never subdivided.
+    ///
+    /// # Arguments
+    /// * `budget` - maximal number of spaces that can be created.
+    /// * `priority` - function that gets space state and returns its subdivision priority.
+    ///
+    /// # Returns
+    /// Number of performed subdivisions.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 27);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// qdf.set_space_state(subs[0], 90).unwrap();
+    /// // subdivides `subs[0]` and then its subspace with state 30.
+    /// assert_eq!(qdf.refine_budget(7, |state| f64::from(*state - 20)), 2);
+    /// assert_eq!(qdf.space_count(), 7);
+    /// assert!(qdf.space_exists(subs[1]));
+    /// // two subspaces with state 30 are left.
+    /// assert_eq!(qdf.refine_budget(100, |state| f64::from(*state - 20)), 2);
+    /// assert_eq!(qdf.refine_budget(100, |state| f64::from(*state - 20)), 0);
+    /// ```
+    pub fn refine_budget<F>(&mut self, budget: usize, priority: F) -> usize
+    where
+        F: Fn(&S) -> f64,
+    {
+        let created = self.subdivision_count();
+        let mut frontier = self
+            .spaces
+            .spaces()
+            .iter()
+            .map(|space| MaxPriority(priority(space.state()), space.id()))
+            .filter(|item| item.0 > 0.0)
+            .collect::<BinaryHeap<MaxPriority>>();
+        let mut remaining = budget;
+        let mut subdivisions = 0;
+        while remaining >= created {
+            let id = match frontier.pop() {
+                Some(MaxPriority(_, id)) => id,
+                None => break,
+            };
+            let (_, subs, _) = self.increase_space_density(id).unwrap();
+            remaining -= created;
+            subdivisions += 1;
+            for id in subs {
+                let value = priority(self.spaces[&id].state());
+                if value > 0.0 {
+                    frontier.push(MaxPriority(value, id));
+                }
+            }
+        }
+        subdivisions
+    }
+
+    /// Removes given space and connects all of its former neighbors with each other (so graph does
+    /// not get fragmented), and returns newly created connections pairs or throws error if space
+    /// does not exists. New edges gets `E::default()` data.
+    ///
+    /// # Arguments
     /// * `id` - space id.
-    /// * `state` - state.
     ///
     /// # Returns
-    /// `Ok` if space exists and state was successfuly set, `Err` otherwise.
+    /// `Ok` with vector of new connections pairs or `Err` if space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// let pairs = qdf.remove_space(subs2[0]).unwrap();
+    /// assert_eq!(pairs, vec![(subs2[1], subs[1]), (subs2[2], subs[1])]);
+    /// assert!(!qdf.space_exists(subs2[0]));
+    /// assert_eq!(qdf.spaces().count(), 4);
+    /// assert_eq!(qdf.connected_components().len(), 1);
+    /// ```
+    pub fn remove_space(&mut self, id: ID) -> Result<Vec<(ID, ID)>> {
+        if self.space_exists(id) {
+            let neighbors = self.graph.neighbors(id).collect::<Vec<ID>>();
+            for n in &neighbors {
+                self.remove_edge(id, *n);
+                self.observer.notify(QdfEvent::EdgeRemoved(id, *n));
+            }
+            self.graph.remove_node(id);
+            self.spaces.remove(id);
+            self.replace_in_fields(id, &[]);
+            self.observer.notify(QdfEvent::SpaceRemoved(id));
+            let mut pairs = vec![];
+            for (i, a) in neighbors.iter().enumerate() {
+                for b in neighbors.iter().skip(i + 1) {
+                    if !self.graph.contains_edge(*a, *b) {
+                        self.add_edge(*a, *b, E::default());
+                        self.observer.notify(QdfEvent::EdgeAdded(*a, *b));
+                        pairs.push((*a, *b));
+                    }
+                }
+            }
+            Ok(pairs)
+        } else {
+            Err(QDFError::SpaceDoesNotExists(id))
+        }
+    }
+
+    /// Copies region of spaces (seed space and all spaces within given number of hops from it)
+    /// into new standalone QDF with fresh space ids, or throws error if seed space does not
+    /// exists. Edges between copied spaces are preserved along with their data, while edges to
+    /// spaces outside of region are dropped.
+    ///
+    /// # Arguments
+    /// * `seed` - id of space in the center of region.
+    /// * `radius` - maximal number of hops from seed space.
+    ///
+    /// # Returns
+    /// `Ok` with tuple of new QDF and map of source space ids to new space ids, or `Err` if seed
+    /// space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// let (region, ids) = qdf.extract_region(subs2[2], 1).unwrap();
+    /// assert_eq!(region.spaces().count(), 3);
+    /// assert_eq!(*region.space(ids[&subs2[0]]).state(), 1);
+    /// assert_eq!(
+    ///     region.find_space_neighbors(ids[&subs2[0]]).unwrap(),
+    ///     vec![ids[&subs2[1]], ids[&subs2[2]]],
+    /// );
+    /// assert!(!ids.contains_key(&subs[1]));
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn extract_region(&self, seed: ID, radius: usize) -> Result<(QDF<S, E>, HashMap<ID, ID>)> {
+        let mut selected = self.neighbors_within(seed, radius)?;
+        selected.push(seed);
+        let selected = selected.into_iter().collect::<HashSet<ID>>();
+        let ids = self
+            .graph
+            .nodes()
+            .filter(|id| selected.contains(id))
+            .map(|id| (id, ID::new()))
+            .collect::<HashMap<ID, ID>>();
+        let mut graph = SpaceGraph::with_capacity(ids.len(), 0);
+        let mut spaces = Vec::with_capacity(ids.len());
+        for id in self.graph.nodes().filter(|id| selected.contains(id)) {
+            let new_id = ids[&id];
+            graph.add_node(new_id);
+            spaces.push(Space::new(new_id, self.spaces[&id].state().clone()));
+        }
+        for (a, b) in ordered_edges(&self.graph) {
+            if let (Some(na), Some(nb)) = (ids.get(&a), ids.get(&b)) {
+                graph.add_edge(*na, *nb, self.graph[(a, b)].clone());
+            }
+        }
+        let spaces = SpaceArena::from_graph(spaces, &graph);
+        let qdf = QDF {
+            id: ID::new(),
+            graph,
+            spaces,
+            dimensions: self.dimensions,
+            lineage: HashMap::new(),
+            fields: HashMap::new(),
+            frozen: HashSet::new(),
+            topology: self.topology,
+            history: None,
+            observer: ObserverSlot::default(),
+        };
+        Ok((qdf, ids))
+    }
+
+    /// Moves all spaces of other QDF into this one and connects them with bridge edge, or throws
+    /// error if universes cannot be joined. Bridge edge gets `E::default()` data. Fields of other
+    /// QDF are moved too. Absorption is not recorded in history (see `with_history()`), so it
+    /// cannot be undone.
+    ///
+    /// # Arguments
+    /// * `other` - QDF to absorb.
+    /// * `bridge` - pair of space id from this QDF and space id from other QDF.
+    ///
+    /// # Returns
+    /// `Ok` if universes were joined or `Err` if dimensions does not match, bridge spaces does not
+    /// exists or some space id or field id exists in both universes.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, a) = QDF::new(2, 9);
+    /// let (mut other, b) = QDF::new(2, 3);
+    /// let (_, subs, _) = other.increase_space_density(b).unwrap();
+    /// qdf.absorb(other, (a, subs[0])).unwrap();
+    /// assert_eq!(qdf.spaces().count(), 4);
+    /// assert_eq!(qdf.find_space_neighbors(a).unwrap(), vec![subs[0]]);
+    /// assert_eq!(*qdf.space(subs[1]).state(), 1);
+    /// ```
+    pub fn absorb(&mut self, other: QDF<S, E>, bridge: (ID, ID)) -> Result<()> {
+        if self.dimensions != other.dimensions {
+            return Err(QDFError::DimensionMismatch(
+                self.dimensions,
+                other.dimensions,
+            ));
+        }
+        if !self.space_exists(bridge.0) {
+            return Err(QDFError::SpaceDoesNotExists(bridge.0));
+        }
+        if !other.space_exists(bridge.1) {
+            return Err(QDFError::SpaceDoesNotExists(bridge.1));
+        }
+        if let Some(id) = other.graph.nodes().find(|id| self.graph.contains_node(*id)) {
+            return Err(QDFError::SpaceAlreadyExists(id));
+        }
+        if let Some(id) = other.fields.keys().find(|id| self.fields.contains_key(id)) {
+            return Err(QDFError::FieldAlreadyExists(*id));
+        }
+        for id in other.graph.nodes() {
+            self.graph.add_node(id);
+        }
+        for space in other.spaces.spaces {
+            let id = space.id();
+            self.spaces.insert(space);
+            self.observer.notify(QdfEvent::SpaceCreated(id));
+        }
+        for (a, b) in ordered_edges(&other.graph) {
+            self.add_edge(a, b, other.graph[(a, b)].clone());
+            self.observer.notify(QdfEvent::EdgeAdded(a, b));
+        }
+        self.add_edge(bridge.0, bridge.1, E::default());
+        self.observer.notify(QdfEvent::EdgeAdded(bridge.0, bridge.1));
+        self.lineage.extend(other.lineage);
+        self.fields.extend(other.fields);
+        self.frozen.extend(other.frozen);
+        Ok(())
+    }
+
+    /// Creates new field (named view over set of spaces) and returns its id, or throws error if
+    /// any of given spaces does not exists.
+    /// Fields follow topology changes and are not kept by binary snapshots.
+    ///
+    /// # Arguments
+    /// * `spaces` - ids of member spaces.
+    ///
+    /// # Returns
+    /// `Ok` with field id or `Err` if some space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let field = qdf.create_field(vec![root]).unwrap();
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// assert_eq!(qdf.get_field(field).unwrap().len(), 3);
+    /// assert!(qdf.get_field(field).unwrap().contains(subs[0]));
+    /// ```
+    pub fn create_field<I>(&mut self, spaces: I) -> Result<ID>
+    where
+        I: IntoIterator<Item = ID>,
+    {
+        let spaces = spaces.into_iter().collect::<HashSet<ID>>();
+        if let Some(id) = spaces.iter().find(|id| !self.space_exists(**id)) {
+            return Err(QDFError::SpaceDoesNotExists(*id));
+        }
+        let id = ID::new();
+        self.fields.insert(id, Field::new(id, spaces));
+        Ok(id)
+    }
+
+    /// Gets iterator over all field ids.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// assert_eq!(qdf.fields().count(), 0);
+    /// let field = qdf.create_field(vec![root]).unwrap();
+    /// assert_eq!(qdf.fields().collect::<Vec<_>>(), vec![&field]);
+    /// ```
+    #[inline]
+    pub fn fields(&self) -> Keys<'_, ID, Field> {
+        self.fields.keys()
+    }
+
+    /// Tells if field with given id exists.
+    ///
+    /// # Arguments
+    /// * `id` - field id.
+    #[inline]
+    pub fn field_exists(&self, id: ID) -> bool {
+        self.fields.contains_key(&id)
+    }
+
+    /// Try to get given field.
+    ///
+    /// # Arguments
+    /// * `id` - field id.
+    ///
+    /// # Returns
+    /// `Some` reference to given `Field` or `None` if field does not exists.
+    #[inline]
+    pub fn try_get_field(&self, id: ID) -> Option<&Field> {
+        self.fields.get(&id)
+    }
+
+    /// Gets given field or throws error if field does not exists.
+    ///
+    /// # Arguments
+    /// * `id` - field id.
+    ///
+    /// # Returns
+    /// `Ok` with reference to given `Field` or `Err` if field does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::{ID, QDF};
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let field = qdf.create_field(vec![root]).unwrap();
+    /// assert!(qdf.get_field(field).unwrap().contains(root));
+    /// assert!(qdf.get_field(ID::new()).is_err());
+    /// ```
+    #[inline]
+    pub fn get_field(&self, id: ID) -> Result<&Field> {
+        self.try_get_field(id).ok_or(QDFError::FieldDoesNotExists(id))
+    }
+
+    /// Removes given field (member spaces stay untouched) or throws error if field does not
+    /// exists.
+    ///
+    /// # Arguments
+    /// * `id` - field id.
+    ///
+    /// # Returns
+    /// `Ok` with removed field or `Err` if field does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let field = qdf.create_field(vec![root]).unwrap();
+    /// assert!(qdf.remove_field(field).unwrap().contains(root));
+    /// assert!(!qdf.field_exists(field));
+    /// assert!(qdf.space_exists(root));
+    /// ```
+    pub fn remove_field(&mut self, id: ID) -> Result<Field> {
+        self.fields.remove(&id).ok_or(QDFError::FieldDoesNotExists(id))
+    }
+
+    /// Adds space to given field or throws error if field or space does not exists.
+    ///
+    /// # Arguments
+    /// * `field` - field id.
+    /// * `space` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with `true` if space was not member of field yet, or `Err` if field or space does not
+    /// exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let field = qdf.create_field(vec![subs[0]]).unwrap();
+    /// assert!(qdf.add_field_space(field, subs[1]).unwrap());
+    /// assert!(!qdf.add_field_space(field, subs[1]).unwrap());
+    /// assert_eq!(qdf.get_field(field).unwrap().len(), 2);
+    /// ```
+    pub fn add_field_space(&mut self, field: ID, space: ID) -> Result<bool> {
+        if !self.space_exists(space) {
+            return Err(QDFError::SpaceDoesNotExists(space));
+        }
+        match self.fields.get_mut(&field) {
+            Some(field) => Ok(field.insert(space)),
+            None => Err(QDFError::FieldDoesNotExists(field)),
+        }
+    }
+
+    /// Removes space from given field or throws error if field does not exists.
+    ///
+    /// # Arguments
+    /// * `field` - field id.
+    /// * `space` - space id.
+    ///
+    /// # Returns
+    /// `Ok` with `true` if space was member of field, or `Err` if field does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let field = qdf.create_field(vec![root]).unwrap();
+    /// assert!(qdf.remove_field_space(field, root).unwrap());
+    /// assert!(!qdf.remove_field_space(field, root).unwrap());
+    /// assert!(qdf.get_field(field).unwrap().is_empty());
+    /// ```
+    pub fn remove_field_space(&mut self, field: ID, space: ID) -> Result<bool> {
+        match self.fields.get_mut(&field) {
+            Some(field) => Ok(field.remove(space)),
+            None => Err(QDFError::FieldDoesNotExists(field)),
+        }
+    }
+
+    /// Performs simulation step (go through all platonic spaces and modifies its states based on
+    /// neighbor states). Actual state simulation is performed by your struct that implements
+    /// `Simulation` trait. States of frozen spaces (see `set_frozen()`) are left unchanged.
+    pub fn simulation_step<M>(&mut self)
+    where
+        M: Simulate<S>,
+    {
+        self.simulation_step_reusing::<M>(&mut vec![]);
+    }
+
+    /// Does the same as `simulation_step()` but first checks that universe is uniform: every
+    /// space has `dimensions` (at universe boundary) or `dimensions + 1` neighbors, like spaces of
+    /// universe made by uniform subdivision (single root space has none). It surfaces topology
+    /// bugs early for simulations that assume fixed number of neighbors. Use `simulation_step()`
+    /// for non-uniform universes.
+    ///
+    /// # Returns
+    /// `Ok` if simulation step was performed, or `Err` with `QDFError::UnexpectedDegree` of first
+    /// space with unexpected number of neighbors (nothing is simulated then).
     ///
     /// # Examples
     /// ```
-    /// use quantized_density_fields::QDF;
+    /// use quantized_density_fields::{ID, QDFError, Simulate, QDF};
     ///
-    /// let (mut qdf, root) = QDF::new(2, 9);
-    /// assert!(qdf.set_space_state(root, 3).is_ok());
+    /// struct Average;
+    ///
+    /// impl Simulate<f64> for Average {
+    ///     fn simulate(state: &f64, neighbor_states: &[&f64]) -> f64 {
+    ///         let sum = neighbor_states.iter().cloned().sum::<f64>();
+    ///         (state + sum) / (neighbor_states.len() + 1) as f64
+    ///     }
+    /// }
+    ///
+    /// let (mut qdf, _) = QDF::with_levels(2, 27.0, 2);
+    /// assert!(qdf.simulation_step_strict::<Average>().is_ok());
+    /// // chain of spaces is not a valid 2D universe.
+    /// let (a, b, c) = (ID::new(), ID::new(), ID::new());
+    /// let nodes = vec![(a, 1.0), (b, 2.0), (c, 3.0)];
+    /// let mut qdf = QDF::<f64>::from_parts(2, nodes, vec![(a, b), (b, c)]).unwrap();
+    /// match qdf.simulation_step_strict::<Average>() {
+    ///     Err(QDFError::UnexpectedDegree(id, 1)) => assert!(id == a || id == c),
+    ///     _ => unreachable!(),
+    /// }
+    /// assert_eq!(*qdf.space(a).state(), 1.0);
     /// ```
-    #[inline]
-    pub fn set_space_state(&mut self, id: ID, state: S) -> Result<()> {
-        if self.space_exists(id) {
-            self.spaces.get_mut(&id).unwrap().apply_state(state);
-            Ok(())
-        } else {
-            Err(QDFError::SpaceDoesNotExists(id))
+    pub fn simulation_step_strict<M>(&mut self) -> Result<()>
+    where
+        M: Simulate<S>,
+    {
+        if self.spaces.len() > 1 {
+            for i in 0..self.spaces.len() {
+                let degree = self.spaces.neighbors(i as u32).len();
+                if degree < self.dimensions || degree > self.dimensions + 1 {
+                    return Err(QDFError::UnexpectedDegree(self.spaces.ids()[i], degree));
+                }
+            }
         }
+        self.simulation_step::<M>();
+        Ok(())
     }
 
-    /// Get list of IDs of given space neighbors or throws error if space does not exists.
+    /// Does the same as `simulation_step()` but simulated states are stored in given buffer, so
+    /// it can be reused between steps to avoid allocations.
     ///
     /// # Arguments
-    /// * `id` - space id.
-    ///
-    /// # Returns
-    /// `Ok` with vector of space neighbors if space exists, `Err` otherwise.
+    /// * `buf` - buffer for simulated states (it is empty after the call).
     ///
     /// # Examples
     /// ```
     /// use quantized_density_fields::QDF;
     ///
     /// let (mut qdf, root) = QDF::new(2, 9);
-    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
-    /// assert_eq!(qdf.find_space_neighbors(subs[0]).unwrap(), vec![subs[1], subs[2]]);
+    /// let mut buf = vec![];
+    /// for _ in 0..10 {
+    ///     qdf.simulation_step_reusing::<()>(&mut buf);
+    /// }
+    /// assert!(buf.is_empty());
+    /// assert_eq!(*qdf.space(root).state(), 9);
     /// ```
-    #[inline]
-    pub fn find_space_neighbors(&self, id: ID) -> Result<Vec<ID>> {
-        if self.graph.contains_node(id) {
-            Ok(self.graph.neighbors(id).collect())
-        } else {
-            Err(QDFError::SpaceDoesNotExists(id))
-        }
+    pub fn simulation_step_reusing<M>(&mut self, buf: &mut Vec<(ID, S)>)
+    where
+        M: Simulate<S>,
+    {
+        self.simulate_states_into::<M>(buf);
+        self.apply_simulated_states(buf.drain(..));
     }
 
-    /// Gets list of space IDs that defines shortest path between two spaces,
-    /// or throws error if space does not exists.
-    ///
-    /// # Arguments
-    /// * `from` - source space id.
-    /// * `to` - target space id.
-    ///
-    /// # Returns
-    /// `Ok` with space ids that builds shortest path between two points, `Err` if path cannot be
-    /// found or spaces does not exists.
+    /// Does the same as `simulation_step()` but states are simulated with
+    /// `Simulate::simulate_indexed()`, so simulation knows ids of spaces and their neighbors.
     ///
     /// # Examples
     /// ```
-    /// use quantized_density_fields::QDF;
+    /// use quantized_density_fields::{Simulate, ID, QDF};
+    ///
+    /// struct Boundary;
+    ///
+    /// impl Simulate<i32> for Boundary {
+    ///     fn simulate(state: &i32, _: &[&i32]) -> i32 {
+    ///         *state
+    ///     }
+    ///     fn simulate_indexed(_: ID, _: &i32, neighbors: &[(ID, &i32)]) -> i32 {
+    ///         neighbors.len() as i32
+    ///     }
+    /// }
     ///
     /// let (mut qdf, root) = QDF::new(2, 9);
     /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
     /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
-    /// assert_eq!(qdf.find_path(subs2[0], subs[2]).unwrap(), vec![subs2[0], subs2[1], subs[2]]);
+    /// qdf.simulation_step_indexed::<Boundary>();
+    /// assert_eq!(*qdf.space(subs2[0]).state(), 3);
+    /// assert_eq!(*qdf.space(subs2[2]).state(), 2);
     /// ```
-    pub fn find_path(&self, from: ID, to: ID) -> Result<Vec<ID>> {
-        if !self.space_exists(from) {
-            return Err(QDFError::SpaceDoesNotExists(from));
-        }
-        if !self.space_exists(to) {
-            return Err(QDFError::SpaceDoesNotExists(to));
-        }
-        if let Some((_, spaces)) = astar(&self.graph, from, |f| f == to, |_| 0, |_| 0) {
-            Ok(spaces)
-        } else {
-            Ok(vec![])
-        }
+    pub fn simulation_step_indexed<M>(&mut self)
+    where
+        M: Simulate<S>,
+    {
+        let states = self.simulate_states_indexed::<M>();
+        self.apply_simulated_states(states);
     }
 
-    /// Increases given space density (subdivide space and rebind it properly to its neighbors),
-    /// and returns process information (source space id, subdivided space ids, connections pairs)
-    /// or throws error if space does not exists.
+    /// Does the same as `simulation_step()` but state simulation is performed by given simulator
+    /// instance that implements `SimulateWith` trait.
     ///
     /// # Arguments
-    /// * `id` - space id.
-    ///
-    /// # Returns
-    /// `Ok` with tuple of source space id, vector of subdivided space ids and vector of
-    /// connections pairs or `Err` if space does not exists.
+    /// * `sim` - simulator.
     ///
     /// # Examples
     /// ```
-    /// use quantized_density_fields::QDF;
+    /// use quantized_density_fields::{SimulateWith, QDF};
     ///
-    /// let (mut qdf, root) = QDF::new(2, 9);
-    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
-    /// assert_eq!(subs.len(), 3);
+    /// struct Scale(f64);
+    ///
+    /// impl SimulateWith<f64> for Scale {
+    ///     fn simulate(&self, state: &f64, _: &[&f64]) -> f64 {
+    ///         state * self.0
+    ///     }
+    /// }
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9.0);
+    /// qdf.simulation_step_with(&Scale(2.0));
+    /// assert_eq!(*qdf.space(root).state(), 18.0);
     /// ```
-    pub fn increase_space_density(&mut self, id: ID) -> Result<(ID, Vec<ID>, Vec<(ID, ID)>)> {
-        if self.space_exists(id) {
-            let space = self.spaces[&id].clone();
-            let subs = self.dimensions + 1;
-            let substates = space.state().subdivide(subs);
-            let spaces = substates
-                .iter()
-                .map(|substate| Space::new(ID::new(), substate.clone()))
-                .collect::<Vec<Space<S>>>();
-            for s in &spaces {
-                let id = s.id();
-                self.spaces.insert(id, s.clone());
-                self.graph.add_node(id);
-                self.space_ids.insert(id);
-            }
-            for a in &spaces {
-                let aid = a.id();
-                for b in &spaces {
-                    let bid = b.id();
-                    if aid != bid {
-                        self.graph.add_edge(aid, bid, ());
-                    }
-                }
-            }
-            let neighbors = self.graph.neighbors(id).collect::<Vec<ID>>();
-            let pairs = neighbors
-                .iter()
-                .enumerate()
-                .map(|(i, n)| {
-                    let t = spaces[i].id();
-                    self.graph.remove_edge(*n, id);
-                    self.graph.add_edge(*n, t, ());
-                    (*n, t)
-                })
-                .collect::<Vec<(ID, ID)>>();
-            self.space_ids.remove(&id);
-            self.spaces.remove(&id);
-            let space_ids = spaces.iter().map(|s| s.id()).collect::<Vec<ID>>();
-            Ok((id, space_ids, pairs))
-        } else {
-            Err(QDFError::SpaceDoesNotExists(id))
-        }
+    pub fn simulation_step_with<M>(&mut self, sim: &M)
+    where
+        M: SimulateWith<S>,
+    {
+        let states = self.simulate_states_with(sim);
+        self.apply_simulated_states(states);
     }
 
-    /// Decreases given space density (merge space children and rebind them properly to theirs
-    /// neighbors if space has 1 level of subdivision, otherwise perform this operation on its
-    /// subspaces), and returns process information (source space ids, merged space id) or throws
-    /// error if space does not exists.
+    /// Performs simulation steps until given convergence check tells that states are settled or
+    /// maximal number of steps is reached. Convergence check gets states from before and after
+    /// the step (in the same order) and is called before new states are applied.
     ///
     /// # Arguments
-    /// * `id` - space id.
+    /// * `max_steps` - maximal number of simulation steps.
+    /// * `converged` - function that tells if simulation converged (gets previous and next states).
     ///
     /// # Returns
-    /// `Ok` with `Some` tuple of vector of merged space ids and created space id, or `Ok` with
-    /// `None` if space cannot be merged or `Err` if given space does not exists.
+    /// Number of simulation steps performed.
     ///
     /// # Examples
     /// ```
-    /// use quantized_density_fields::QDF;
+    /// use quantized_density_fields::{Simulate, QDF};
     ///
-    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// struct Average;
+    ///
+    /// impl Simulate<f64> for Average {
+    ///     fn simulate(state: &f64, neighbor_states: &[&f64]) -> f64 {
+    ///         let sum = neighbor_states.iter().map(|s| **s).sum::<f64>();
+    ///         (state + sum) / (neighbor_states.len() + 1) as f64
+    ///     }
+    /// }
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9.0);
     /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
-    /// assert_eq!(subs.len(), 3);
-    /// let (_, root) = qdf.decrease_space_density(subs[0]).unwrap().unwrap();
-    /// assert_eq!(qdf.spaces().len(), 1);
-    /// assert_eq!(*qdf.spaces().nth(0).unwrap(), root);
+    /// qdf.set_space_state(subs[0], 9.0).unwrap();
+    /// let steps = qdf.simulate_until::<Average, _>(10, |prev, next| {
+    ///     prev.iter().zip(next.iter()).all(|(a, b)| (a.1 - b.1).abs() < 0.001)
+    /// });
+    /// assert_eq!(steps, 2);
+    /// assert_eq!(*qdf.space(subs[1]).state(), 5.0);
     /// ```
-    pub fn decrease_space_density(&mut self, id: ID) -> Result<Option<(Vec<ID>, ID)>> {
-        if self.space_exists(id) {
-            let neighbor = self.graph.neighbors(id).collect::<Vec<ID>>();
-            let mut connected = neighbor
-                .iter()
-                .filter(|a| {
-                    neighbor
-                        .iter()
-                        .any(|b| **a != *b && self.graph.edge_weight(**a, *b).is_some())
-                }).cloned()
-                .collect::<Vec<ID>>();
-            if connected.len() != self.dimensions {
-                Ok(None)
-            } else {
-                connected.push(id);
-                let states = connected
-                    .iter()
-                    .map(|i| self.spaces[&i].state())
-                    .cloned()
-                    .collect::<Vec<S>>();
-                let id = ID::new();
-                self.graph.add_node(id);
-                self.space_ids.insert(id);
-                self.spaces
-                    .insert(id, Space::new(id, State::merge(&states)));
-                for i in &connected {
-                    let outsiders = self
-                        .graph
-                        .neighbors(*i)
-                        .filter(|n| !connected.contains(n))
-                        .collect::<Vec<ID>>();
-                    for n in outsiders {
-                        self.graph.add_edge(id, n, ());
-                    }
-                }
-                let space_ids = connected
-                    .iter()
-                    .map(|i| {
-                        self.graph.remove_node(*i);
-                        self.spaces.remove(i);
-                        self.space_ids.remove(i);
-                        *i
-                    })
-                    .collect::<Vec<ID>>();
-                Ok(Some((space_ids, id)))
-            }
-        } else {
-            Err(QDFError::SpaceDoesNotExists(id))
-        }
-    }
-
-    /// Performs simulation step (go through all platonic spaces and modifies its states based on
-    /// neighbor states). Actual state simulation is performed by your struct that implements
-    /// `Simulation` trait.
-    pub fn simulation_step<M>(&mut self)
+    pub fn simulate_until<M, F>(&mut self, max_steps: usize, converged: F) -> usize
     where
         M: Simulate<S>,
+        F: Fn(&[(ID, S)], &[(ID, S)]) -> bool,
     {
-        let states = self.simulate_states::<M>();
-        for (id, state) in states {
-            self.spaces.get_mut(&id).unwrap().apply_state(state);
+        let mut previous = self
+            .spaces
+            .spaces()
+            .iter()
+            .map(|space| (space.id(), space.state().clone()))
+            .collect::<Vec<(ID, S)>>();
+        for step in 0..max_steps {
+            let states = self.simulate_states::<M>();
+            let done = converged(&previous, &states);
+            self.apply_simulated_states(states.iter().cloned());
+            if done {
+                return step + 1;
+            }
+            previous = states;
         }
+        max_steps
     }
 
     /// Does the same as `simulation_step()` but in parallel manner (it may or may not increase
@@ -549,15 +3686,43 @@ where
     pub fn simulation_step_parallel<M>(&mut self)
     where
         M: Simulate<S>,
+        E: Send + Sync,
     {
         let states = self.simulate_states_parallel::<M>();
-        for (id, state) in states {
-            self.spaces.get_mut(&id).unwrap().apply_state(state);
-        }
+        self.apply_simulated_states(states);
+    }
+
+    /// Does the same as `simulation_step_parallel()` but work is executed by given thread pool
+    /// instead of global one.
+    ///
+    /// # Arguments
+    /// * `pool` - thread pool that performs simulation.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate quantized_density_fields;
+    /// extern crate rayon;
+    ///
+    /// use quantized_density_fields::QDF;
+    /// use rayon::ThreadPoolBuilder;
+    ///
+    /// let pool = ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// qdf.simulation_step_parallel_in::<()>(&pool);
+    /// assert_eq!(*qdf.space(root).state(), 9);
+    /// ```
+    pub fn simulation_step_parallel_in<M>(&mut self, pool: &ThreadPool)
+    where
+        M: Simulate<S>,
+        E: Send + Sync,
+    {
+        let states = self.simulate_states_parallel_in::<M>(pool);
+        self.apply_simulated_states(states);
     }
 
     /// Performs simulation on QDF like `simulation_step()` but instead of applying results to QDF,
-    /// it returns simulated platonic space states along with their space ID.
+    /// it returns simulated platonic space states along with their space ID. Frozen spaces get
+    /// their current states.
     ///
     /// # Returns
     /// Vector of tuples of id and its updated space that were simulated.
@@ -565,16 +3730,104 @@ where
     where
         M: Simulate<S>,
     {
-        self.space_ids
+        let mut buf = Vec::with_capacity(self.spaces.len());
+        self.simulate_states_into::<M>(&mut buf);
+        buf
+    }
+
+    /// Performs simulation on QDF like `simulate_states()` but results are stored in given buffer
+    /// (it is cleared first), so it can be reused between steps to avoid allocations.
+    ///
+    /// # Arguments
+    /// * `buf` - buffer for tuples of id and its updated space that were simulated.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (qdf, root) = QDF::new(2, 9);
+    /// let mut buf = vec![];
+    /// qdf.simulate_states_into::<()>(&mut buf);
+    /// qdf.simulate_states_into::<()>(&mut buf);
+    /// assert_eq!(buf, vec![(root, 9)]);
+    /// ```
+    pub fn simulate_states_into<M>(&self, buf: &mut Vec<(ID, S)>)
+    where
+        M: Simulate<S>,
+    {
+        buf.clear();
+        let spaces = self.spaces.spaces();
+        let mut neighbor_states = vec![];
+        for (i, space) in spaces.iter().enumerate() {
+            neighbor_states.clear();
+            neighbor_states.extend(
+                self.spaces
+                    .neighbors(i as u32)
+                    .iter()
+                    .map(|n| spaces[*n as usize].state()),
+            );
+            buf.push((space.id(), M::simulate(space.state(), &neighbor_states)));
+        }
+        self.keep_frozen_states(buf);
+    }
+
+    /// Performs simulation on QDF like `simulation_step_indexed()` but instead of applying results
+    /// to QDF, it returns simulated platonic space states along with their space ID.
+    ///
+    /// # Returns
+    /// Vector of tuples of id and its updated space that were simulated.
+    pub fn simulate_states_indexed<M>(&self) -> Vec<(ID, S)>
+    where
+        M: Simulate<S>,
+    {
+        let spaces = self.spaces.spaces();
+        let mut states = spaces
             .iter()
-            .map(|id| {
+            .enumerate()
+            .map(|(i, space)| {
+                let neighbors = self
+                    .spaces
+                    .neighbors(i as u32)
+                    .iter()
+                    .map(|n| &spaces[*n as usize])
+                    .map(|n| (n.id(), n.state()))
+                    .collect::<Vec<(ID, &S)>>();
+                (
+                    space.id(),
+                    M::simulate_indexed(space.id(), space.state(), &neighbors),
+                )
+            }).collect::<Vec<(ID, S)>>();
+        self.keep_frozen_states(&mut states);
+        states
+    }
+
+    /// Performs simulation on QDF like `simulation_step_with()` but instead of applying results to
+    /// QDF, it returns simulated platonic space states along with their space ID.
+    ///
+    /// # Arguments
+    /// * `sim` - simulator.
+    ///
+    /// # Returns
+    /// Vector of tuples of id and its updated space that were simulated.
+    pub fn simulate_states_with<M>(&self, sim: &M) -> Vec<(ID, S)>
+    where
+        M: SimulateWith<S>,
+    {
+        let spaces = self.spaces.spaces();
+        let mut states = spaces
+            .iter()
+            .enumerate()
+            .map(|(i, space)| {
                 let neighbor_states = self
-                    .graph
-                    .neighbors(*id)
-                    .map(|i| self.spaces[&i].state())
+                    .spaces
+                    .neighbors(i as u32)
+                    .iter()
+                    .map(|n| spaces[*n as usize].state())
                     .collect::<Vec<&S>>();
-                (*id, M::simulate(self.spaces[id].state(), &neighbor_states))
-            }).collect()
+                (space.id(), sim.simulate(space.state(), &neighbor_states))
+            }).collect::<Vec<(ID, S)>>();
+        self.keep_frozen_states(&mut states);
+        states
     }
 
     /// Performs simulation on QDF like `simulation_step_parallel()` but instead of applying
@@ -585,18 +3838,202 @@ where
     pub fn simulate_states_parallel<M>(&self) -> Vec<(ID, S)>
     where
         M: Simulate<S>,
+        E: Send + Sync,
     {
-        let spaces = &self.spaces;
-        let space_ids = &self.space_ids;
-        let graph = &self.graph;
-        space_ids
+        let arena = &self.spaces;
+        let spaces = arena.spaces();
+        let mut states = spaces
             .par_iter()
-            .map(|id| {
-                let neighbor_states = graph
-                    .neighbors(*id)
-                    .map(|i| spaces[&i].state())
+            .enumerate()
+            .map(|(i, space)| {
+                let neighbor_states = arena
+                    .neighbors(i as u32)
+                    .iter()
+                    .map(|n| spaces[*n as usize].state())
                     .collect::<Vec<&S>>();
-                (*id, M::simulate(spaces[id].state(), &neighbor_states))
-            }).collect()
+                (space.id(), M::simulate(space.state(), &neighbor_states))
+            }).collect::<Vec<(ID, S)>>();
+        self.keep_frozen_states(&mut states);
+        states
+    }
+
+    /// Performs simulation on QDF like `simulation_step_parallel_in()` but instead of applying
+    /// results to QDF, it returns simulated platonic space states along with their space ID.
+    ///
+    /// # Arguments
+    /// * `pool` - thread pool that performs simulation.
+    ///
+    /// # Returns
+    /// Vector of tuples of id and its updated space that were simulated.
+    pub fn simulate_states_parallel_in<M>(&self, pool: &ThreadPool) -> Vec<(ID, S)>
+    where
+        M: Simulate<S>,
+        E: Send + Sync,
+    {
+        pool.install(|| self.simulate_states_parallel::<M>())
+    }
+}
+
+impl<S, E> PartialEq for QDF<S, E>
+where
+    S: State + PartialEq,
+    E: Clone + Default + PartialEq,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.structurally_eq(other)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S, E> Serialize for QDF<S, E>
+where
+    S: State + Serialize,
+    E: Serialize,
+{
+    fn serialize<T>(&self, serializer: T) -> StdResult<T::Ok, T::Error>
+    where
+        T: Serializer,
+    {
+        let nodes = self.graph.nodes().collect::<Vec<ID>>();
+        let spaces = nodes
+            .iter()
+            .filter_map(|id| self.spaces.get(*id))
+            .collect::<Vec<&Space<S>>>();
+        let mut state = serializer.serialize_struct("QDF", 8)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("dimensions", &self.dimensions)?;
+        state.serialize_field("spaces", &spaces)?;
+        state.serialize_field("nodes", &nodes)?;
+        let edges = ordered_edges(&self.graph)
+            .into_iter()
+            .map(|(a, b)| (a, b, &self.graph[(a, b)]))
+            .collect::<Vec<(ID, ID, &E)>>();
+        state.serialize_field("edges", &edges)?;
+        let mut lineage = self.lineage.iter().collect::<Vec<(&ID, &ID)>>();
+        lineage.sort();
+        state.serialize_field("lineage", &lineage)?;
+        let mut fields = self
+            .fields
+            .values()
+            .map(|field| {
+                let mut spaces = field.spaces().iter().cloned().collect::<Vec<ID>>();
+                spaces.sort();
+                (field.id(), spaces)
+            }).collect::<Vec<(ID, Vec<ID>)>>();
+        fields.sort();
+        state.serialize_field("fields", &fields)?;
+        let mut frozen = self.frozen.iter().collect::<Vec<&ID>>();
+        frozen.sort();
+        state.serialize_field("frozen", &frozen)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, S, E> Deserialize<'de> for QDF<S, E>
+where
+    S: State + Deserialize<'de>,
+    E: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename = "QDF")]
+        struct Data<S, E>
+        where
+            S: State,
+        {
+            id: ID,
+            dimensions: usize,
+            spaces: Vec<Space<S>>,
+            nodes: Vec<ID>,
+            edges: Vec<(ID, ID, E)>,
+            #[serde(default)]
+            lineage: Vec<(ID, ID)>,
+            #[serde(default)]
+            fields: Vec<(ID, Vec<ID>)>,
+            #[serde(default)]
+            frozen: Vec<ID>,
+        }
+
+        let data = Data::<S, E>::deserialize(deserializer)?;
+        let mut graph = UnGraphMap::with_capacity(data.nodes.len(), data.edges.len());
+        for id in data.nodes {
+            if graph.contains_node(id) {
+                return Err(D::Error::custom(format!("node {:?} is duplicated", id)));
+            }
+            graph.add_node(id);
+        }
+        for (a, b, data) in data.edges {
+            if !graph.contains_node(a) || !graph.contains_node(b) {
+                return Err(D::Error::custom(format!(
+                    "edge between {:?} and {:?} points to unknown node",
+                    a, b
+                )));
+            }
+            if a == b {
+                return Err(D::Error::custom(format!(
+                    "edge connects node {:?} with itself",
+                    a
+                )));
+            }
+            graph.add_edge(a, b, data);
+        }
+        if data.spaces.len() != graph.node_count() {
+            return Err(D::Error::custom(format!(
+                "QDF has {} spaces for {} nodes",
+                data.spaces.len(),
+                graph.node_count()
+            )));
+        }
+        let mut decoded = HashSet::with_capacity(data.spaces.len());
+        for space in &data.spaces {
+            if !graph.contains_node(space.id()) {
+                return Err(D::Error::custom(format!(
+                    "space {:?} is not a graph node",
+                    space.id()
+                )));
+            }
+            if !decoded.insert(space.id()) {
+                return Err(D::Error::custom(format!(
+                    "space {:?} is duplicated",
+                    space.id()
+                )));
+            }
+        }
+        for (id, spaces) in &data.fields {
+            if let Some(space) = spaces.iter().find(|space| !graph.contains_node(**space)) {
+                return Err(D::Error::custom(format!(
+                    "field {:?} contains unknown space {:?}",
+                    id, space
+                )));
+            }
+        }
+        if let Some(id) = data.frozen.iter().find(|id| !graph.contains_node(**id)) {
+            return Err(D::Error::custom(format!(
+                "frozen space {:?} is unknown",
+                id
+            )));
+        }
+        let spaces = SpaceArena::from_graph(data.spaces, &graph);
+        Ok(Self {
+            id: data.id,
+            graph,
+            spaces,
+            dimensions: data.dimensions,
+            lineage: data.lineage.into_iter().collect(),
+            fields: data
+                .fields
+                .into_iter()
+                .map(|(id, spaces)| (id, Field::new(id, spaces.into_iter().collect())))
+                .collect(),
+            frozen: data.frozen.into_iter().collect(),
+            topology: SubdivisionTopology::default(),
+            history: None,
+            observer: ObserverSlot::default(),
+        })
     }
 }
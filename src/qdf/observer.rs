@@ -0,0 +1,45 @@
+use id::ID;
+use std::fmt;
+
+/// Event that tells about change of QDF structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QdfEvent {
+    /// Tells that space was created.
+    SpaceCreated(ID),
+    /// Tells that space was removed.
+    SpaceRemoved(ID),
+    /// Tells that edge between spaces was added.
+    EdgeAdded(ID, ID),
+    /// Tells that edge between spaces was removed.
+    EdgeRemoved(ID, ID),
+}
+
+/// Callback that gets notified about QDF structure changes.
+pub type Observer = Box<dyn FnMut(QdfEvent) + Send + Sync>;
+
+#[derive(Default)]
+pub(crate) struct ObserverSlot(Option<Observer>);
+
+impl ObserverSlot {
+    #[inline]
+    pub(crate) fn set(&mut self, observer: Option<Observer>) -> Option<Observer> {
+        ::std::mem::replace(&mut self.0, observer)
+    }
+
+    #[inline]
+    pub(crate) fn notify(&mut self, event: QdfEvent) {
+        if let Some(observer) = &mut self.0 {
+            observer(event);
+        }
+    }
+}
+
+impl fmt::Debug for ObserverSlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_some() {
+            write!(f, "Some(Observer)")
+        } else {
+            write!(f, "None")
+        }
+    }
+}
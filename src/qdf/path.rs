@@ -0,0 +1,133 @@
+use error::*;
+use id::*;
+use qdf::*;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+// space waiting for visit, ordered so that the lowest number of hops pops first (ties are equal,
+// so visiting order is the same as in petgraph `astar()` used by `find_path()`).
+struct MinHops(u32, ID);
+
+impl PartialEq for MinHops {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for MinHops {}
+
+impl PartialOrd for MinHops {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MinHops {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+#[derive(Default)]
+struct PathScratch {
+    visit_next: BinaryHeap<MinHops>,
+    scores: HashMap<ID, u32>,
+    predecessors: HashMap<ID, ID>,
+    visited: HashSet<ID>,
+}
+
+thread_local! {
+    // buffers of path search are kept between calls, so steady searching does not allocate.
+    static PATH_SCRATCH: RefCell<PathScratch> = RefCell::new(PathScratch::default());
+}
+
+impl<S, E> QDF<S, E>
+where
+    S: State,
+    E: Clone + Default,
+{
+    /// Does the same as `find_path()` (and gives exactly the same path), but writes path into
+    /// given buffer instead of allocating new one. Search buffers are reused between calls made
+    /// on the same thread, so pathfinding done every frame stays allocation-light.
+    ///
+    /// # Arguments
+    /// * `from` - source space id.
+    /// * `to` - target space id.
+    /// * `out` - buffer that gets cleared and filled with space ids of path (including both ends).
+    ///
+    /// # Returns
+    /// `Ok` with `true` if path was found, `Ok` with `false` if it cannot be found (buffer is left
+    /// empty) or `Err` if spaces does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    /// let mut path = vec![];
+    /// assert!(qdf.find_path_into(subs2[0], subs[2], &mut path).unwrap());
+    /// assert_eq!(path, vec![subs2[0], subs2[1], subs[2]]);
+    /// assert!(qdf.find_path_into(subs[1], subs[1], &mut path).unwrap());
+    /// assert_eq!(path, vec![subs[1]]);
+    /// ```
+    pub fn find_path_into(&self, from: ID, to: ID, out: &mut Vec<ID>) -> Result<bool> {
+        if !self.space_exists(from) {
+            return Err(QDFError::SpaceDoesNotExists(from));
+        }
+        if !self.space_exists(to) {
+            return Err(QDFError::SpaceDoesNotExists(to));
+        }
+        out.clear();
+        PATH_SCRATCH.with(|scratch| {
+            let scratch = &mut *scratch.borrow_mut();
+            scratch.visit_next.clear();
+            scratch.scores.clear();
+            scratch.predecessors.clear();
+            scratch.visited.clear();
+            scratch.scores.insert(from, 0);
+            scratch.visit_next.push(MinHops(0, from));
+            while let Some(MinHops(_, node)) = scratch.visit_next.pop() {
+                if node == to {
+                    let mut current = node;
+                    out.push(current);
+                    while let Some(previous) = scratch.predecessors.get(&current) {
+                        current = *previous;
+                        out.push(current);
+                    }
+                    out.reverse();
+                    return Ok(true);
+                }
+                if !scratch.visited.insert(node) {
+                    continue;
+                }
+                let node_score = scratch.scores[&node];
+                for next in self.graph.neighbors(node) {
+                    if scratch.visited.contains(&next) {
+                        continue;
+                    }
+                    let mut next_score = node_score + 1;
+                    match scratch.scores.entry(next) {
+                        Entry::Occupied(mut entry) => {
+                            if next_score < *entry.get() {
+                                entry.insert(next_score);
+                                scratch.predecessors.insert(next, node);
+                            } else {
+                                next_score = *entry.get();
+                            }
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(next_score);
+                            scratch.predecessors.insert(next, node);
+                        }
+                    }
+                    scratch.visit_next.push(MinHops(next_score, next));
+                }
+            }
+            Ok(false)
+        })
+    }
+}
@@ -0,0 +1,212 @@
+use error::*;
+use id::*;
+use qdf::*;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+const LAYOUT_ITERATIONS: usize = 100;
+const LAYOUT_EDGE_LENGTH: f32 = 1.0;
+
+impl<S, E> QDF<S, E>
+where
+    S: State,
+    E: Clone + Default,
+{
+    /// Projects spaces onto 2D plane using force-directed (spring) layout, so universe can be
+    /// drawn. Connected spaces pull each other while all spaces repel each other. Anchor space
+    /// stays at given position and initial placement is seeded around it by graph distance, so
+    /// the result is deterministic for the same graph.
+    ///
+    /// # Arguments
+    /// * `anchor` - id of space that layout is seeded at.
+    /// * `anchor_pos` - position of anchor space.
+    ///
+    /// # Returns
+    /// `Ok` with map of space positions or `Err` if anchor space does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(2, 9);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let positions = qdf.project_2d(subs[0], [1.0, 2.0]).unwrap();
+    /// assert_eq!(positions.len(), 3);
+    /// assert_eq!(positions[&subs[0]], [1.0, 2.0]);
+    /// assert!(qdf.project_2d(root, [0.0, 0.0]).is_err());
+    /// ```
+    pub fn project_2d(&self, anchor: ID, anchor_pos: [f32; 2]) -> Result<HashMap<ID, [f32; 2]>> {
+        if !self.space_exists(anchor) {
+            return Err(QDFError::SpaceDoesNotExists(anchor));
+        }
+        let mut ids = self.bfs(anchor).collect::<Vec<ID>>();
+        let mut rings = HashMap::with_capacity(ids.len());
+        rings.insert(anchor, 0);
+        for id in &ids[1..] {
+            let ring = self
+                .graph
+                .neighbors(*id)
+                .filter_map(|n| rings.get(&n).cloned())
+                .min()
+                .unwrap_or(0);
+            rings.insert(*id, ring + 1);
+        }
+        let outer = rings.values().max().cloned().unwrap_or(0) + 1;
+        for id in self.graph.nodes() {
+            if let Entry::Vacant(entry) = rings.entry(id) {
+                entry.insert(outer);
+                ids.push(id);
+            }
+        }
+        let mut positions = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| {
+                let angle = i as f32 * 2.0 * PI / ids.len() as f32;
+                let radius = rings[id] as f32 * LAYOUT_EDGE_LENGTH;
+                [
+                    anchor_pos[0] + radius * angle.cos(),
+                    anchor_pos[1] + radius * angle.sin(),
+                ]
+            })
+            .collect::<Vec<[f32; 2]>>();
+        let indices = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect::<HashMap<ID, usize>>();
+        let edges = self
+            .graph
+            .all_edges()
+            .map(|(a, b, _)| (indices[&a], indices[&b]))
+            .collect::<Vec<(usize, usize)>>();
+        let k = LAYOUT_EDGE_LENGTH;
+        let mut temperature = k * (ids.len() as f32).sqrt();
+        let cooling = temperature / LAYOUT_ITERATIONS as f32;
+        for _ in 0..LAYOUT_ITERATIONS {
+            let mut forces = vec![[0.0f32; 2]; ids.len()];
+            for a in 0..ids.len() {
+                for b in (a + 1)..ids.len() {
+                    let (dx, dy, d) = delta(positions[a], positions[b], a, b);
+                    let f = k * k / d;
+                    forces[a][0] += dx / d * f;
+                    forces[a][1] += dy / d * f;
+                    forces[b][0] -= dx / d * f;
+                    forces[b][1] -= dy / d * f;
+                }
+            }
+            for (a, b) in &edges {
+                let (dx, dy, d) = delta(positions[*a], positions[*b], *a, *b);
+                let f = d * d / k;
+                forces[*a][0] -= dx / d * f;
+                forces[*a][1] -= dy / d * f;
+                forces[*b][0] += dx / d * f;
+                forces[*b][1] += dy / d * f;
+            }
+            for (i, force) in forces.iter().enumerate().skip(1) {
+                let length = (force[0] * force[0] + force[1] * force[1]).sqrt();
+                if length > 0.0 {
+                    let step = length.min(temperature) / length;
+                    positions[i][0] += force[0] * step;
+                    positions[i][1] += force[1] * step;
+                }
+            }
+            temperature -= cooling;
+        }
+        Ok(ids.into_iter().zip(positions).collect())
+    }
+
+    /// Embeds spaces into `D` dimensional Euclidean space with stress majorization, so distances
+    /// between positions approximate graph hop distances between spaces. Initial placement is
+    /// random, seeded by given seed, so the result is deterministic for the same graph and seed.
+    /// Spaces that are not reachable from each other do not affect each other's placement.
+    ///
+    /// # Arguments
+    /// * `iterations` - number of majorization iterations.
+    /// * `seed` - seed of initial placement.
+    ///
+    /// # Returns
+    /// Map of space positions.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::QDF;
+    ///
+    /// let (mut qdf, root) = QDF::new(3, 16);
+    /// let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    /// let positions = qdf.project::<3>(50, 42);
+    /// assert_eq!(positions.len(), 4);
+    /// let distance = |a: [f32; 3], b: [f32; 3]| {
+    ///     (0..3).map(|i| (a[i] - b[i]).powi(2)).sum::<f32>().sqrt()
+    /// };
+    /// // tetrahedron with unit edges.
+    /// assert!((distance(positions[&subs[0]], positions[&subs[3]]) - 1.0).abs() < 1.0e-3);
+    /// assert_eq!(positions, qdf.project::<3>(50, 42));
+    /// ```
+    pub fn project<const D: usize>(&self, iterations: usize, seed: u64) -> HashMap<ID, [f32; D]> {
+        let ids = self.graph.nodes().collect::<Vec<ID>>();
+        let distances = self.distance_matrix(&ids).unwrap();
+        let mut gen = IdGenerator::new(seed);
+        let scale = (ids.len() as f32).sqrt();
+        let mut positions = ids
+            .iter()
+            .map(|_| {
+                let mut position = [0.0; D];
+                for value in position.iter_mut() {
+                    let random = (gen.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+                    *value = (random * 2.0 - 1.0) * scale;
+                }
+                position
+            })
+            .collect::<Vec<[f32; D]>>();
+        for _ in 0..iterations {
+            for i in 0..ids.len() {
+                let mut sum = [0.0; D];
+                let mut total_weight = 0.0;
+                for j in 0..ids.len() {
+                    let target = distances[i][j];
+                    if i == j || target == usize::MAX {
+                        continue;
+                    }
+                    let target = target as f32;
+                    let weight = 1.0 / (target * target);
+                    let (pi, pj) = (positions[i], positions[j]);
+                    let current = (0..D)
+                        .map(|k| (pi[k] - pj[k]) * (pi[k] - pj[k]))
+                        .sum::<f32>()
+                        .sqrt();
+                    for (k, value) in sum.iter_mut().enumerate() {
+                        let direction = if current > 1.0e-6 {
+                            (pi[k] - pj[k]) / current
+                        } else {
+                            0.0
+                        };
+                        *value += weight * (pj[k] + target * direction);
+                    }
+                    total_weight += weight;
+                }
+                if total_weight > 0.0 {
+                    for (k, value) in sum.iter().enumerate() {
+                        positions[i][k] = value / total_weight;
+                    }
+                }
+            }
+        }
+        ids.into_iter().zip(positions).collect()
+    }
+}
+
+fn delta(a: [f32; 2], b: [f32; 2], ia: usize, ib: usize) -> (f32, f32, f32) {
+    let mut dx = a[0] - b[0];
+    let mut dy = a[1] - b[1];
+    let mut d = (dx * dx + dy * dy).sqrt();
+    if d < 1.0e-4 {
+        // separate overlapping spaces in deterministic direction.
+        let angle = (ia * 31 + ib * 17) as f32;
+        dx = angle.cos() * 1.0e-2;
+        dy = angle.sin() * 1.0e-2;
+        d = 1.0e-2;
+    }
+    (dx, dy, d)
+}
@@ -1,3 +1,4 @@
+use id::ID;
 use state::*;
 
 /// Trait that tells QDF how to simulate states of space.
@@ -11,6 +12,18 @@ where
     /// * `state` - current state.
     /// * `neighbor_states` - current neighbor states.
     fn simulate(state: &S, neighbor_states: &[&S]) -> S;
+
+    /// Performs simulation of state based on neighbor states, knowing ids of simulated space and
+    /// its neighbors. By default ids are ignored and it does the same as `simulate()`.
+    ///
+    /// # Arguments
+    /// * `id` - simulated space id.
+    /// * `state` - current state.
+    /// * `neighbors` - current neighbor ids and states (in the same order during simulation step).
+    fn simulate_indexed(_id: ID, state: &S, neighbors: &[(ID, &S)]) -> S {
+        let neighbor_states = neighbors.iter().map(|(_, s)| *s).collect::<Vec<&S>>();
+        Self::simulate(state, &neighbor_states)
+    }
 }
 
 impl<S> Simulate<S> for ()
@@ -21,3 +34,17 @@ where
         state.clone()
     }
 }
+
+/// Trait that tells QDF how to simulate states of space, using simulator instance (so it can
+/// carry simulation parameters).
+pub trait SimulateWith<S>
+where
+    S: State,
+{
+    /// Performs simulation of state based on neighbor states.
+    ///
+    /// # Arguments
+    /// * `state` - current state.
+    /// * `neighbor_states` - current neighbor states.
+    fn simulate(&self, state: &S, neighbor_states: &[&S]) -> S;
+}
@@ -1,14 +1,19 @@
 use id::*;
 use qdf::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Holds information about space region.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Space<S>
 where
     S: State,
 {
     id: ID,
     state: S,
+    #[cfg_attr(feature = "serde", serde(default))]
+    parent: Option<ID>,
 }
 
 impl<S> Space<S>
@@ -17,7 +22,20 @@ where
 {
     #[inline]
     pub(crate) fn new(id: ID, state: S) -> Self {
-        Self { id, state }
+        Self {
+            id,
+            state,
+            parent: None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn with_parent(id: ID, state: S, parent: ID) -> Self {
+        Self {
+            id,
+            state,
+            parent: Some(parent),
+        }
     }
 
     /// Gets space id.
@@ -26,6 +44,13 @@ where
         self.id
     }
 
+    /// Gets id of space that this space was subdivided from (`None` for spaces that were not
+    /// created by subdivision, like root space or space created by merging).
+    #[inline]
+    pub fn parent(&self) -> Option<ID> {
+        self.parent
+    }
+
     /// Gets space state.
     #[inline]
     pub fn state(&self) -> &S {
@@ -36,6 +61,11 @@ where
     pub(crate) fn apply_state(&mut self, state: S) {
         self.state = state;
     }
+
+    #[inline]
+    pub(crate) fn state_mut(&mut self) -> &mut S {
+        &mut self.state
+    }
 }
 
 impl<S> Default for Space<S>
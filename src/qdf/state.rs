@@ -1,4 +1,10 @@
+use bytes::{write_usize, BytesReader};
+use error::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::fmt::Debug;
+use std::result::Result as StdResult;
 
 /// Trait that describes QDF space state.
 ///
@@ -35,6 +41,24 @@ pub trait State: Sized + Clone + Default + Send + Sync + Debug {
     /// # Arguments
     /// * `states` - list of source data to merge.
     fn merge(states: &[Self]) -> Self;
+    /// Merge multiple data instances into one, taking given weights into account.
+    /// By default weights are ignored and it does the same as `merge()`.
+    ///
+    /// # Arguments
+    /// * `states` - list of source data to merge.
+    /// * `weights` - list of weights paired with source data (missing weights equals `1.0`).
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::State;
+    ///
+    /// assert_eq!(State::merge_weighted(&[1.0, 2.0], &[0.5, 0.25]), 1.0);
+    /// assert_eq!(State::merge_weighted(&[1, 2], &[0.5, 0.25]), 3);
+    /// ```
+    #[inline]
+    fn merge_weighted(states: &[Self], _weights: &[f64]) -> Self {
+        Self::merge(states)
+    }
     /// Multiply and merge multiple instances of itself into one super state.
     ///
     /// # Arguments
@@ -47,87 +71,302 @@ pub trait State: Sized + Clone + Default + Send + Sync + Debug {
             .collect::<Vec<Self>>();
         Self::merge(&states)
     }
+    /// Write binary representation of state into buffer.
+    ///
+    /// # Arguments
+    /// * `buf` - target buffer.
+    ///
+    /// # Panics
+    /// When state does not implement binary encoding.
+    fn encode(&self, _buf: &mut Vec<u8>) {
+        panic!("`State::encode()` is not implemented for this state type")
+    }
+    /// Read state from its binary representation.
+    ///
+    /// # Arguments
+    /// * `data` - bytes produced by `encode()`.
+    ///
+    /// # Returns
+    /// `Ok` with decoded state or `Err` if data is invalid.
+    ///
+    /// # Panics
+    /// When state does not implement binary encoding.
+    fn decode(_data: &[u8]) -> Result<Self> {
+        panic!("`State::decode()` is not implemented for this state type")
+    }
+}
+
+/// Implements `State` for given type with subdivision and merging taken from two functions (or
+/// closures that do not capture anything), so new field semantics can be prototyped without
+/// writing whole trait implementation. Type must still derive `Clone`, `Default` and `Debug`.
+///
+/// # Examples
+/// ```
+/// #[macro_use]
+/// extern crate quantized_density_fields;
+///
+/// use quantized_density_fields::{State, QDF};
+///
+/// // field that keeps the highest value instead of summing them.
+/// #[derive(Debug, Default, Clone, Copy, PartialEq)]
+/// struct Max(f64);
+///
+/// impl_state!(Max,
+///     subdivide: |state: &Max, subdivisions| vec![*state; subdivisions],
+///     merge: |states: &[Max]| Max(states.iter().fold(0.0, |a, s| s.0.max(a))),
+/// );
+///
+/// fn main() {
+///     let (mut qdf, root) = QDF::new(2, Max(1.0));
+///     let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+///     qdf.set_space_state(subs[1], Max(5.0)).unwrap();
+///     assert_eq!(*qdf.space(subs[0]).state(), Max(1.0));
+///     assert_eq!(qdf.total_state(), Max(5.0));
+/// }
+/// ```
+#[macro_export]
+macro_rules! impl_state {
+    ($type:ty, subdivide: $subdivide:expr, merge: $merge:expr $(,)*) => {
+        impl $crate::State for $type {
+            fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
+                let subdivide: fn(&$type, usize) -> Vec<$type> = $subdivide;
+                subdivide(self, subdivisions)
+            }
+            fn merge(states: &[Self]) -> Self {
+                let merge: fn(&[$type]) -> $type = $merge;
+                merge(states)
+            }
+        }
+    };
+}
+
+/// Trait that describes fallible subdivision of QDF space state.
+/// Implement it when subdivision may break state rules (e.g. conservation of quantity) and you
+/// want to get an error instead of invalid substates.
+pub trait TrySubdivide: State {
+    /// Error returned when state cannot be subdivided.
+    type Error;
+
+    /// Create data template that we get by subdivision of source data or return error if source
+    /// data cannot be subdivided.
+    ///
+    /// # Arguments
+    /// * `subdivisions` - number of subdivisions.
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error>;
+}
+
+fn decode_bytes<A>(data: &[u8]) -> Result<A>
+where
+    A: Default + AsMut<[u8]>,
+{
+    let mut bytes = A::default();
+    if bytes.as_mut().len() == data.len() {
+        bytes.as_mut().copy_from_slice(data);
+        Ok(bytes)
+    } else {
+        Err(QDFError::DecodeError(format!(
+            "expected {} bytes of state but got {}",
+            bytes.as_mut().len(),
+            data.len()
+        )))
+    }
 }
 
 impl State for i8 {
     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
-        ::std::iter::repeat(self / subdivisions as Self)
-            .take(subdivisions)
+        let value = self / subdivisions as Self;
+        let remainder = (self % subdivisions as Self).unsigned_abs() as usize;
+        let sign = self.signum();
+        (0..subdivisions)
+            .map(|i| if i < remainder { value + sign } else { value })
             .collect()
     }
     fn merge(states: &[Self]) -> Self {
         states.iter().sum()
     }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(Self::from_le_bytes(decode_bytes(data)?))
+    }
+}
+impl TrySubdivide for i8 {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
 }
 impl State for i16 {
     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
-        ::std::iter::repeat(self / subdivisions as Self)
-            .take(subdivisions)
+        let value = self / subdivisions as Self;
+        let remainder = (self % subdivisions as Self).unsigned_abs() as usize;
+        let sign = self.signum();
+        (0..subdivisions)
+            .map(|i| if i < remainder { value + sign } else { value })
             .collect()
     }
     fn merge(states: &[Self]) -> Self {
         states.iter().sum()
     }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(Self::from_le_bytes(decode_bytes(data)?))
+    }
+}
+impl TrySubdivide for i16 {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
 }
 impl State for i32 {
     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
-        ::std::iter::repeat(self / subdivisions as Self)
-            .take(subdivisions)
+        let value = self / subdivisions as Self;
+        let remainder = (self % subdivisions as Self).unsigned_abs() as usize;
+        let sign = self.signum();
+        (0..subdivisions)
+            .map(|i| if i < remainder { value + sign } else { value })
             .collect()
     }
     fn merge(states: &[Self]) -> Self {
         states.iter().sum()
     }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(Self::from_le_bytes(decode_bytes(data)?))
+    }
+}
+impl TrySubdivide for i32 {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
 }
 impl State for i64 {
     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
-        ::std::iter::repeat(self / subdivisions as Self)
-            .take(subdivisions)
+        let value = self / subdivisions as Self;
+        let remainder = (self % subdivisions as Self).unsigned_abs() as usize;
+        let sign = self.signum();
+        (0..subdivisions)
+            .map(|i| if i < remainder { value + sign } else { value })
             .collect()
     }
     fn merge(states: &[Self]) -> Self {
         states.iter().sum()
     }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(Self::from_le_bytes(decode_bytes(data)?))
+    }
+}
+impl TrySubdivide for i64 {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
 }
 impl State for u8 {
     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
-        ::std::iter::repeat(self / subdivisions as Self)
-            .take(subdivisions)
+        let value = self / subdivisions as Self;
+        let remainder = (self % subdivisions as Self) as usize;
+        (0..subdivisions)
+            .map(|i| if i < remainder { value + 1 } else { value })
             .collect()
     }
     fn merge(states: &[Self]) -> Self {
         states.iter().sum()
     }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(Self::from_le_bytes(decode_bytes(data)?))
+    }
+}
+impl TrySubdivide for u8 {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
 }
 impl State for u16 {
     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
-        ::std::iter::repeat(self / subdivisions as Self)
-            .take(subdivisions)
+        let value = self / subdivisions as Self;
+        let remainder = (self % subdivisions as Self) as usize;
+        (0..subdivisions)
+            .map(|i| if i < remainder { value + 1 } else { value })
             .collect()
     }
     fn merge(states: &[Self]) -> Self {
         states.iter().sum()
     }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(Self::from_le_bytes(decode_bytes(data)?))
+    }
+}
+impl TrySubdivide for u16 {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
 }
 impl State for u32 {
     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
-        ::std::iter::repeat(self / subdivisions as Self)
-            .take(subdivisions)
+        let value = self / subdivisions as Self;
+        let remainder = (self % subdivisions as Self) as usize;
+        (0..subdivisions)
+            .map(|i| if i < remainder { value + 1 } else { value })
             .collect()
     }
     fn merge(states: &[Self]) -> Self {
         states.iter().sum()
     }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(Self::from_le_bytes(decode_bytes(data)?))
+    }
+}
+impl TrySubdivide for u32 {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
 }
 impl State for u64 {
     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
-        ::std::iter::repeat(self / subdivisions as Self)
-            .take(subdivisions)
+        let value = self / subdivisions as Self;
+        let remainder = (self % subdivisions as Self) as usize;
+        (0..subdivisions)
+            .map(|i| if i < remainder { value + 1 } else { value })
             .collect()
     }
     fn merge(states: &[Self]) -> Self {
         states.iter().sum()
     }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(Self::from_le_bytes(decode_bytes(data)?))
+    }
+}
+impl TrySubdivide for u64 {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
 }
 impl State for f32 {
     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
@@ -138,6 +377,25 @@ impl State for f32 {
     fn merge(states: &[Self]) -> Self {
         states.iter().sum()
     }
+    fn merge_weighted(states: &[Self], weights: &[f64]) -> Self {
+        states
+            .iter()
+            .zip(weights.iter().chain(::std::iter::repeat(&1.0)))
+            .map(|(s, w)| s * *w as Self)
+            .sum()
+    }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(Self::from_le_bytes(decode_bytes(data)?))
+    }
+}
+impl TrySubdivide for f32 {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
 }
 impl State for f64 {
     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
@@ -148,24 +406,285 @@ impl State for f64 {
     fn merge(states: &[Self]) -> Self {
         states.iter().sum()
     }
+    fn merge_weighted(states: &[Self], weights: &[f64]) -> Self {
+        states
+            .iter()
+            .zip(weights.iter().chain(::std::iter::repeat(&1.0)))
+            .map(|(s, w)| s * *w)
+            .sum()
+    }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.to_le_bytes());
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(Self::from_le_bytes(decode_bytes(data)?))
+    }
+}
+impl TrySubdivide for f64 {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
 }
 impl State for isize {
     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
-        ::std::iter::repeat(self / subdivisions as Self)
-            .take(subdivisions)
+        let value = self / subdivisions as Self;
+        let remainder = (self % subdivisions as Self).unsigned_abs();
+        let sign = self.signum();
+        (0..subdivisions)
+            .map(|i| if i < remainder { value + sign } else { value })
             .collect()
     }
     fn merge(states: &[Self]) -> Self {
         states.iter().sum()
     }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&(*self as i64).to_le_bytes());
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(i64::from_le_bytes(decode_bytes(data)?) as Self)
+    }
+}
+impl TrySubdivide for isize {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
 }
 impl State for usize {
     fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
-        ::std::iter::repeat(self / subdivisions as Self)
-            .take(subdivisions)
+        let value = self / subdivisions;
+        let remainder = self % subdivisions;
+        (0..subdivisions)
+            .map(|i| if i < remainder { value + 1 } else { value })
             .collect()
     }
     fn merge(states: &[Self]) -> Self {
         states.iter().sum()
     }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&(*self as u64).to_le_bytes());
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        Ok(u64::from_le_bytes(decode_bytes(data)?) as Self)
+    }
+}
+impl TrySubdivide for usize {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
+}
+/// Boolean state describes occupancy: subdivision replicates value into every subspace and
+/// merging is logical OR, so region is occupied if any of its subregions is.
+/// Unlike numeric sum-merge it is not invertible - subdividing merged state marks all subspaces
+/// as occupied, even if only one of merged spaces was.
+///
+/// # Examples
+/// ```
+/// use quantized_density_fields::State;
+///
+/// assert_eq!(true.subdivide(3), vec![true, true, true]);
+/// assert!(State::merge(&[false, true, false]));
+/// assert!(!State::merge(&[false, false]));
+/// ```
+impl State for bool {
+    fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
+        vec![*self; subdivisions]
+    }
+    fn merge(states: &[Self]) -> Self {
+        states.iter().any(|s| *s)
+    }
+    #[inline]
+    fn super_state_at_level(&self, _dimensions: usize, _level: usize) -> Self {
+        *self
+    }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8);
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        match decode_bytes::<[u8; 1]>(data)? {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            [v] => Err(QDFError::DecodeError(format!(
+                "expected boolean state but got {}",
+                v
+            ))),
+        }
+    }
+}
+impl TrySubdivide for bool {
+    type Error = Infallible;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        Ok(self.subdivide(subdivisions))
+    }
+}
+impl<T, const N: usize> State for [T; N]
+where
+    T: State,
+    [T; N]: Default,
+{
+    fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
+        let channels = self
+            .iter()
+            .map(|state| state.subdivide(subdivisions))
+            .collect::<Vec<Vec<T>>>();
+        (0..subdivisions)
+            .map(|i| ::std::array::from_fn(|c| channels[c][i].clone()))
+            .collect()
+    }
+    fn merge(states: &[Self]) -> Self {
+        ::std::array::from_fn(|c| {
+            T::merge(&states.iter().map(|s| s[c].clone()).collect::<Vec<T>>())
+        })
+    }
+    fn merge_weighted(states: &[Self], weights: &[f64]) -> Self {
+        ::std::array::from_fn(|c| {
+            T::merge_weighted(
+                &states.iter().map(|s| s[c].clone()).collect::<Vec<T>>(),
+                weights,
+            )
+        })
+    }
+    fn encode(&self, buf: &mut Vec<u8>) {
+        let mut state = vec![];
+        for channel in self {
+            state.clear();
+            channel.encode(&mut state);
+            write_usize(buf, state.len());
+            buf.extend_from_slice(&state);
+        }
+    }
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = BytesReader::new(data);
+        let mut channels = Vec::with_capacity(N);
+        for _ in 0..N {
+            let size = reader.read_usize()?;
+            channels.push(T::decode(reader.read(size)?)?);
+        }
+        if !reader.is_empty() {
+            return Err(QDFError::DecodeError(
+                "unexpected data after state channels".to_owned(),
+            ));
+        }
+        let mut channels = channels.into_iter();
+        Ok(::std::array::from_fn(|_| channels.next().unwrap()))
+    }
+}
+impl<T, const N: usize> TrySubdivide for [T; N]
+where
+    T: TrySubdivide,
+    [T; N]: Default,
+{
+    type Error = T::Error;
+    fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+        let channels = self
+            .iter()
+            .map(|state| state.try_subdivide(subdivisions))
+            .collect::<StdResult<Vec<Vec<T>>, T::Error>>()?;
+        Ok((0..subdivisions)
+            .map(|i| ::std::array::from_fn(|c| channels[c][i].clone()))
+            .collect())
+    }
+}
+
+macro_rules! impl_tuple_state {
+    ($($t:ident: $i:tt),+) => {
+        impl<$($t),+> State for ($($t,)+)
+        where
+            $($t: State),+
+        {
+            fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
+                let components = ($(self.$i.subdivide(subdivisions),)+);
+                (0..subdivisions)
+                    .map(|i| ($(components.$i[i].clone(),)+))
+                    .collect()
+            }
+            fn merge(states: &[Self]) -> Self {
+                ($($t::merge(&states.iter().map(|s| s.$i.clone()).collect::<Vec<$t>>()),)+)
+            }
+            fn merge_weighted(states: &[Self], weights: &[f64]) -> Self {
+                ($($t::merge_weighted(
+                    &states.iter().map(|s| s.$i.clone()).collect::<Vec<$t>>(),
+                    weights,
+                ),)+)
+            }
+            fn encode(&self, buf: &mut Vec<u8>) {
+                let mut state = vec![];
+                $(
+                    state.clear();
+                    self.$i.encode(&mut state);
+                    write_usize(buf, state.len());
+                    buf.extend_from_slice(&state);
+                )+
+            }
+            fn decode(data: &[u8]) -> Result<Self> {
+                let mut reader = BytesReader::new(data);
+                let result = ($({
+                    let size = reader.read_usize()?;
+                    $t::decode(reader.read(size)?)?
+                },)+);
+                if reader.is_empty() {
+                    Ok(result)
+                } else {
+                    Err(QDFError::DecodeError(
+                        "unexpected data after state components".to_owned(),
+                    ))
+                }
+            }
+        }
+    };
+}
+
+impl_tuple_state!(A: 0, B: 1);
+impl_tuple_state!(A: 0, B: 1, C: 2);
+impl_tuple_state!(A: 0, B: 1, C: 2, D: 3);
+
+/// Integer state wrapper which merges with saturating addition, so merging (e.g. in
+/// `QDF::total_state()` of big universe) clamps at bounds of integer type instead of overflowing.
+/// Subdivision and binary encoding are the same as of wrapped integer.
+///
+/// # Examples
+/// ```
+/// use quantized_density_fields::{SaturatingState, State};
+///
+/// let states = [SaturatingState(i8::MAX), SaturatingState(1)];
+/// assert_eq!(State::merge(&states), SaturatingState(i8::MAX));
+/// assert_eq!(SaturatingState(9u8).subdivide(2), vec![SaturatingState(5), SaturatingState(4)]);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SaturatingState<T>(pub T);
+
+macro_rules! impl_saturating_state {
+    ($($t:ty),+) => {
+        $(
+            impl State for SaturatingState<$t> {
+                fn subdivide(&self, subdivisions: usize) -> Vec<Self> {
+                    self.0
+                        .subdivide(subdivisions)
+                        .into_iter()
+                        .map(SaturatingState)
+                        .collect()
+                }
+                fn merge(states: &[Self]) -> Self {
+                    SaturatingState(states.iter().fold(0, |a, s| a.saturating_add(s.0)))
+                }
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    self.0.encode(buf);
+                }
+                fn decode(data: &[u8]) -> Result<Self> {
+                    Ok(SaturatingState(<$t>::decode(data)?))
+                }
+            }
+            impl TrySubdivide for SaturatingState<$t> {
+                type Error = Infallible;
+                fn try_subdivide(&self, subdivisions: usize) -> StdResult<Vec<Self>, Self::Error> {
+                    Ok(self.subdivide(subdivisions))
+                }
+            }
+        )+
+    };
 }
+
+impl_saturating_state!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
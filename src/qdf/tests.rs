@@ -1,9 +1,25 @@
 #![cfg(test)]
 
 use super::*;
+use graph::escape_dot;
+use std::collections::{HashMap, HashSet};
 // use test::Bencher;
 
+// QDF subdivided to given levels with every space state computed from its index.
+fn indexed_levels<S, F>(dimensions: usize, levels: usize, state: F) -> (QDF<S>, Vec<ID>)
+where
+    S: State,
+    F: Fn(usize) -> S,
+{
+    let (mut qdf, spaces) = QDF::with_levels(dimensions, S::default(), levels);
+    for (i, id) in spaces.iter().enumerate() {
+        qdf.set_space_state(*id, state(i)).unwrap();
+    }
+    (qdf, spaces)
+}
+
 #[test]
+#[allow(clippy::redundant_pattern_matching, clippy::assertions_on_constants)]
 fn test_2d() {
     let (mut qdf, root) = QDF::new(2, 9);
     assert!(qdf.space_exists(root));
@@ -51,7 +67,7 @@ fn test_2d() {
         qdf.find_space_neighbors(subspace2[2]).unwrap(),
         vec![subspace2[0], subspace2[1]]
     );
-    assert_eq!(qdf.find_space_neighbors(subspace[0]).unwrap(), vec![]);
+    assert!(qdf.find_space_neighbors(subspace[0]).is_err());
     assert_eq!(
         qdf.find_space_neighbors(subspace[1]).unwrap(),
         vec![subspace[2], subspace2[0]]
@@ -62,7 +78,7 @@ fn test_2d() {
     );
     assert_eq!(
         qdf.find_path(subspace2[0], subspace[2]).unwrap(),
-        vec![subspace2[0], subspace2[1], subspace[2]]
+        Some(vec![subspace2[0], subspace2[1], subspace[2]])
     );
 
     {
@@ -81,7 +97,7 @@ fn test_2d() {
     qdf.simulation_step::<()>();
     qdf.simulation_step_parallel::<()>();
 
-    let (_, uberspace2) = qdf.decrease_space_density(subspace2[0]).unwrap().unwrap();
+    let uberspace2 = qdf.decrease_space_density(subspace2[0]).unwrap().unwrap().id;
     assert_eq!(
         qdf.find_space_neighbors(uberspace2).unwrap(),
         vec![subspace[2], subspace[1]]
@@ -96,14 +112,14 @@ fn test_2d() {
     );
     assert_eq!(
         qdf.find_path(uberspace2, subspace[2]).unwrap(),
-        vec![uberspace2, subspace[2]]
+        Some(vec![uberspace2, subspace[2]])
     );
-    let (_, uberspace) = qdf.decrease_space_density(uberspace2).unwrap().unwrap();
+    let uberspace = qdf.decrease_space_density(uberspace2).unwrap().unwrap().id;
     assert_eq!(qdf.find_space_neighbors(uberspace).unwrap(), vec![]);
 
     {
         let (mut qdf, root) = QDF::new(2, 1);
-        increase_space_density(&mut qdf, root, 10).unwrap();
+        qdf.subdivide_to_depth(root, 10).unwrap();
         for id in qdf.spaces() {
             let len = qdf.find_space_neighbors(*id).unwrap().len();
             assert!(len > 0 && len <= 3);
@@ -111,11 +127,1775 @@ fn test_2d() {
     }
 }
 
+#[test]
+fn test_integer_subdivide_conservation() {
+    macro_rules! check {
+        ($range:expr; $($t:ty),*) => {$(
+            for value in $range {
+                let value = value as $t;
+                for subdivisions in 1..=7 {
+                    let substates = value.subdivide(subdivisions);
+                    assert_eq!(substates.len(), subdivisions);
+                    assert_eq!(<$t as State>::merge(&substates), value);
+                    let min = *substates.iter().min().unwrap();
+                    let max = *substates.iter().max().unwrap();
+                    assert!(max - min <= 1);
+                }
+            }
+        )*};
+    }
+    check!(0..=100; i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+    check!(-100..=0; i8, i16, i32, i64, isize);
+    assert_eq!(9i32.subdivide(2), vec![5, 4]);
+    assert_eq!((-9i32).subdivide(2), vec![-5, -4]);
+    assert_eq!(10u8.subdivide(4), vec![3, 3, 2, 2]);
+    assert_eq!(State::merge(&9i32.subdivide(2)), 9);
+}
+
+#[test]
+fn test_ancestors() {
+    let (mut qdf, root) = QDF::new(2, 27);
+    let subs = qdf.subdivide_to_depth(root, 3).unwrap();
+    for id in &subs {
+        let ancestors = qdf.ancestors(*id);
+        assert_eq!(ancestors.len(), 3);
+        assert_eq!(Some(ancestors[0]), qdf.space(*id).parent());
+        assert_eq!(ancestors[2], root);
+        assert!(ancestors.iter().all(|a| !qdf.space_exists(*a)));
+    }
+    let siblings = subs
+        .iter()
+        .filter(|id| qdf.space(**id).parent() == qdf.space(subs[0]).parent())
+        .count();
+    assert_eq!(siblings, 3);
+    let merged = qdf.decrease_space_density(subs[0]).unwrap().unwrap();
+    assert!(qdf.ancestors(merged.id).is_empty());
+    let (_, again, _) = qdf.increase_space_density(merged.id).unwrap();
+    assert_eq!(qdf.ancestors(again[0]), vec![merged.id]);
+    assert!(qdf.ancestors(ID::new()).is_empty());
+}
+
+#[test]
+fn test_error_display() {
+    use std::error::Error;
+
+    let (qdf, root) = QDF::new(2, 1);
+    let missing = ID::new();
+    let error = qdf.get_space(missing).unwrap_err();
+    assert_eq!(error.to_string(), format!("space {} does not exist", missing));
+    let boxed: Box<dyn Error> = Box::new(error);
+    assert!(boxed.to_string().contains(&missing.to_string()));
+    let (other, _) = QDF::new(3, 1);
+    let mut qdf = qdf;
+    let error = qdf.absorb(other, (root, root)).unwrap_err();
+    assert_eq!(error.to_string(), "dimensions mismatch: expected 2, got 3");
+    let error = QDFError::NotUniform(root);
+    assert!(error.to_string().starts_with(&format!("space {} breaks", root)));
+}
+
+#[test]
+fn test_fields() {
+    let (mut qdf, root) = QDF::new(2, 27);
+    let subs = qdf.subdivide_to_depth(root, 2).unwrap();
+    let field = qdf.create_field(subs[..3].to_vec()).unwrap();
+    let other = qdf.create_field(vec![subs[3]]).unwrap();
+    assert_eq!(qdf.fields().count(), 2);
+    assert_eq!(qdf.get_field(field).unwrap().id(), field);
+    assert_eq!(qdf.get_field(field).unwrap().len(), 3);
+
+    let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    {
+        let f = qdf.get_field(field).unwrap();
+        assert_eq!(f.len(), 5);
+        assert!(!f.contains(subs[0]));
+        assert!(subs2.iter().all(|id| f.contains(*id)));
+    }
+    let info = qdf.decrease_space_density(subs2[0]).unwrap().unwrap();
+    assert!(qdf.get_field(field).unwrap().contains(info.id));
+    assert!(info
+        .merged
+        .iter()
+        .all(|id| !qdf.get_field(field).unwrap().contains(*id)));
+    qdf.remove_space(subs[3]).unwrap();
+    assert!(qdf.get_field(other).unwrap().is_empty());
+    assert!(qdf.validate().is_ok());
+
+    let missing = ID::new();
+    match qdf.get_field(missing) {
+        Err(QDFError::FieldDoesNotExists(id)) => assert_eq!(id, missing),
+        _ => panic!("missing field must not be found"),
+    }
+    match qdf.add_field_space(missing, subs[1]) {
+        Err(QDFError::FieldDoesNotExists(id)) => assert_eq!(id, missing),
+        _ => panic!("missing field must not be updated"),
+    }
+    match qdf.add_field_space(field, missing) {
+        Err(QDFError::SpaceDoesNotExists(id)) => assert_eq!(id, missing),
+        _ => panic!("missing space must not be added"),
+    }
+    assert!(qdf.remove_field_space(missing, subs[1]).is_err());
+    assert!(qdf.create_field(vec![subs[1], missing]).is_err());
+    assert!(qdf.remove_field(other).unwrap().is_empty());
+    assert!(qdf.remove_field(other).is_err());
+    assert_eq!(qdf.fields().collect::<Vec<_>>(), vec![&field]);
+}
+
+#[test]
+fn test_sample_region() {
+    let (mut qdf, root) = QDF::new(3, 4096);
+    let subs = qdf.subdivide_to_depth(root, 3).unwrap();
+    for id in &subs {
+        for radius in 0..4 {
+            let count = qdf.neighbors_within(*id, radius).unwrap().len() + 1;
+            assert_eq!(qdf.sample_region(*id, radius).unwrap(), 64 * count as i32);
+        }
+        assert_eq!(qdf.sample_region(*id, 100).unwrap(), 4096);
+    }
+    match qdf.sample_region(root, 1) {
+        Err(QDFError::SpaceDoesNotExists(id)) => assert_eq!(id, root),
+        _ => panic!("removed space must not be sampled"),
+    }
+}
+
+#[test]
+fn test_sample_region_weighted() {
+    let (mut qdf, root) = QDF::new(2, 81.0f64);
+    let subs = qdf.subdivide_to_depth(root, 4).unwrap();
+    for id in subs.iter().take(10) {
+        let flat = qdf.sample_region_weighted(*id, 3, |_| 1.0).unwrap();
+        assert!((flat - qdf.sample_region(*id, 3).unwrap()).abs() < 1e-9);
+        let near = qdf.sample_region_weighted(*id, 3, |h| if h == 0 { 1.0 } else { 0.0 });
+        assert_eq!(near.unwrap(), 1.0);
+        let mut expected = 1.0;
+        for hops in 1..4 {
+            let ring = qdf.neighbors_within(*id, hops).unwrap().len()
+                - qdf.neighbors_within(*id, hops - 1).unwrap().len();
+            expected += ring as f64 / (hops + 1) as f64;
+        }
+        let blurred = qdf
+            .sample_region_weighted(*id, 3, |h| 1.0 / (h + 1) as f64)
+            .unwrap();
+        assert!((blurred - expected).abs() < 1e-9);
+    }
+    let (qdf, root) = QDF::new(2, 9);
+    assert_eq!(qdf.sample_region_weighted(root, 1, |_| 0.5).unwrap(), 9);
+    assert!(qdf.sample_region_weighted(ID::new(), 1, |_| 1.0).is_err());
+}
+
+#[test]
+fn test_find_path_into() {
+    let (mut qdf, root) = QDF::new(2, 729);
+    let subs = qdf.subdivide_to_depth(root, 4).unwrap();
+    for id in subs.iter().step_by(7) {
+        if qdf.space_exists(*id) {
+            qdf.decrease_space_density(*id).unwrap();
+        }
+    }
+    let spaces = qdf.spaces().cloned().collect::<Vec<ID>>();
+    let mut path = vec![ID::new()];
+    for from in spaces.iter().step_by(5) {
+        for to in spaces.iter().step_by(3) {
+            let found = qdf.find_path_into(*from, *to, &mut path).unwrap();
+            assert!(found);
+            assert_eq!(Some(path.clone()), qdf.find_path(*from, *to).unwrap());
+        }
+    }
+
+    let (mut qdf, root) = QDF::new(2, 9);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    qdf.remove_edge(subs[0], subs[1]);
+    qdf.remove_edge(subs[0], subs[2]);
+    assert!(!qdf.find_path_into(subs[0], subs[1], &mut path).unwrap());
+    assert!(path.is_empty());
+    assert!(qdf.find_path_into(subs[0], root, &mut path).is_err());
+}
+
+#[test]
+fn test_simulation_step_strict() {
+    struct Wave;
+
+    impl Simulate<i32> for Wave {
+        fn simulate(state: &i32, neighbor_states: &[&i32]) -> i32 {
+            neighbor_states.iter().map(|s| **s).max().unwrap_or(*state)
+        }
+    }
+
+    for dimensions in 1..4 {
+        let (mut qdf, root) = QDF::new(dimensions, 4096);
+        qdf.simulation_step_strict::<Wave>().unwrap();
+        qdf.subdivide_to_depth(root, 3).unwrap();
+        qdf.simulation_step_strict::<Wave>().unwrap();
+        let corner = qdf.boundary_spaces()[0];
+        let neighbor = qdf.find_space_neighbors(corner).unwrap()[0];
+        qdf.remove_edge(corner, neighbor);
+        let before = qdf.iter_spaces().map(|(_, s)| *s.state()).collect::<Vec<i32>>();
+        match qdf.simulation_step_strict::<Wave>() {
+            Err(QDFError::UnexpectedDegree(id, degree)) => {
+                assert!(id == corner || id == neighbor);
+                assert_eq!(degree, qdf.space_degree(id).unwrap());
+            }
+            _ => panic!("space with missing neighbor must be reported"),
+        }
+        let after = qdf.iter_spaces().map(|(_, s)| *s.state()).collect::<Vec<i32>>();
+        assert_eq!(after, before);
+    }
+}
+
+#[test]
+fn test_frozen() {
+    struct Diffuse;
+
+    impl Simulate<f64> for Diffuse {
+        fn simulate(state: &f64, neighbor_states: &[&f64]) -> f64 {
+            let sum = neighbor_states.iter().map(|s| **s).sum::<f64>();
+            (state + sum) / (neighbor_states.len() + 1) as f64
+        }
+    }
+
+    let (mut qdf, spaces) = QDF::with_levels(2, 0.0, 2);
+    let source = spaces[0];
+    qdf.set_space_state(source, 100.0).unwrap();
+    qdf.set_frozen(source, true).unwrap();
+    assert!(qdf.set_frozen(ID::new(), true).is_err());
+    let neighbors = qdf.find_space_neighbors(source).unwrap();
+    for _ in 0..10 {
+        qdf.simulation_step::<Diffuse>();
+    }
+    assert_eq!(*qdf.space(source).state(), 100.0);
+    let relaxed = neighbors
+        .iter()
+        .map(|id| *qdf.space(*id).state())
+        .collect::<Vec<f64>>();
+    assert!(relaxed.iter().all(|state| *state > 0.0 && *state < 100.0));
+    let states = qdf.simulate_states_parallel::<Diffuse>();
+    assert!(states.iter().any(|(id, state)| *id == source && *state == 100.0));
+    assert!(qdf.validate().is_ok());
+    qdf.set_frozen(source, false).unwrap();
+    qdf.simulation_step::<Diffuse>();
+    assert!(*qdf.space(source).state() < 100.0);
+}
+
+#[test]
+fn test_increase_space_density_keeping_id() {
+    for dimensions in 1..4 {
+        let (mut qdf, root) = QDF::new(dimensions, 4096);
+        let leaves = qdf.subdivide_to_depth(root, 2).unwrap();
+        let selected = leaves[0];
+        let field = qdf.create_field(vec![selected]).unwrap();
+        qdf.set_frozen(selected, true).unwrap();
+        let ancestors = qdf.ancestors(selected);
+        let degrees = qdf
+            .iter_spaces()
+            .map(|(id, _)| qdf.space_degree(id).unwrap())
+            .collect::<Vec<usize>>();
+        let (id, subs, pairs) = qdf.increase_space_density_keeping_id(selected).unwrap();
+        assert_eq!(id, selected);
+        assert_eq!(subs.len(), dimensions + 1);
+        assert_eq!(subs[0], selected);
+        assert!(qdf.space_exists(selected));
+        assert_eq!(qdf.space_count(), leaves.len() + dimensions);
+        assert_eq!(qdf.total_state(), 4096);
+        for (n, t) in pairs {
+            assert!(subs.contains(&t));
+            assert!(qdf.find_space_neighbors(n).unwrap().contains(&t));
+        }
+        for sub in &subs {
+            assert_eq!(qdf.ancestors(*sub), ancestors);
+            assert!(qdf.get_field(field).unwrap().contains(*sub));
+            assert!(qdf.is_frozen(*sub));
+        }
+        let mut before = degrees;
+        before.extend(vec![dimensions + 1; dimensions]);
+        before.sort();
+        let mut after = qdf
+            .iter_spaces()
+            .map(|(id, _)| qdf.space_degree(id).unwrap())
+            .collect::<Vec<usize>>();
+        after.sort();
+        assert_eq!(after, before);
+        assert!(qdf.validate().is_ok());
+    }
+    let (mut qdf, _) = QDF::new(2, 9);
+    assert!(qdf.increase_space_density_keeping_id(ID::new()).is_err());
+}
+
+#[test]
+fn test_neighbor_states() {
+    struct Sum;
+
+    impl Simulate<i32> for Sum {
+        fn simulate(_: &i32, neighbor_states: &[&i32]) -> i32 {
+            neighbor_states.iter().map(|s| **s).sum()
+        }
+    }
+
+    let (qdf, _) = indexed_levels(3, 2, |i| i as i32);
+    let expected = qdf.simulate_states::<Sum>();
+    for (id, sum) in expected {
+        let states = qdf.neighbor_states(id).unwrap();
+        assert_eq!(states.len(), qdf.space_degree(id).unwrap());
+        assert_eq!(states.iter().map(|s| **s).sum::<i32>(), sum);
+    }
+    assert!(qdf.neighbor_states(ID::new()).is_err());
+}
+
+#[test]
+fn test_neighbors_opt() {
+    let (mut qdf, spaces) = QDF::with_levels(2, 81, 2);
+    let merged = qdf.decrease_space_density(spaces[0]).unwrap().unwrap();
+    let mut cached = spaces.clone();
+    cached.push(merged.id);
+    let mut alive = 0;
+    for id in &cached {
+        match qdf.neighbors_opt(*id) {
+            Some(neighbors) => {
+                alive += 1;
+                assert_eq!(neighbors, qdf.find_space_neighbors(*id).unwrap());
+            }
+            None => assert!(qdf.find_space_neighbors(*id).is_err()),
+        }
+    }
+    assert_eq!(alive, qdf.spaces().count());
+}
+
+#[test]
+fn test_edges() {
+    let (mut qdf, spaces) = QDF::with_levels(3, 0, 2);
+    qdf.remove_space(spaces[0]).unwrap();
+    let edges = qdf.edges();
+    assert_eq!(edges.len(), qdf.graph.edge_count());
+    let unique = edges.iter().cloned().collect::<HashSet<(ID, ID)>>();
+    assert_eq!(unique.len(), edges.len());
+    for (a, b) in edges {
+        assert!(a < b);
+        assert!(qdf.find_space_neighbors(a).unwrap().contains(&b));
+    }
+    let degrees = qdf
+        .iter_spaces()
+        .map(|(id, _)| qdf.space_degree(id).unwrap())
+        .sum::<usize>();
+    assert_eq!(degrees, unique.len() * 2);
+}
+
+#[test]
+fn test_try_relax_to() {
+    for dimensions in 1..4 {
+        let (mut qdf, _) = QDF::new(dimensions, 1 << 20);
+        for target in &[1, 30, 30, 7, 100, 2] {
+            let target = *target;
+            let before = qdf.space_count();
+            let operations = qdf.try_relax_to(target);
+            let count = qdf.space_count();
+            assert_eq!(
+                operations,
+                (count as isize - before as isize).unsigned_abs() / dimensions
+            );
+            assert!(count >= target);
+            // 1D spaces form a chain, which cannot be merged.
+            assert!(count < target + dimensions || dimensions == 1);
+            assert_eq!(qdf.try_relax_to(target), 0);
+            assert_eq!(qdf.total_state(), 1 << 20);
+            assert!(qdf.validate().is_ok());
+        }
+    }
+    let (mut qdf, _) = QDF::new(2, 1 << 20);
+    assert_eq!(qdf.try_relax_to(20000), 10000);
+    assert_eq!(qdf.try_relax_to(3), 9999);
+    assert_eq!(qdf.space_count(), 3);
+    assert!(qdf.validate().is_ok());
+    let (mut qdf, _) = QDF::new(0, 9);
+    assert_eq!(qdf.try_relax_to(10), 0);
+}
+
+#[test]
+fn test_visit_ordered() {
+    let (qdf, spaces) = indexed_levels(3, 2, |i| i as i32);
+    let mut visited = vec![];
+    qdf.visit_ordered(|id, space| {
+        assert_eq!(space.id(), id);
+        visited.push((id, *space.state()));
+    });
+    assert_eq!(visited.len(), spaces.len());
+    assert!(visited.windows(2).all(|w| w[0].0 < w[1].0));
+    for (id, state) in &visited {
+        assert_eq!(qdf.space(*id).state(), state);
+    }
+    let copy = QDF::<i32>::from_bytes(&qdf.to_bytes()).unwrap();
+    let mut visited2 = vec![];
+    copy.visit_ordered(|id, space| visited2.push((id, *space.state())));
+    assert_eq!(visited2, visited);
+}
+
+#[test]
+fn test_refine_budget() {
+    let (mut qdf, spaces) = indexed_levels(2, 1, |i| i as f64 * 9.0);
+    // budget smaller than single subdivision does nothing.
+    assert_eq!(qdf.refine_budget(2, |state| *state), 0);
+    assert_eq!(qdf.refine_budget(3, |state| *state), 1);
+    assert!(!qdf.space_exists(spaces[2]));
+    assert!(qdf.space_exists(spaces[1]));
+    // subspaces of the densest space (6 each) lose with space of state 9.
+    assert_eq!(qdf.refine_budget(5, |state| *state), 1);
+    assert!(!qdf.space_exists(spaces[1]));
+    assert_eq!(qdf.space_count(), 7);
+    assert_eq!(qdf.total_state(), 27.0);
+
+    let count = qdf.space_count();
+    assert_eq!(qdf.refine_budget(1000, |_| f64::NAN), 0);
+    assert_eq!(qdf.refine_budget(1000, |_| -1.0), 0);
+    assert_eq!(qdf.space_count(), count);
+    let subdivisions = qdf.refine_budget(1000, |state| *state - 1.0);
+    assert!(subdivisions > 0 && subdivisions <= 1000 / 3);
+    assert_eq!(qdf.space_count(), count + subdivisions * 2);
+    assert!(qdf.spaces().all(|id| *qdf.space(*id).state() <= 1.0));
+}
+
+#[test]
+fn test_states_in_order() {
+    let (mut qdf, spaces) = indexed_levels(2, 2, |i| i as i32);
+    let (ids, states) = qdf.states_in_order();
+    assert_eq!(ids.len(), spaces.len());
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    for (id, state) in ids.iter().zip(states.iter()) {
+        assert_eq!(qdf.space(*id).state(), state);
+    }
+    assert_eq!(qdf.states_in_order(), qdf.states_in_order());
+    let reversed = states.iter().rev().cloned().collect::<Vec<i32>>();
+    qdf.apply_states(ids.iter().cloned().zip(reversed.clone())).unwrap();
+    assert_eq!(qdf.states_in_order(), (ids, reversed));
+}
+
+#[test]
+fn test_path_states() {
+    let (qdf, spaces) = indexed_levels(2, 3, |i| i as i32);
+    let path = qdf
+        .find_path(spaces[0], spaces[spaces.len() - 1])
+        .unwrap()
+        .unwrap();
+    let states = qdf.path_states(&path).unwrap();
+    assert_eq!(states.len(), path.len());
+    for (id, state) in path.iter().zip(states) {
+        assert_eq!(qdf.space(*id).state(), state);
+    }
+    assert!(qdf.path_states(&[]).unwrap().is_empty());
+    let missing = ID::new();
+    match qdf.path_states(&[spaces[0], missing, ID::new()]) {
+        Err(QDFError::SpaceDoesNotExists(id)) => assert_eq!(id, missing),
+        _ => panic!("missing space must be reported"),
+    }
+}
+
+#[test]
+fn test_subdivision_topology() {
+    let topologies = [
+        SubdivisionTopology::Complete,
+        SubdivisionTopology::Ring,
+        SubdivisionTopology::Star,
+    ];
+    for dimensions in 1..5 {
+        for topology in &topologies {
+            let (mut qdf, root) = QDF::new(dimensions, 1 << 20);
+            qdf.set_subdivision_topology(*topology);
+            let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+            let inner = topology.connections(subs.len());
+            assert_eq!(qdf.edges().len(), inner.len());
+            for (i, j) in inner {
+                assert!(qdf.find_space_neighbors(subs[i]).unwrap().contains(&subs[j]));
+            }
+            let leaves = qdf.subdivide_to_depth(subs[0], 2).unwrap();
+            let (_, _, pairs) = qdf.increase_space_density_keeping_id(leaves[0]).unwrap();
+            for (n, t) in pairs {
+                assert!(qdf.find_space_neighbors(n).unwrap().contains(&t));
+            }
+            assert_eq!(qdf.connected_components().len(), 1);
+            assert_eq!(qdf.total_state(), 1 << 20);
+            assert!(qdf.validate().is_ok());
+        }
+    }
+    let (mut qdf, root) = QDF::new(3, 16);
+    assert_eq!(qdf.subdivision_topology(), SubdivisionTopology::Complete);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    assert!(qdf.decrease_space_density(subs[0]).unwrap().is_some());
+}
+
+#[test]
+fn test_history_keeping_id_and_depth() {
+    #[allow(clippy::type_complexity)]
+    fn summary(qdf: &QDF<i32>) -> (Vec<(ID, i32, Vec<ID>, bool)>, Vec<(ID, ID)>) {
+        let mut spaces = qdf
+            .iter_spaces()
+            .map(|(id, space)| (id, *space.state(), qdf.ancestors(id), qdf.is_frozen(id)))
+            .collect::<Vec<_>>();
+        spaces.sort();
+        let mut edges = qdf.edges();
+        edges.sort();
+        (spaces, edges)
+    }
+
+    let (qdf, root) = QDF::new(2, 81);
+    let mut qdf = qdf.with_history();
+    let field = qdf.create_field(vec![root]).unwrap();
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    qdf.set_frozen(subs[0], true).unwrap();
+    let before = summary(&qdf);
+    let (_, kept, _) = qdf.increase_space_density_keeping_id(subs[0]).unwrap();
+    assert_eq!(kept[0], subs[0]);
+    let after = summary(&qdf);
+    assert!(qdf.undo().unwrap());
+    assert!(qdf.validate().is_ok());
+    assert_eq!(qdf.total_state(), 81);
+    assert_eq!(qdf.space_count(), 3);
+    assert!(kept[1..].iter().all(|id| !qdf.space_exists(*id)));
+    assert_eq!(*qdf.space(subs[0]).state(), 27);
+    assert_eq!(qdf.get_field(field).unwrap().spaces().len(), 3);
+    assert_eq!(summary(&qdf), before);
+    assert!(qdf.redo().unwrap());
+    assert_eq!(summary(&qdf), after);
+    assert!(qdf.undo().unwrap());
+    assert!(qdf.undo().unwrap());
+    assert_eq!(qdf.spaces().cloned().collect::<Vec<_>>(), vec![root]);
+    assert_eq!(*qdf.space(root).state(), 81);
+    assert!(qdf.validate().is_ok());
+
+    let before = summary(&qdf);
+    let leaves = qdf.subdivide_to_depth(root, 2).unwrap();
+    let after = summary(&qdf);
+    assert_eq!(qdf.subdivide_to_depth(leaves[0], 0).unwrap(), vec![leaves[0]]);
+    assert!(qdf.undo().unwrap());
+    assert!(!qdf.can_undo());
+    assert_eq!(summary(&qdf), before);
+    assert_eq!(qdf.get_field(field).unwrap().spaces().len(), 1);
+    assert!(qdf.redo().unwrap());
+    assert_eq!(summary(&qdf), after);
+    assert!(qdf.validate().is_ok());
+}
+
+#[test]
+fn test_history() {
+    #[allow(clippy::type_complexity)]
+    fn summary(
+        qdf: &QDF<i32, f32>,
+    ) -> (
+        Vec<(ID, i32, Vec<ID>, bool)>,
+        Vec<(ID, ID, String)>,
+        Vec<Vec<ID>>,
+    ) {
+        let mut spaces = qdf
+            .iter_spaces()
+            .map(|(id, space)| (id, *space.state(), qdf.ancestors(id), qdf.is_frozen(id)))
+            .collect::<Vec<_>>();
+        spaces.sort();
+        let mut edges = qdf
+            .edges()
+            .into_iter()
+            .map(|(a, b)| (a, b, format!("{:?}", qdf.edge_data(a, b))))
+            .collect::<Vec<_>>();
+        edges.sort();
+        let mut fields = qdf.fields().cloned().collect::<Vec<ID>>();
+        fields.sort();
+        let fields = fields
+            .into_iter()
+            .map(|field| {
+                let field = qdf.get_field(field).unwrap();
+                let mut spaces = field.spaces().iter().cloned().collect::<Vec<_>>();
+                spaces.sort();
+                spaces
+            }).collect();
+        (spaces, edges, fields)
+    }
+
+    let (qdf, root) = QDF::<i32, f32>::new_with_edge_data(2, 81);
+    let mut qdf = qdf.with_history();
+    assert!(qdf.has_history());
+    qdf.create_field(vec![root]).unwrap();
+    let mut before = vec![];
+    before.push(summary(&qdf));
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    qdf.set_edge_data(subs[0], subs[1], 2.5).unwrap();
+    qdf.set_frozen(subs[2], true).unwrap();
+    before.push(summary(&qdf));
+    let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    qdf.set_space_state(subs2[1], 4).unwrap();
+    before.push(summary(&qdf));
+    let (_, subs3, _) = qdf.increase_space_density(subs[2]).unwrap();
+    before.push(summary(&qdf));
+    let merged = qdf.decrease_space_density(subs2[0]).unwrap().unwrap();
+    qdf.set_space_state(merged.id, 10).unwrap();
+    before.push(summary(&qdf));
+    assert!(qdf.decrease_space_density(subs3[1]).unwrap().is_some());
+    let after = summary(&qdf);
+    assert!(qdf.validate().is_ok());
+
+    let mut undone = vec![after.clone()];
+    while qdf.can_undo() {
+        assert!(qdf.undo().unwrap());
+        assert!(qdf.validate().is_ok());
+        undone.push(summary(&qdf));
+    }
+    assert!(!qdf.undo().unwrap());
+    let mut expected = before.clone();
+    expected.reverse();
+    assert_eq!(&undone[1..], &expected[..]);
+    let mut redone = vec![];
+    while qdf.can_redo() {
+        assert!(qdf.redo().unwrap());
+        assert!(qdf.validate().is_ok());
+        redone.push(summary(&qdf));
+    }
+    let mut expected = before[1..].to_vec();
+    expected.push(after);
+    assert_eq!(redone, expected);
+
+    assert!(qdf.undo().unwrap());
+    assert!(qdf.can_redo());
+    let id = qdf.spaces().cloned().next().unwrap();
+    qdf.increase_space_density(id).unwrap();
+    assert!(!qdf.can_redo());
+    let id = qdf.spaces().cloned().next().unwrap();
+    let (_, subs4, _) = qdf.increase_space_density(id).unwrap();
+    qdf.remove_space(subs4[0]).unwrap();
+    let broken = summary(&qdf);
+    match qdf.undo() {
+        Err(QDFError::SpaceDoesNotExists(id)) => assert_eq!(id, subs4[0]),
+        _ => panic!("undo of changed spaces must fail"),
+    }
+    assert_eq!(summary(&qdf), broken);
+    qdf.clear_history();
+    assert!(!qdf.can_undo());
+
+    let (mut qdf, root) = QDF::new(2, 9);
+    qdf.increase_space_density(root).unwrap();
+    assert!(!qdf.has_history());
+    assert!(!qdf.undo().unwrap());
+}
+
+#[test]
+fn test_map_states() {
+    let (mut qdf, spaces) = indexed_levels(3, 3, |i| i as i32);
+    let edges = qdf.edges();
+    let total = qdf.total_state();
+    qdf.map_states(|state| state * 2);
+    assert_eq!(qdf.total_state(), total * 2);
+    qdf.map_states_parallel(|state| state * 2);
+    assert_eq!(qdf.total_state(), total * 4);
+    for (i, id) in spaces.iter().enumerate() {
+        assert_eq!(*qdf.space(*id).state(), i as i32 * 4);
+    }
+    assert_eq!(qdf.edges(), edges);
+}
+
+#[test]
+fn test_reduce() {
+    let (qdf, spaces) = indexed_levels(3, 3, |i| i as i32);
+    let count = spaces.len() as i32;
+    assert_eq!(qdf.reduce(0, |sum, state| sum + state), qdf.total_state());
+    assert_eq!(
+        qdf.reduce_parallel(0, |sum, state| sum + state, |a, b| a + b),
+        qdf.total_state()
+    );
+    assert_eq!(qdf.reduce(0, |max, state| max.max(*state)), count - 1);
+    assert_eq!(
+        qdf.reduce_parallel(0, |max, state| max.max(*state), i32::max),
+        count - 1
+    );
+    let above = |n: usize, state: &i32| if *state >= 10 { n + 1 } else { n };
+    assert_eq!(qdf.reduce(0, above), spaces.len() - 10);
+    assert_eq!(
+        qdf.reduce_parallel(0, above, |a, b| a + b),
+        spaces.len() - 10
+    );
+}
+
+#[test]
+fn test_space_state_mut() {
+    let (mut qdf, root) = QDF::new(2, 9);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    *qdf.space_state_mut(subs[0]).unwrap() -= 1;
+    *qdf.space_state_mut(subs[1]).unwrap() += 1;
+    assert_eq!(*qdf.space(subs[0]).state(), 2);
+    assert_eq!(*qdf.space(subs[1]).state(), 4);
+    assert_eq!(qdf.total_state(), 9);
+    match qdf.space_state_mut(root) {
+        Err(QDFError::SpaceDoesNotExists(id)) => assert_eq!(id, root),
+        _ => panic!("removed space state must not be borrowed"),
+    }
+}
+
+#[test]
+fn test_subdivision_count() {
+    for dimensions in 0..4 {
+        let (mut qdf, root) = QDF::new(dimensions, 0);
+        assert_eq!(qdf.subdivision_count(), dimensions + 1);
+        let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+        assert_eq!(subs.len(), qdf.subdivision_count());
+        let leaves = qdf.subdivide_to_depth(subs[0], 2).unwrap();
+        assert_eq!(leaves.len(), qdf.subdivision_count().pow(2));
+    }
+}
+
+#[test]
+fn test_total_state() {
+    let (mut qdf, root) = QDF::new(3, 1001);
+    assert_eq!(qdf.total_state(), 1001);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    assert_eq!(qdf.total_state(), 1001);
+    let leaves = qdf.subdivide_to_depth(subs[0], 2).unwrap();
+    qdf.increase_space_density(subs[3]).unwrap();
+    assert_eq!(qdf.total_state(), 1001);
+    assert_eq!(qdf.total_state_parallel(), 1001);
+    qdf.decrease_space_density(leaves[0]).unwrap().unwrap();
+    assert_eq!(qdf.total_state(), 1001);
+    assert_eq!(qdf.total_state_parallel(), 1001);
+}
+
+struct Life;
+
+impl Simulate<bool> for Life {
+    fn simulate(state: &bool, neighbor_states: &[&bool]) -> bool {
+        let alive = neighbor_states.iter().filter(|s| ***s).count();
+        alive == 2 || (*state && alive == 1)
+    }
+}
+
+#[test]
+fn test_bool_state() {
+    let (mut qdf, root) = QDF::new(2, false);
+    let subs = qdf.subdivide_to_depth(root, 3).unwrap();
+    assert!(subs.iter().all(|id| !*qdf.space(*id).state()));
+    qdf.set_space_state(subs[0], true).unwrap();
+    assert!(qdf.total_state());
+    let info = qdf.decrease_space_density(subs[0]).unwrap().unwrap();
+    assert!(*qdf.space(info.id).state());
+    let (_, subs2, _) = qdf.increase_space_density(info.id).unwrap();
+    assert!(subs2.iter().all(|id| *qdf.space(*id).state()));
+
+    let expected = qdf
+        .spaces()
+        .map(|id| {
+            let alive = qdf
+                .find_space_neighbors(*id)
+                .unwrap()
+                .iter()
+                .filter(|n| *qdf.space(**n).state())
+                .count();
+            (*id, alive == 2 || (*qdf.space(*id).state() && alive == 1))
+        }).collect::<Vec<_>>();
+    qdf.simulation_step::<Life>();
+    for (id, state) in expected {
+        assert_eq!(*qdf.space(id).state(), state);
+    }
+
+    let qdf2 = QDF::<bool>::from_bytes(&qdf.to_bytes()).unwrap();
+    assert!(qdf2 == qdf);
+    assert!(bool::decode(&[2]).is_err());
+    assert!(true.super_state_at_level(3, 10));
+}
+
+#[test]
+fn test_saturating_state() {
+    let near = SaturatingState(i32::MAX - 1);
+    assert_eq!(
+        State::merge(&[near, SaturatingState(1), SaturatingState(5)]),
+        SaturatingState(i32::MAX)
+    );
+    assert_eq!(
+        State::merge(&[SaturatingState(i32::MIN + 1), SaturatingState(-5)]),
+        SaturatingState(i32::MIN)
+    );
+    let (mut qdf, root) = QDF::new(2, SaturatingState(0i32));
+    let subs = qdf.subdivide_to_depth(root, 2).unwrap();
+    for id in &subs {
+        qdf.set_space_state(*id, near).unwrap();
+    }
+    assert_eq!(qdf.total_state(), SaturatingState(i32::MAX));
+    assert_eq!(qdf.total_state_parallel(), SaturatingState(i32::MAX));
+    assert_eq!(
+        SaturatingState(u8::MAX).super_state_at_level(2, 3),
+        SaturatingState(u8::MAX)
+    );
+    let qdf2 = QDF::<SaturatingState<i32>>::from_bytes(&qdf.to_bytes()).unwrap();
+    assert!(qdf2 == qdf);
+}
+
+#[test]
+fn test_array_state() {
+    let state = [9.0f32, 3.0, 6.0];
+    let substates = state.subdivide(3);
+    assert_eq!(substates, vec![[3.0, 1.0, 2.0]; 3]);
+    assert_eq!(State::merge(&substates), state);
+    assert_eq!(
+        State::merge_weighted(&[[1.0f32, 2.0, 3.0], [2.0, 2.0, 2.0]], &[0.5]),
+        [2.5, 3.0, 3.5]
+    );
+    assert_eq!([10u8, 7].subdivide(4), vec![[3, 2], [3, 2], [2, 2], [2, 1]]);
+
+    let mut buf = vec![];
+    state.encode(&mut buf);
+    assert_eq!(<[f32; 3]>::decode(&buf).unwrap(), state);
+    assert!(<[f32; 3]>::decode(&buf[..buf.len() - 1]).is_err());
+    assert!(<[f32; 2]>::decode(&buf).is_err());
+
+    let (mut qdf, root) = QDF::new(2, [81.0f32, 27.0, 54.0]);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    let leaves = qdf.subdivide_to_depth(subs[0], 2).unwrap();
+    assert_eq!(*qdf.space(leaves[0]).state(), [3.0, 1.0, 2.0]);
+    assert_eq!(qdf.total_state(), [81.0, 27.0, 54.0]);
+    let qdf2 = QDF::<[f32; 3]>::from_bytes(&qdf.to_bytes()).unwrap();
+    assert_eq!(qdf2.space(leaves[0]).state(), qdf.space(leaves[0]).state());
+}
+
+#[test]
+fn test_tuple_state() {
+    for subdivisions in 1..=5 {
+        let pair = (10u32, 7.5f64);
+        let substates = pair.subdivide(subdivisions);
+        assert_eq!(substates.len(), subdivisions);
+        assert_eq!(State::merge(&substates), pair);
+        assert_eq!(
+            substates.iter().map(|s| s.0).collect::<Vec<u32>>(),
+            10u32.subdivide(subdivisions)
+        );
+
+        let triple = (-7i8, 100usize, 3.0f32);
+        assert_eq!(State::merge(&triple.subdivide(subdivisions)), triple);
+
+        let quad = (1u8, -11i64, 64.0f64, [4u16, 9]);
+        assert_eq!(State::merge(&quad.subdivide(subdivisions)), quad);
+    }
+    assert_eq!((9i32, 9.0f64).subdivide(2), vec![(5, 4.5), (4, 4.5)]);
+    assert_eq!(
+        State::merge_weighted(&[(1, 1.0f64), (2, 2.0)], &[0.5, 0.25]),
+        (3, 1.0)
+    );
+
+    let quad = (3u8, -2i32, 0.5f64, 1.5f32);
+    let mut buf = vec![];
+    quad.encode(&mut buf);
+    assert_eq!(<(u8, i32, f64, f32)>::decode(&buf).unwrap(), quad);
+    assert!(<(u8, i32, f64, f32)>::decode(&buf[..buf.len() - 1]).is_err());
+    assert!(<(u8, i32, f64)>::decode(&buf).is_err());
+
+    let (mut qdf, root) = QDF::new(2, (10u32, 90.0f64));
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    assert_eq!(*qdf.space(subs[0]).state(), (4, 30.0));
+    assert_eq!(*qdf.space(subs[2]).state(), (3, 30.0));
+    qdf.decrease_space_density(subs[0]).unwrap().unwrap();
+    assert_eq!(qdf.total_state(), (10, 90.0));
+}
+
+#[test]
+fn test_impl_state() {
+    #[derive(Debug, Default, Clone, PartialEq)]
+    struct Count(u32);
+
+    fn split(state: &Count, subdivisions: usize) -> Vec<Count> {
+        u32::subdivide(&state.0, subdivisions)
+            .into_iter()
+            .map(Count)
+            .collect()
+    }
+
+    ::impl_state!(Count,
+        subdivide: split,
+        merge: |states: &[Count]| Count(states.iter().map(|s| s.0).sum()),
+    );
+
+    assert_eq!(Count(10).subdivide(3), vec![Count(4), Count(3), Count(3)]);
+    let (mut qdf, root) = QDF::new(3, Count(10));
+    qdf.subdivide_to_depth(root, 2).unwrap();
+    assert_eq!(qdf.total_state(), Count(10));
+}
+
+#[test]
+fn test_merge_weighted() {
+    assert_eq!(State::merge_weighted(&[2.0f32, 4.0, 8.0], &[0.5, 0.25, 0.125]), 3.0);
+    assert_eq!(State::merge_weighted(&[2.0f64, 4.0, 8.0], &[0.5, 0.25, 0.125]), 3.0);
+    assert_eq!(State::merge_weighted(&[2.0f64, 4.0], &[0.5]), 5.0);
+    assert_eq!(State::merge_weighted(&[2.0f64, 4.0], &[]), 6.0);
+    assert_eq!(State::merge_weighted(&[2i32, 4, 8], &[0.5, 0.25, 0.125]), 14);
+    assert_eq!(f64::merge_weighted(&[], &[1.0]), 0.0);
+}
+
+#[test]
+fn test_edge_data() {
+    let (mut qdf, root) = QDF::<i32, u32>::new_with_edge_data(2, 9);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    assert_eq!(qdf.edge_data(subs[0], subs[1]), Some(&0));
+    qdf.set_edge_data(subs[0], subs[1], 5).unwrap();
+    qdf.set_edge_data(subs[2], subs[0], 7).unwrap();
+    assert_eq!(qdf.edge_data(subs[1], subs[0]), Some(&5));
+    assert_eq!(qdf.edge_data(subs[0], subs[2]), Some(&7));
+    match qdf.set_edge_data(subs[0], root, 1) {
+        Err(QDFError::SpaceDoesNotExists(id)) => assert_eq!(id, root),
+        _ => panic!("edge to not existing space must not be set"),
+    }
+
+    let (_, subs2, pairs) = qdf.increase_space_density(subs[0]).unwrap();
+    assert_eq!(pairs, vec![(subs[1], subs2[0]), (subs[2], subs2[1])]);
+    assert_eq!(qdf.edge_data(subs[1], subs2[0]), Some(&5));
+    assert_eq!(qdf.edge_data(subs[2], subs2[1]), Some(&7));
+    assert_eq!(qdf.edge_data(subs2[0], subs2[1]), Some(&0));
+    match qdf.set_edge_data(subs[1], subs2[2], 1) {
+        Err(QDFError::EdgeDoesNotExists(a, b)) => assert_eq!((a, b), (subs[1], subs2[2])),
+        _ => panic!("not connected spaces must not get edge data"),
+    }
+
+    let merged = qdf.decrease_space_density(subs2[0]).unwrap().unwrap().id;
+    assert_eq!(qdf.edge_data(merged, subs[1]), Some(&5));
+    assert_eq!(qdf.edge_data(merged, subs[2]), Some(&7));
+    assert_eq!(qdf.edge_data(subs[1], subs[2]), Some(&0));
+}
+
+#[test]
+fn test_weighted_path() {
+    let (mut qdf, root) = QDF::<i32, u32>::new_with_edge_data(2, 9);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    assert_eq!(
+        qdf.find_path(subs2[2], subs[2]).unwrap(),
+        Some(vec![subs2[2], subs2[1], subs[2]])
+    );
+    assert_eq!(
+        qdf.find_weighted_path(subs2[2], subs[2], |w| *w + 1).unwrap(),
+        Some(vec![subs2[2], subs2[1], subs[2]])
+    );
+    qdf.set_edge_data(subs2[1], subs[2], 10).unwrap();
+    assert_eq!(
+        qdf.find_path(subs2[2], subs[2]).unwrap(),
+        Some(vec![subs2[2], subs2[1], subs[2]])
+    );
+    assert_eq!(
+        qdf.find_weighted_path(subs2[2], subs[2], |w| *w + 1).unwrap(),
+        Some(vec![subs2[2], subs2[0], subs[1], subs[2]])
+    );
+    assert!(qdf.find_weighted_path(root, subs[2], |w| *w).is_err());
+
+    let (a, b, c) = (ID::new(), ID::new(), ID::new());
+    let qdf = QDF::<i32, u32>::from_parts(2, vec![(a, 1), (b, 2), (c, 3)], vec![(a, b)]).unwrap();
+    assert_eq!(qdf.find_weighted_path(a, c, |w| *w + 1).unwrap(), None);
+    assert_eq!(qdf.find_weighted_path(a, a, |w| *w + 1).unwrap(), Some(vec![a]));
+}
+
+#[test]
+fn test_simulation_step_with() {
+    struct Diffusion {
+        rate: f64,
+    }
+
+    impl SimulateWith<f64> for Diffusion {
+        fn simulate(&self, state: &f64, neighbor_states: &[&f64]) -> f64 {
+            if neighbor_states.is_empty() {
+                return *state;
+            }
+            let average =
+                neighbor_states.iter().map(|s| **s).sum::<f64>() / neighbor_states.len() as f64;
+            state + (average - state) * self.rate
+        }
+    }
+
+    let build = || {
+        let (mut qdf, root) = QDF::new(2, 9.0);
+        let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+        qdf.set_space_state(subs[0], 6.0).unwrap();
+        qdf.set_space_state(subs[1], 0.0).unwrap();
+        qdf.set_space_state(subs[2], 0.0).unwrap();
+        (qdf, subs)
+    };
+    let (mut slow, subs) = build();
+    slow.simulation_step_with(&Diffusion { rate: 0.25 });
+    assert_eq!(*slow.space(subs[0]).state(), 4.5);
+    assert_eq!(*slow.space(subs[1]).state(), 0.75);
+    let (mut fast, subs) = build();
+    fast.simulation_step_with(&Diffusion { rate: 0.5 });
+    assert_eq!(*fast.space(subs[0]).state(), 3.0);
+    assert_eq!(*fast.space(subs[1]).state(), 1.5);
+}
+
+#[test]
+fn test_simulation_step_parallel_in() {
+    struct Average;
+
+    impl Simulate<f64> for Average {
+        fn simulate(state: &f64, neighbor_states: &[&f64]) -> f64 {
+            let sum = neighbor_states.iter().map(|s| **s).sum::<f64>();
+            (state + sum) / (neighbor_states.len() + 1) as f64
+        }
+    }
+
+    let (mut qdf, root) = QDF::new(2, 81.0);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    for id in subs {
+        qdf.increase_space_density(id).unwrap();
+    }
+    let id = *qdf.spaces().next().unwrap();
+    qdf.set_space_state(id, 100.0).unwrap();
+    let pool = ::rayon::ThreadPoolBuilder::new()
+        .num_threads(2)
+        .build()
+        .unwrap();
+    let mut expected = qdf.simulate_states_parallel::<Average>();
+    let mut result = qdf.simulate_states_parallel_in::<Average>(&pool);
+    expected.sort_by_key(|(id, _)| *id);
+    result.sort_by_key(|(id, _)| *id);
+    assert_eq!(result, expected);
+    qdf.simulation_step_parallel_in::<Average>(&pool);
+    for (id, state) in expected {
+        assert_eq!(*qdf.space(id).state(), state);
+    }
+}
+
+#[test]
+fn test_simulation_follows_topology_changes() {
+    struct Sum;
+
+    impl Simulate<i32> for Sum {
+        fn simulate(state: &i32, neighbor_states: &[&i32]) -> i32 {
+            state + neighbor_states.iter().map(|s| **s).sum::<i32>()
+        }
+    }
+
+    let expected = |qdf: &QDF<i32>| {
+        let mut result = qdf
+            .spaces()
+            .map(|id| {
+                let neighbors = qdf.find_space_neighbors(*id).unwrap();
+                let sum = neighbors.iter().map(|n| *qdf.space(*n).state()).sum::<i32>();
+                (*id, qdf.space(*id).state() + sum)
+            }).collect::<Vec<(ID, i32)>>();
+        result.sort_by_key(|(id, _)| *id);
+        result
+    };
+    let simulated = |mut states: Vec<(ID, i32)>| {
+        states.sort_by_key(|(id, _)| *id);
+        states
+    };
+
+    let (mut qdf, root) = QDF::new(2, 729);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    let (_, subs3, _) = qdf.increase_space_density(subs2[1]).unwrap();
+    for step in 0..4 {
+        match step {
+            1 => {
+                qdf.decrease_space_density(subs3[0]).unwrap().unwrap();
+            }
+            2 => {
+                qdf.remove_space(subs[2]).unwrap();
+            }
+            3 => {
+                qdf.increase_space_density(subs[1]).unwrap();
+            }
+            _ => {}
+        }
+        assert!(qdf.validate().is_ok());
+        let states = expected(&qdf);
+        assert_eq!(simulated(qdf.simulate_states::<Sum>()), states);
+        assert_eq!(simulated(qdf.simulate_states_parallel::<Sum>()), states);
+        qdf.simulation_step::<Sum>();
+        for (id, state) in states {
+            assert_eq!(*qdf.space(id).state(), state);
+        }
+    }
+}
+
+#[test]
+fn test_absorb() {
+    let (mut qdf, root) = QDF::new(2, 9);
+    let (other, other_root) = QDF::new(3, 9);
+    match qdf.absorb(other, (root, other_root)) {
+        Err(QDFError::DimensionMismatch(2, 3)) => {}
+        _ => panic!("universes with different dimensions must not be joined"),
+    }
+    let (other, other_root) = QDF::new(2, 9);
+    match qdf.absorb(other, (other_root, root)) {
+        Err(QDFError::SpaceDoesNotExists(id)) => assert_eq!(id, other_root),
+        _ => panic!("bridge must start in absorbing universe"),
+    }
+    let (other, _) = QDF::new_with_generator(2, 9, &mut IdGenerator::new(1));
+    let (mut qdf, root) = QDF::new_with_generator(2, 9, &mut IdGenerator::new(1));
+    match qdf.absorb(other, (root, root)) {
+        Err(QDFError::SpaceAlreadyExists(id)) => assert_eq!(id, root),
+        _ => panic!("colliding spaces must not be absorbed"),
+    }
+    assert_eq!(qdf.spaces().count(), 1);
+    assert_eq!(qdf.find_space_neighbors(root).unwrap(), vec![]);
+
+    let (mut other, other_root) = QDF::new(2, 9);
+    let field = other.create_field(vec![other_root]).unwrap();
+    qdf.fields
+        .insert(field, Field::new(field, vec![root].into_iter().collect()));
+    match qdf.absorb(other, (root, other_root)) {
+        Err(QDFError::FieldAlreadyExists(id)) => assert_eq!(id, field),
+        _ => panic!("colliding fields must not be absorbed"),
+    }
+    assert_eq!(qdf.spaces().count(), 1);
+    assert_eq!(qdf.get_field(field).unwrap().len(), 1);
+    assert!(qdf.get_field(field).unwrap().contains(root));
+
+    let (mut other, other_root) = QDF::new(2, 9);
+    let (_, subs, _) = other.increase_space_density(other_root).unwrap();
+    qdf.absorb(other, (root, subs[0])).unwrap();
+    assert!(qdf.validate().is_ok());
+    qdf.simulation_step::<()>();
+    assert_eq!(qdf.boundary_spaces().len(), 3);
+}
+
+#[test]
+fn test_to_dot() {
+    let (mut qdf, root) = QDF::new(2, 27);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    qdf.increase_space_density(subs[0]).unwrap();
+    let dot = qdf.to_dot();
+    assert!(dot.starts_with("graph QDF {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert_eq!(dot.matches("[label=").count(), qdf.spaces().count());
+    assert_eq!(dot.matches(" -- ").count(), qdf.graph().edge_count());
+    assert!(!dot.contains(&root.key()));
+    for id in qdf.spaces() {
+        assert!(dot.contains(&format!("\"{}\" [label=", id.key())));
+    }
+    let (a, b) = (subs[1].key(), subs[2].key());
+    assert!(
+        dot.contains(&format!("\"{}\" -- \"{}\";", a, b))
+            || dot.contains(&format!("\"{}\" -- \"{}\";", b, a))
+    );
+
+    assert_eq!(
+        escape_dot("a \"quoted\" \\ state\n"),
+        r#"a \"quoted\" \\ state\n"#
+    );
+}
+
+#[test]
+fn test_distance_matrix() {
+    let (mut qdf, _) = QDF::with_levels(2, 81, 2);
+    let first = *qdf.spaces().next().unwrap();
+    let (_, subs, _) = qdf.increase_space_density(first).unwrap();
+    let ids = qdf.spaces().cloned().chain(subs).collect::<Vec<ID>>();
+    let matrix = qdf.distance_matrix(&ids).unwrap();
+    assert_eq!(matrix.len(), ids.len());
+    for (i, a) in ids.iter().enumerate() {
+        assert_eq!(matrix[i].len(), ids.len());
+        for (j, b) in ids.iter().enumerate() {
+            let (_, cost) = qdf.find_path_with_cost(*a, *b).unwrap().unwrap();
+            assert_eq!(matrix[i][j], cost as usize);
+            let within = qdf.neighbors_within(*a, matrix[i][j]).unwrap();
+            assert!(a == b || within.contains(b));
+            if matrix[i][j] > 0 {
+                let within = qdf.neighbors_within(*a, matrix[i][j] - 1).unwrap();
+                assert!(!within.contains(b));
+            }
+            assert_eq!(matrix[i][j], matrix[j][i]);
+        }
+    }
+    assert_eq!(qdf.distance_matrix(&[]).unwrap(), Vec::<Vec<usize>>::new());
+}
+
+#[test]
+fn test_find_path_heuristic() {
+    let (mut qdf, _) = QDF::with_levels(2, 81, 3);
+    let ids = qdf.spaces().cloned().collect::<Vec<ID>>();
+    let (_, subs, _) = qdf.increase_space_density(ids[0]).unwrap();
+    let from = subs[2];
+    for to in &ids[1..] {
+        let distances = qdf.distance_matrix(&[from, *to]).unwrap();
+        let exact = |a: ID, b: ID| qdf.distance_matrix(&[a, b]).unwrap()[0][1] as u32;
+        let path = qdf.find_path_heuristic(from, *to, exact).unwrap().unwrap();
+        assert_eq!(path.len() - 1, distances[0][1]);
+        assert_eq!(path[0], from);
+        assert_eq!(path[path.len() - 1], *to);
+        assert_eq!(path.len(), qdf.find_path(from, *to).unwrap().unwrap().len());
+    }
+    assert!(qdf.find_path_heuristic(ids[0], subs[0], |_, _| 0).is_err());
+}
+
+#[test]
+fn test_validate() {
+    let (mut qdf, root) = QDF::new(2, 81);
+    assert!(qdf.validate().is_ok());
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    assert!(!qdf.graph().contains_node(root));
+    assert!(qdf.validate().is_ok());
+    qdf.decrease_space_density(subs2[0]).unwrap().unwrap();
+    assert!(qdf.validate().is_ok());
+    qdf.remove_space(subs[1]).unwrap();
+    assert!(qdf.validate().is_ok());
+
+    qdf.spaces.index.remove(&subs[2]);
+    qdf.graph.add_node(root);
+    let errors = qdf.validate().unwrap_err();
+    assert_eq!(errors.len(), 5);
+    assert!(errors.iter().any(|e| e.contains("is not listed in space index")));
+    assert!(errors.iter().any(|e| e.contains("has no space")));
+    assert!(errors.iter().any(|e| e.contains("neighbor links")));
+}
+
+#[test]
+fn test_project_2d() {
+    let (mut qdf, _) = QDF::with_levels(2, 81, 2);
+    let anchor = *qdf.spaces().next().unwrap();
+    qdf.increase_space_density(anchor).unwrap();
+    let anchor = *qdf.spaces().next().unwrap();
+    let positions = qdf.project_2d(anchor, [10.0, -5.0]).unwrap();
+    assert_eq!(positions, qdf.project_2d(anchor, [10.0, -5.0]).unwrap());
+    assert_eq!(positions.len(), qdf.spaces().count());
+    assert_eq!(positions[&anchor], [10.0, -5.0]);
+    let distance = |a: &ID, b: &ID| {
+        let (pa, pb) = (positions[a], positions[b]);
+        ((pa[0] - pb[0]).powi(2) + (pa[1] - pb[1]).powi(2)).sqrt()
+    };
+    let (mut near, mut near_count, mut far, mut far_count) = (0.0, 0, 0.0, 0);
+    for a in qdf.spaces() {
+        assert!(positions[a][0].is_finite() && positions[a][1].is_finite());
+        let neighbors = qdf.find_space_neighbors(*a).unwrap();
+        for b in qdf.spaces().filter(|b| *b != a) {
+            if neighbors.contains(b) {
+                near += distance(a, b);
+                near_count += 1;
+            } else {
+                far += distance(a, b);
+                far_count += 1;
+            }
+        }
+    }
+    assert!(near / (near_count as f32) < far / (far_count as f32));
+}
+
+#[test]
+fn test_project() {
+    let (qdf, _) = QDF::with_levels(2, 81, 2);
+    let ids = qdf.graph().nodes().collect::<Vec<ID>>();
+    let distances = qdf.distance_matrix(&ids).unwrap();
+    let stress = |positions: &HashMap<ID, [f32; 2]>| {
+        let mut result = 0.0;
+        for (i, a) in ids.iter().enumerate() {
+            for (j, b) in ids.iter().enumerate().skip(i + 1) {
+                let (pa, pb) = (positions[a], positions[b]);
+                let d = ((pa[0] - pb[0]).powi(2) + (pa[1] - pb[1]).powi(2)).sqrt();
+                let t = distances[i][j] as f32;
+                result += (d - t) * (d - t) / (t * t);
+            }
+        }
+        result
+    };
+    let initial = qdf.project::<2>(0, 7);
+    let projected = qdf.project::<2>(100, 7);
+    assert_eq!(projected, qdf.project::<2>(100, 7));
+    assert_ne!(projected, qdf.project::<2>(100, 8));
+    assert_eq!(projected.len(), ids.len());
+    assert!(stress(&projected) < stress(&initial) * 0.5);
+    assert_eq!(qdf.project::<0>(10, 7).len(), ids.len());
+}
+
+#[test]
+fn test_decrease_space_density_high_dimensions() {
+    for dimensions in 3..5 {
+        let (mut qdf, root) = QDF::new(dimensions, 0);
+        let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+        let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+        assert!(qdf.decrease_space_density(subs[1]).unwrap().is_none());
+        let info = qdf.decrease_space_density(subs2[0]).unwrap().unwrap();
+        assert_eq!(info.merged.len(), dimensions + 1);
+        assert_eq!(qdf.spaces().count(), dimensions + 1);
+        assert!(qdf.validate().is_ok());
+        let info = qdf.decrease_space_density(subs[1]).unwrap().unwrap();
+        assert_eq!(info.merged.len(), dimensions + 1);
+        assert_eq!(qdf.spaces().count(), 1);
+    }
+}
+
+#[test]
+fn test_increase_space_density_into() {
+    for subdivisions in 1..7 {
+        let (mut qdf, root) = QDF::new(3, 1000);
+        let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+        let (_, subs2, pairs) = qdf
+            .increase_space_density_into(subs[0], subdivisions)
+            .unwrap();
+        assert_eq!(subs2.len(), subdivisions);
+        assert_eq!(pairs.len(), 3);
+        assert!(qdf.validate().is_ok());
+        assert_eq!(qdf.total_state(), 1000);
+        for (i, (outer, inner)) in pairs.iter().enumerate() {
+            assert_eq!(*inner, subs2[i % subdivisions]);
+            let inside = qdf
+                .find_space_neighbors(*outer)
+                .unwrap()
+                .into_iter()
+                .filter(|n| subs2.contains(n))
+                .collect::<Vec<ID>>();
+            assert_eq!(inside, vec![*inner]);
+        }
+        for id in &subs2 {
+            let neighbors = qdf.find_space_neighbors(*id).unwrap();
+            assert_eq!(
+                neighbors.iter().filter(|n| subs2.contains(n)).count(),
+                subdivisions - 1
+            );
+        }
+    }
+    let (mut qdf, root) = QDF::new(2, 9);
+    match qdf.increase_space_density_into(root, 0) {
+        Err(QDFError::InvalidSubdivisionCount(0)) => {}
+        _ => panic!("space must not be split into 0 subspaces"),
+    }
+    assert_eq!(qdf.spaces().collect::<Vec<_>>(), vec![&root]);
+    match qdf.increase_space_density_into(ID::new(), 0) {
+        Err(QDFError::SpaceDoesNotExists(_)) => {}
+        _ => panic!("missing space must be reported first"),
+    }
+}
+
+#[test]
+fn test_subdivide_to_depth() {
+    for dimensions in 0..4 {
+        let (mut qdf, root) = QDF::new(dimensions, 0);
+        let leaves = qdf.subdivide_to_depth(root, 3).unwrap();
+        assert_eq!(leaves.len(), (dimensions + 1).pow(3));
+        assert_eq!(qdf.spaces().count(), leaves.len());
+        assert!(leaves.iter().all(|id| qdf.space_exists(*id)));
+        assert!(qdf.validate().is_ok());
+        assert_eq!(qdf.connected_components().len(), 1);
+    }
+    let (mut qdf, root) = QDF::new(2, 81);
+    for depth in &[41, 64, usize::MAX] {
+        match qdf.subdivide_to_depth(root, *depth) {
+            Err(QDFError::DepthOutOfRange(requested, 40)) => assert_eq!(requested, *depth),
+            _ => panic!("leaves count of depth {} must not fit in usize", depth),
+        }
+    }
+    assert_eq!(qdf.spaces().collect::<Vec<_>>(), vec![&root]);
+    qdf.subdivide_to_depth(root, 1).unwrap();
+    assert!(qdf.subdivide_to_depth(root, 1).is_err());
+}
+
+#[test]
+fn test_with_target_count() {
+    for target in 0..20 {
+        let (qdf, spaces) = QDF::with_target_count(3, 4096, target);
+        assert!(spaces.len() >= target);
+        assert!(spaces.len() < target.max(1) + 3);
+        assert_eq!(qdf.iter_spaces().map(|(_, s)| *s.state()).sum::<i32>(), 4096);
+        assert!(qdf.validate().is_ok());
+    }
+    let (_, spaces) = QDF::with_target_count(0, 1, 10);
+    assert_eq!(spaces.len(), 1);
+}
+
+#[test]
+fn test_extract_region() {
+    let (mut qdf, _) = QDF::<i32, u8>::new_with_edge_data(2, 729);
+    for _ in 0..3 {
+        let spaces = qdf.spaces().cloned().collect::<Vec<ID>>();
+        for id in spaces {
+            qdf.increase_space_density(id).unwrap();
+        }
+    }
+    let seed = *qdf.spaces().next().unwrap();
+    let neighbor = qdf.find_space_neighbors(seed).unwrap()[0];
+    qdf.set_edge_data(seed, neighbor, 7).unwrap();
+    let (region, ids) = qdf.extract_region(seed, 2).unwrap();
+    assert!(region.validate().is_ok());
+    assert_ne!(region.id(), qdf.id());
+    assert_eq!(region.dimensions(), 2);
+    assert_eq!(ids.len(), qdf.neighbors_within(seed, 2).unwrap().len() + 1);
+    assert_eq!(region.spaces().count(), ids.len());
+    assert_eq!(region.edge_data(ids[&seed], ids[&neighbor]), Some(&7));
+    for (old, new) in &ids {
+        assert!(!qdf.space_exists(*new));
+        assert_eq!(region.space(*new).state(), qdf.space(*old).state());
+        let expected = qdf
+            .find_space_neighbors(*old)
+            .unwrap()
+            .into_iter()
+            .filter_map(|n| ids.get(&n).cloned())
+            .collect::<Vec<ID>>();
+        assert_eq!(region.find_space_neighbors(*new).unwrap(), expected);
+    }
+    assert!(qdf.extract_region(ID::new(), 1).is_err());
+}
+
+#[test]
+fn test_observer() {
+    use std::sync::{Arc, Mutex};
+
+    let (mut qdf, root) = QDF::new(2, 729);
+    let mirror = Arc::new(Mutex::new((HashSet::new(), HashSet::new())));
+    mirror.lock().unwrap().0.insert(root);
+    let events = mirror.clone();
+    let key = |a: ID, b: ID| if a < b { (a, b) } else { (b, a) };
+    assert!(qdf
+        .set_observer(Box::new(move |event| {
+            let (spaces, edges) = &mut *events.lock().unwrap();
+            match event {
+                QdfEvent::SpaceCreated(id) => assert!(spaces.insert(id)),
+                QdfEvent::SpaceRemoved(id) => assert!(spaces.remove(&id)),
+                QdfEvent::EdgeAdded(a, b) => assert!(edges.insert(key(a, b))),
+                QdfEvent::EdgeRemoved(a, b) => assert!(edges.remove(&key(a, b))),
+            }
+        })).is_none());
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    let (_, subs2, _) = qdf.increase_space_density(subs[1]).unwrap();
+    let (_, subs3, _) = qdf.increase_space_density(subs2[0]).unwrap();
+    qdf.decrease_space_density(subs3[2]).unwrap().unwrap();
+    qdf.remove_space(subs2[2]).unwrap();
+    let (mut other, seed) = QDF::new(2, 9);
+    let (_, subs4, _) = other.increase_space_density(seed).unwrap();
+    qdf.absorb(other, (subs[2], subs4[0])).unwrap();
+    {
+        let (spaces, edges) = &*mirror.lock().unwrap();
+        assert!(spaces.contains(&subs4[1]));
+        assert!(!spaces.contains(&subs2[2]));
+        assert_eq!(*spaces, qdf.spaces().cloned().collect::<HashSet<ID>>());
+        let expected = qdf
+            .graph()
+            .all_edges()
+            .map(|(a, b, _)| key(a, b))
+            .collect::<HashSet<(ID, ID)>>();
+        assert_eq!(*edges, expected);
+    }
+    assert!(qdf.clear_observer().is_some());
+    qdf.increase_space_density(subs[0]).unwrap();
+    assert_ne!(mirror.lock().unwrap().0.len(), qdf.spaces().count());
+}
+
+#[test]
+fn test_bytes() {
+    let (mut qdf, root) = QDF::new(2, 81);
+    qdf.subdivide_to_depth(root, 3).unwrap();
+    let id = *qdf.spaces().next().unwrap();
+    qdf.decrease_space_density(id).unwrap();
+
+    let bytes = qdf.to_bytes();
+    let qdf2 = QDF::<i32>::from_bytes(&bytes).unwrap();
+    assert_eq!(qdf2.id(), qdf.id());
+    assert_eq!(qdf2.dimensions(), qdf.dimensions());
+    assert_eq!(qdf2.spaces().count(), qdf.spaces().count());
+    for id in qdf.spaces() {
+        assert_eq!(qdf2.space(*id).state(), qdf.space(*id).state());
+        assert_eq!(
+            qdf2.find_space_neighbors(*id).unwrap(),
+            qdf.find_space_neighbors(*id).unwrap()
+        );
+    }
+    assert_eq!(qdf2.to_bytes(), bytes);
+    assert!(qdf2.structurally_eq(&qdf));
+    match QDF::<i32>::from_bytes(&bytes[..bytes.len() - 1]) {
+        Err(QDFError::DecodeError(_)) => {}
+        _ => panic!("truncated snapshot must not decode"),
+    }
+    match QDF::<i64>::from_bytes(&bytes) {
+        Err(QDFError::DecodeError(_)) => {}
+        _ => panic!("snapshot must not decode with different state type"),
+    }
+}
+
+#[test]
+fn test_json() {
+    use std::io::{Cursor, ErrorKind};
+
+    let (mut qdf, root) = QDF::new(2, 81);
+    qdf.subdivide_to_depth(root, 3).unwrap();
+    let id = *qdf.spaces().next().unwrap();
+    qdf.decrease_space_density(id).unwrap();
+
+    let mut cursor = Cursor::new(vec![]);
+    qdf.write_json(&mut cursor).unwrap();
+    cursor.set_position(0);
+    let qdf2 = QDF::<i32>::read_json(&mut cursor).unwrap();
+    assert_eq!(qdf2.id(), qdf.id());
+    assert!(qdf2.structurally_eq(&qdf));
+    for id in qdf.spaces() {
+        let neighbors = qdf.find_space_neighbors(*id).unwrap();
+        let neighbors2 = qdf2.find_space_neighbors(*id).unwrap();
+        assert_eq!(
+            neighbors.into_iter().collect::<HashSet<_>>(),
+            neighbors2.into_iter().collect::<HashSet<_>>()
+        );
+    }
+    let json = cursor.into_inner();
+    let mut json2 = vec![];
+    qdf2.write_json(&mut json2).unwrap();
+    assert_eq!(json2.len(), json.len());
+
+    let error = QDF::<i32>::read_json(&mut &json[..json.len() - 3]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    let error = QDF::<i64>::read_json(&mut &json[..]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    let text = String::from_utf8(json).unwrap();
+    let (a, _) = qdf.edges()[0];
+    let split = text.find("\"edges\"").unwrap();
+    let unknown = format!(
+        "{}{}",
+        &text[..split],
+        text[split..].replace(&a.to_string(), &ID::new().to_string())
+    );
+    let error = QDF::<i32>::read_json(&mut unknown.as_bytes()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    let extra = format!("{}{{}}", text);
+    let error = QDF::<i32>::read_json(&mut extra.as_bytes()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_bytes_malformed() {
+    use bytes::{write_id, write_u64, write_usize};
+
+    fn snapshot(nodes: &[ID], edges: &[(usize, usize)], spaces: &[usize], count: u64) -> Vec<u8> {
+        let mut buf = vec![];
+        write_id(&mut buf, ID::new());
+        write_usize(&mut buf, 2);
+        write_usize(&mut buf, nodes.len());
+        for id in nodes {
+            write_id(&mut buf, *id);
+        }
+        write_usize(&mut buf, edges.len());
+        for (a, b) in edges {
+            write_usize(&mut buf, *a);
+            write_usize(&mut buf, *b);
+        }
+        write_u64(&mut buf, count);
+        let mut state = vec![];
+        3.encode(&mut state);
+        for index in spaces {
+            write_usize(&mut buf, *index);
+            write_usize(&mut buf, state.len());
+            buf.extend_from_slice(&state);
+        }
+        buf
+    }
+    fn rejected(data: &[u8], reason: &str) {
+        match QDF::<i32>::from_bytes(data) {
+            Err(QDFError::DecodeError(message)) => assert!(
+                message.contains(reason),
+                "`{}` does not mention `{}`",
+                message,
+                reason
+            ),
+            _ => panic!("malformed snapshot must not decode ({})", reason),
+        }
+    }
+
+    let (a, b) = (ID::new(), ID::new());
+    let valid = snapshot(&[a, b], &[(0, 1)], &[0, 1], 2);
+    let qdf = QDF::<i32>::from_bytes(&valid).unwrap();
+    assert!(qdf.validate().is_ok());
+    assert_eq!(qdf.total_state(), 6);
+    rejected(&snapshot(&[a, b], &[(0, 1)], &[0, 1], u64::MAX), "2 nodes");
+    rejected(&snapshot(&[a, b], &[(0, 1)], &[], 0), "0 spaces for 2 nodes");
+    rejected(&snapshot(&[a, b], &[(0, 1)], &[0], 1), "1 spaces for 2 nodes");
+    rejected(&snapshot(&[a, b], &[(0, 1)], &[0, 0], 2), "is duplicated");
+    rejected(&snapshot(&[a, b], &[(0, 1), (1, 1)], &[0, 1], 2), "with itself");
+    rejected(&snapshot(&[a, a], &[], &[0, 1], 2), "is duplicated");
+    rejected(&snapshot(&[a, b], &[(0, 2)], &[0, 1], 2), "out of bounds");
+    rejected(&snapshot(&[a], &[], &[], 1), "unexpected end of data");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_malformed() {
+    use serde_json::Value;
+
+    let (mut qdf, root) = QDF::new(2, 9);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    qdf.create_field(vec![subs[0]]).unwrap();
+    qdf.set_frozen(subs[1], true).unwrap();
+    let valid = serde_json::to_value(&qdf).unwrap();
+    assert!(serde_json::from_value::<QDF<i32>>(valid.clone()).unwrap() == qdf);
+    let unknown = serde_json::to_value(ID::new()).unwrap();
+    let node = valid["nodes"][0].clone();
+    let rejected = |change: &dyn Fn(&mut Value), reason: &str| {
+        let mut data = valid.clone();
+        change(&mut data);
+        match serde_json::from_value::<QDF<i32>>(data) {
+            Err(error) => assert!(
+                error.to_string().contains(reason),
+                "`{}` does not mention `{}`",
+                error,
+                reason
+            ),
+            Ok(_) => panic!("malformed QDF must not deserialize ({})", reason),
+        }
+    };
+
+    rejected(
+        &|data| data["nodes"].as_array_mut().unwrap().push(unknown.clone()),
+        "3 spaces for 4 nodes",
+    );
+    rejected(
+        &|data| data["nodes"].as_array_mut().unwrap().push(node.clone()),
+        "is duplicated",
+    );
+    rejected(
+        &|data| {
+            let space = data["spaces"][0].clone();
+            data["spaces"].as_array_mut().unwrap()[1] = space;
+        },
+        "is duplicated",
+    );
+    rejected(
+        &|data| {
+            let space = data["spaces"][0].clone();
+            data["spaces"].as_array_mut().unwrap().push(space);
+        },
+        "4 spaces for 3 nodes",
+    );
+    rejected(
+        &|data| {
+            let edge = Value::Array(vec![node.clone(), node.clone(), Value::Null]);
+            data["edges"].as_array_mut().unwrap().push(edge);
+        },
+        "with itself",
+    );
+    rejected(
+        &|data| data["fields"][0][1].as_array_mut().unwrap().push(unknown.clone()),
+        "unknown space",
+    );
+    rejected(
+        &|data| data["frozen"].as_array_mut().unwrap().push(unknown.clone()),
+        "is unknown",
+    );
+}
+
+#[test]
+fn test_from_parts() {
+    let (mut qdf, root) = QDF::new(2, 27);
+    qdf.subdivide_to_depth(root, 2).unwrap();
+    let nodes = qdf
+        .spaces()
+        .map(|id| (*id, *qdf.space(*id).state()))
+        .collect::<Vec<_>>();
+    let edges = qdf.graph().all_edges().map(|(a, b, _)| (a, b)).collect::<Vec<_>>();
+    let built = QDF::<i32>::from_parts(2, nodes.clone(), edges.clone()).unwrap();
+    assert_eq!(built.space_count(), nodes.len());
+    assert!(built.validate().is_ok());
+    assert!(built == qdf);
+    assert_ne!(built.id(), qdf.id());
+
+    let missing = ID::new();
+    let mut bad = edges.clone();
+    bad.insert(1, (nodes[0].0, missing));
+    bad.push((missing, nodes[1].0));
+    match QDF::<i32>::from_parts(2, nodes.clone(), bad) {
+        Err(QDFError::InvalidEdge(a, b)) => assert_eq!((a, b), (nodes[0].0, missing)),
+        _ => panic!("edge to unknown space must be rejected"),
+    }
+    match QDF::<i32>::from_parts(2, nodes.clone(), vec![(nodes[0].0, nodes[0].0)]) {
+        Err(QDFError::InvalidEdge(..)) => {}
+        _ => panic!("loop edge must be rejected"),
+    }
+    let mut twice = nodes.clone();
+    twice.push(nodes[2]);
+    match QDF::<i32>::from_parts(2, twice, edges) {
+        Err(QDFError::SpaceAlreadyExists(id)) => assert_eq!(id, nodes[2].0),
+        _ => panic!("duplicated space must be rejected"),
+    }
+}
+
+#[test]
+fn test_structurally_eq() {
+    let (mut qdf, root) = QDF::new(2, 27);
+    let subs = qdf.subdivide_to_depth(root, 2).unwrap();
+    let copy = QDF::from_bytes(&qdf.to_bytes()).unwrap();
+    assert!(qdf.structurally_eq(&copy));
+    assert!(copy.structurally_eq(&qdf));
+
+    qdf.set_space_state(subs[0], 2).unwrap();
+    assert!(!qdf.structurally_eq(&copy));
+    qdf.set_space_state(subs[0], 3).unwrap();
+    assert!(qdf == copy);
+
+    qdf.remove_space(subs[0]).unwrap();
+    assert!(qdf != copy);
+    let (region, _) = copy.extract_region(subs[1], 100).unwrap();
+    assert_eq!(region.spaces().count(), copy.spaces().count());
+    assert!(!region.structurally_eq(&copy));
+    let (other, _) = QDF::new(3, 27);
+    let (same, _) = QDF::new(3, 27);
+    assert!(other != same);
+}
+
+#[cfg(feature = "compact-id")]
+#[test]
+fn test_compact_id() {
+    use std::str::FromStr;
+
+    assert_eq!(::std::mem::size_of::<ID>(), 8);
+    let far = ID::from_str(&format!("ID({})", ID::new().value() + 1000)).unwrap();
+    let next = ID::new();
+    assert!(next > far);
+    assert!(IdGenerator::new(42).generate() > next);
+    let (qdf, root) = QDF::new(2, 9);
+    let qdf2 = QDF::<i32>::from_bytes(&qdf.to_bytes()).unwrap();
+    assert_eq!(*qdf2.space(root).state(), 9);
+    assert!(ID::new() > root);
+}
+
+#[test]
+fn test_id_generator() {
+    let build = |seed| {
+        let mut gen = IdGenerator::new(seed);
+        let (mut qdf, root) = QDF::new_with_generator(2, 27, &mut gen);
+        let (_, subs, _) = qdf.increase_space_density_with(root, &mut gen).unwrap();
+        let (_, subs2, _) = qdf.increase_space_density_with(subs[0], &mut gen).unwrap();
+        qdf.increase_space_density_with(subs[1], &mut gen).unwrap();
+        qdf.decrease_space_density_with(subs2[0], &mut gen)
+            .unwrap()
+            .unwrap();
+        qdf
+    };
+    let a = build(42);
+    let b = build(42);
+    assert_eq!(a.id(), b.id());
+    assert_eq!(a.to_bytes(), b.to_bytes());
+    #[cfg(feature = "serde")]
+    assert_eq!(
+        ::serde_json::to_string(&a).unwrap(),
+        ::serde_json::to_string(&b).unwrap()
+    );
+    assert_ne!(a.to_bytes(), build(7).to_bytes());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    let (mut qdf, root) = QDF::new(2, 81);
+    qdf.subdivide_to_depth(root, 3).unwrap();
+    let members = qdf.spaces().cloned().take(5).collect::<Vec<ID>>();
+    qdf.create_field(members).unwrap();
+    let id = *qdf.spaces().next().unwrap();
+    qdf.subdivide_to_depth(id, 2).unwrap();
+    let id = *qdf.spaces().next().unwrap();
+    qdf.decrease_space_density(id).unwrap();
+    let id = *qdf.spaces().next().unwrap();
+    qdf.increase_space_density(id).unwrap();
+
+    let json = ::serde_json::to_string(&qdf).unwrap();
+    let qdf2: QDF<i32> = ::serde_json::from_str(&json).unwrap();
+    assert_eq!(qdf2.id(), qdf.id());
+    assert_eq!(qdf2.dimensions(), qdf.dimensions());
+    assert_eq!(qdf2.spaces().count(), qdf.spaces().count());
+    for id in qdf.spaces() {
+        assert_eq!(qdf2.space(*id).state(), qdf.space(*id).state());
+        assert_eq!(
+            qdf2.find_space_neighbors(*id).unwrap(),
+            qdf.find_space_neighbors(*id).unwrap()
+        );
+        assert_eq!(qdf2.ancestors(*id), qdf.ancestors(*id));
+    }
+    for id in qdf.fields() {
+        assert_eq!(qdf2.get_field(*id).unwrap(), qdf.get_field(*id).unwrap());
+    }
+    assert!(qdf2 == qdf);
+    assert_eq!(::serde_json::to_string(&qdf2).unwrap(), json);
+}
+
 // #[bench]
 // fn bench_simulation_step_level_5_2d(b: &mut Bencher) {
 //     let mut qdf = QDF::new(2, 243);
 //     let root = qdf.root();
-//     increase_space_density(&mut qdf, root, 5).unwrap();
+//     qdf.subdivide_to_depth(root, 5).unwrap();
 //     b.iter(|| qdf.simulation_step::<()>());
 // }
 //
@@ -123,7 +1903,7 @@ fn test_2d() {
 // fn bench_simulation_step_level_10_2d(b: &mut Bencher) {
 //     let mut qdf = QDF::new(2, 59049);
 //     let root = qdf.root();
-//     increase_space_density(&mut qdf, root, 10).unwrap();
+//     qdf.subdivide_to_depth(root, 10).unwrap();
 //     b.iter(|| qdf.simulation_step::<()>());
 // }
 //
@@ -131,7 +1911,7 @@ fn test_2d() {
 // fn bench_simulation_step_parallel_level_5_2d(b: &mut Bencher) {
 //     let mut qdf = QDF::new(2, 243);
 //     let root = qdf.root();
-//     increase_space_density(&mut qdf, root, 5).unwrap();
+//     qdf.subdivide_to_depth(root, 5).unwrap();
 //     b.iter(|| qdf.simulation_step_parallel::<()>());
 // }
 //
@@ -139,15 +1919,67 @@ fn test_2d() {
 // fn bench_simulation_step_parallel_level_10_2d(b: &mut Bencher) {
 //     let mut qdf = QDF::new(2, 59049);
 //     let root = qdf.root();
-//     increase_space_density(&mut qdf, root, 10).unwrap();
+//     qdf.subdivide_to_depth(root, 10).unwrap();
 //     b.iter(|| qdf.simulation_step_parallel::<()>());
 // }
 
-fn increase_space_density(qdf: &mut QDF<i32>, id: ID, depth: usize) -> Result<()> {
-    if depth > 0 {
-        for id in qdf.increase_space_density(id)?.1 {
-            increase_space_density(qdf, id, depth - 1)?;
+#[test]
+fn test_directed() {
+    let (mut qdf, root) = QDFDirected::new(2, 27);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    assert!(!qdf.graph().contains_node(root));
+    qdf.disconnect_spaces(subs[1], subs[0]).unwrap();
+    qdf.disconnect_spaces(subs[2], subs[0]).unwrap();
+    let (_, subs2, pairs) = qdf.increase_space_density(subs[0]).unwrap();
+    assert_eq!(qdf.spaces().count(), 5);
+    for (outer, inner) in pairs {
+        assert!(qdf.graph().contains_edge(inner, outer));
+        assert!(!qdf.graph().contains_edge(outer, inner));
+        assert!(qdf.find_space_predecessors(inner).unwrap().len() == 2);
+    }
+    for a in &subs2 {
+        for b in &subs2 {
+            assert_eq!(a != b, qdf.graph().contains_edge(*a, *b));
+        }
+    }
+    let (merged, id) = qdf.decrease_space_density(subs2[2]).unwrap().unwrap();
+    assert_eq!(merged.len(), 3);
+    assert_eq!(*qdf.space(id).state(), 9);
+    assert_eq!(qdf.graph().node_count(), 3);
+    let mut neighbors = qdf.find_space_neighbors(id).unwrap();
+    neighbors.sort();
+    let mut expected = vec![subs[1], subs[2]];
+    expected.sort();
+    assert_eq!(neighbors, expected);
+    assert!(qdf.find_space_predecessors(id).unwrap().is_empty());
+    assert!(qdf.find_space_predecessors(root).is_err());
+}
+
+#[test]
+fn test_directed_high_degree() {
+    let (mut qdf, root) = QDFDirected::new(2, 81);
+    let (_, subs, _) = qdf.increase_space_density(root).unwrap();
+    let (_, subs2, _) = qdf.increase_space_density(subs[0]).unwrap();
+    for id in &subs2 {
+        qdf.connect_spaces(subs[1], *id).unwrap();
+    }
+    let mut outer = qdf.find_space_neighbors(subs[1]).unwrap();
+    for id in qdf.find_space_predecessors(subs[1]).unwrap() {
+        if !outer.contains(&id) {
+            outer.push(id);
         }
     }
-    Ok(())
+    assert_eq!(outer.len(), 4);
+    assert!(!outer.contains(&subs[0]));
+    let (_, subs3, pairs) = qdf.increase_space_density(subs[1]).unwrap();
+    assert_eq!(qdf.spaces().count(), 7);
+    assert_eq!(pairs.len(), 4);
+    for n in outer {
+        assert!(pairs.iter().any(|(o, t)| *o == n && subs3.contains(t)));
+        assert!(subs3.iter().any(|t| qdf.graph().contains_edge(*t, n)));
+    }
+    assert!(!qdf.graph().contains_node(subs[1]));
+    assert_eq!(qdf.graph().edge_count(), 20);
+    let total = qdf.spaces().map(|id| *qdf.space(*id).state()).sum::<i32>();
+    assert_eq!(total, 81);
 }
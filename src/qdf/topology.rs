@@ -0,0 +1,74 @@
+/// Tells how subspaces created by space subdivision are connected with each other.
+///
+/// Only `Complete` topology conserves invariants that the rest of QDF relies on: every space of
+/// uniformly subdivided universe has `dimensions` or `dimensions + 1` neighbors (checked by
+/// `QDF::simulation_step_strict()`), and subspaces form clique that `QDF::decrease_space_density()`
+/// recognizes and merges back. With other topologies subdivisions of `dimensions + 1` subspaces
+/// give spaces with different number of neighbors and `decrease_space_density()` usually refuses
+/// to merge them (returns `None`), so they are meant for universes that only grow.
+/// In 2D `Ring` gives the same connections as `Complete` (3 subspaces).
+///
+/// Outer neighbors of subdivided space are handed out to subspaces the same way for every
+/// topology, so universe stays connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubdivisionTopology {
+    /// Every subspace is connected with every other subspace (simplex).
+    Complete,
+    /// Every subspace is connected with the previous and the next one, and the last one is
+    /// connected with the first one.
+    Ring,
+    /// First subspace is connected with every other subspace, which are not connected with each
+    /// other.
+    Star,
+}
+
+impl SubdivisionTopology {
+    /// Gets pairs of indices of connected subspaces (lower index first), each pair listed once.
+    ///
+    /// # Arguments
+    /// * `count` - number of subspaces.
+    ///
+    /// # Examples
+    /// ```
+    /// use quantized_density_fields::SubdivisionTopology;
+    ///
+    /// assert_eq!(
+    ///     SubdivisionTopology::Complete.connections(3),
+    ///     vec![(0, 1), (0, 2), (1, 2)],
+    /// );
+    /// assert_eq!(
+    ///     SubdivisionTopology::Ring.connections(4),
+    ///     vec![(0, 1), (1, 2), (2, 3), (0, 3)],
+    /// );
+    /// assert_eq!(SubdivisionTopology::Star.connections(3), vec![(0, 1), (0, 2)]);
+    /// assert_eq!(SubdivisionTopology::Ring.connections(2), vec![(0, 1)]);
+    /// ```
+    pub fn connections(self, count: usize) -> Vec<(usize, usize)> {
+        match self {
+            SubdivisionTopology::Complete => (0..count)
+                .flat_map(|i| (i + 1..count).map(move |j| (i, j)))
+                .collect(),
+            SubdivisionTopology::Ring => match count {
+                0 | 1 => vec![],
+                2 => vec![(0, 1)],
+                _ => (0..count)
+                    .map(|i| {
+                        let j = (i + 1) % count;
+                        if i < j {
+                            (i, j)
+                        } else {
+                            (j, i)
+                        }
+                    }).collect(),
+            },
+            SubdivisionTopology::Star => (1..count).map(|i| (0, i)).collect(),
+        }
+    }
+}
+
+impl Default for SubdivisionTopology {
+    #[inline]
+    fn default() -> Self {
+        SubdivisionTopology::Complete
+    }
+}